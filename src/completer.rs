@@ -0,0 +1,91 @@
+use crate::{core::session::Session, shell::help};
+use rustyline::{
+    Context, Helper,
+    completion::{Completer, Pair},
+    highlight::Highlighter,
+    hint::Hinter,
+    validate::Validator,
+};
+
+/// コマンド名とタスクIDの前方一致で Tab 補完する
+#[derive(Default)]
+pub struct ShellCompleter {
+    /// (タスクIDの16進表記, タイトル)
+    tasks: Vec<(String, String)>,
+}
+
+impl ShellCompleter {
+    /// 補完候補のタスク一覧をセッションの現在の状態で更新する
+    pub fn update(&mut self, session: &Session) {
+        self.tasks = session.iter_tasks().filter(|t| !t.is_dropped()).map(|t| (t.id.hex(), t.title.clone())).collect();
+    }
+
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let head = &line[..pos];
+        let start = head.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        let word = &head[start..];
+        // @timestamp を除いた先頭の語ならコマンド名、それ以降はタスクID
+        let preceding = head[..start].split_whitespace().filter(|w| !w.starts_with('@')).count();
+        if preceding == 0 {
+            let pairs = help::COMMANDS
+                .iter()
+                .filter(|command| command.name.starts_with(word))
+                .map(|command| Pair {
+                    display: command.name.to_string(),
+                    replacement: format!("{} ", command.name),
+                })
+                .collect();
+            return (start, pairs);
+        }
+        let prefix = word.trim_start_matches('#');
+        if prefix.is_empty() {
+            return (start, vec![]);
+        }
+        let pairs = self
+            .tasks
+            .iter()
+            .filter(|(hex, _)| hex.starts_with(prefix))
+            .map(|(hex, title)| Pair {
+                display: format!("{} {}", &hex[..6], title),
+                replacement: format!("{} ", &hex[..6]),
+            })
+            .collect();
+        (start, pairs)
+    }
+}
+
+impl Completer for ShellCompleter {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+impl Hinter for ShellCompleter {
+    type Hint = String;
+}
+impl Highlighter for ShellCompleter {}
+impl Validator for ShellCompleter {}
+impl Helper for ShellCompleter {}
+
+#[test]
+fn test_candidates() {
+    let completer = ShellCompleter {
+        tasks: vec![("abc123ff".to_string(), "Write report".to_string()), ("abd999ff".to_string(), "Review PR".to_string())],
+    };
+    let (start, pairs) = completer.candidates("li", 2);
+    assert_eq!(start, 0);
+    assert!(pairs.iter().any(|p| p.replacement == "list "));
+
+    let (start, pairs) = completer.candidates("@2025-05-07T10:00:00 sta", 24);
+    assert_eq!(start, 21);
+    assert!(pairs.iter().any(|p| p.replacement == "start "));
+
+    let (start, pairs) = completer.candidates("start ab", 8);
+    assert_eq!(start, 6);
+    assert_eq!(pairs.len(), 2);
+    let (_, pairs) = completer.candidates("start #abc", 10);
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].replacement, "abc123 ");
+    assert_eq!(pairs[0].display, "abc123 Write report");
+}
@@ -1,6 +1,10 @@
+pub mod burndown;
 pub mod calendar;
+pub mod config;
 pub mod deadline;
 pub mod estimate;
+pub mod ics;
+pub mod plan;
 pub mod schedule;
 pub mod session;
 pub mod slot;
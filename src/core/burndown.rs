@@ -0,0 +1,81 @@
+use super::task::Task;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 全タスクの残り時間の合計を記録したスナップショット (burndown.jsonl の 1 行)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BurndownSnapshot {
+    pub at: NaiveDateTime,
+    pub remaining_minutes: i64,
+}
+
+impl BurndownSnapshot {
+    pub fn take<'a>(at: NaiveDateTime, tasks: impl Iterator<Item = &'a Task>) -> Self {
+        let remaining: Duration = tasks.map(|task| task.remaining()).sum();
+        Self {
+            at,
+            remaining_minutes: remaining.num_minutes(),
+        }
+    }
+    pub fn remaining(&self) -> Duration {
+        Duration::minutes(self.remaining_minutes)
+    }
+}
+
+/// 日ごとの残り時間 (その日の最後のスナップショット) を返す
+pub fn daily_remaining(snapshots: &[BurndownSnapshot]) -> BTreeMap<NaiveDate, Duration> {
+    let mut daily = BTreeMap::new();
+    for snapshot in snapshots {
+        let entry = daily.entry(snapshot.at.date()).or_insert((snapshot.at, snapshot.remaining()));
+        if snapshot.at >= entry.0 {
+            *entry = (snapshot.at, snapshot.remaining());
+        }
+    }
+    daily.into_iter().map(|(date, (_, remaining))| (date, remaining)).collect()
+}
+
+/// 値の推移を ▁▂▃▄▅▆▇█ のスパークラインで表す
+pub fn sparkline(values: &[Duration]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    let range = (*max - *min).num_minutes();
+    values
+        .iter()
+        .map(|value| {
+            if range == 0 {
+                return BARS[BARS.len() / 2];
+            }
+            let level = (*value - *min).num_minutes() * (BARS.len() as i64 - 1) / range;
+            BARS[level as usize]
+        })
+        .collect()
+}
+
+#[test]
+fn test_daily_remaining_and_sparkline() {
+    let at = |d: u32, h: u32| NaiveDate::from_ymd_opt(2025, 5, d).unwrap().and_hms_opt(h, 0, 0).unwrap();
+    let snapshots = vec![
+        BurndownSnapshot {
+            at: at(1, 18),
+            remaining_minutes: 600,
+        },
+        BurndownSnapshot {
+            at: at(1, 10),
+            remaining_minutes: 700,
+        },
+        BurndownSnapshot {
+            at: at(2, 18),
+            remaining_minutes: 300,
+        },
+        BurndownSnapshot { at: at(3, 18), remaining_minutes: 0 },
+    ];
+    let daily = daily_remaining(&snapshots);
+    let values: Vec<_> = daily.values().copied().collect();
+    assert_eq!(values, vec![Duration::minutes(600), Duration::minutes(300), Duration::zero()]);
+    assert_eq!(sparkline(&values), "█▄▁");
+    assert_eq!(sparkline(&[Duration::hours(1), Duration::hours(1)]), "▅▅");
+    assert_eq!(sparkline(&[]), "");
+}
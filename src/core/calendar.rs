@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
-use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
+
+use super::utils::DurationUnits;
 use std::{
     collections::{BTreeMap, BTreeSet},
     fs,
@@ -14,7 +16,7 @@ pub struct ScheduleItem {
     pub note: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CalendarDay {
     pub work_start_time: Option<NaiveTime>,
     pub work_end_time: Option<NaiveTime>,
@@ -26,9 +28,29 @@ impl CalendarDay {
         work_end_time: None,
         scheduled_items: BTreeSet::new(),
     };
+    /// 予定を開始時刻順に並べ、重なっている予定を 1 つの区間にまとめたもの (メモは " / " でつなぐ)。
+    /// 予定そのものは別々に残したまま、空き時間の計算にはこちらを使う
+    pub fn busy_spans(&self) -> Vec<ScheduleItem> {
+        let mut spans: Vec<ScheduleItem> = Vec::new();
+        // scheduled_items は開始時刻順に並んでいる
+        for item in &self.scheduled_items {
+            match spans.last_mut() {
+                Some(last) if item.start < last.start + last.duration => {
+                    let end = (last.start + last.duration).max(item.start + item.duration);
+                    last.duration = end - last.start;
+                    last.note = match (last.note.take(), &item.note) {
+                        (Some(a), Some(b)) if !a.split(" / ").any(|note| note == b) => Some(format!("{} / {}", a, b)),
+                        (a, b) => a.or_else(|| b.clone()),
+                    };
+                }
+                _ => spans.push(item.clone()),
+            }
+        }
+        spans
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Calendar {
     official_days: BTreeSet<NaiveDate>,
     working_time: (NaiveTime, NaiveTime),
@@ -61,21 +83,21 @@ impl Calendar {
         }
         self.calendar_days.remove(&date);
     }
+    /// 予定を追加する。稼働日でないか、同じ予定がすでにあれば false
     pub fn add_scheduled_item(&mut self, date: &NaiveDate, item: ScheduleItem) -> bool {
         let Some(day) = self.calendar_days.get_mut(date) else {
             return false;
         };
-        day.scheduled_items.insert(item);
-        true
+        day.scheduled_items.insert(item)
     }
     pub fn update_working_time(&mut self, date: NaiveDate, start: Option<NaiveTime>, end: Option<NaiveTime>) {
         let Some(day) = self.calendar_days.get_mut(&date) else {
             return;
         };
-        if let (Some(start), Some(end)) = &(start, end) {
-            if start >= end {
-                return;
-            }
+        if let (Some(start), Some(end)) = &(start, end)
+            && start >= end
+        {
+            return;
         }
         day.work_start_time = start;
         day.work_end_time = end;
@@ -86,9 +108,40 @@ impl Calendar {
         let end_time = day.work_end_time.unwrap_or(self.working_time.1);
         Some((start_time, end_time))
     }
+    /// 既定の勤務時間から求めた、期間表示の 1 日・1 週間の長さ
+    pub fn duration_units(&self) -> DurationUnits {
+        DurationUnits::from_working_time(self.working_time)
+    }
     pub fn calendar_days(&self, start_date: &NaiveDate) -> impl Iterator<Item = (&NaiveDate, &CalendarDay)> {
         self.calendar_days.iter().skip_while(|(date, _)| *date < start_date)
     }
+    /// 別のカレンダーを重ねる。予定は両方のものを残し、稼働日は `workdays` に従って決める。
+    /// 勤務時間 (既定・日ごと) は自分の設定を優先し、自分が決めていない日だけ `other` の日ごとの設定を使う
+    pub fn merge(&mut self, other: Calendar, workdays: WorkdayMerge) {
+        match workdays {
+            WorkdayMerge::Intersect => {
+                self.official_days.retain(|date| other.official_days.contains(date));
+                self.calendar_days.retain(|date, _| other.calendar_days.contains_key(date));
+            }
+            WorkdayMerge::Union => self.official_days.extend(other.official_days.iter().cloned()),
+        }
+        for (date, other_day) in other.calendar_days {
+            let day = match (workdays, self.calendar_days.get_mut(&date)) {
+                (_, Some(day)) => day,
+                (WorkdayMerge::Union, None) => self.calendar_days.entry(date).or_insert(CalendarDay {
+                    work_start_time: None,
+                    work_end_time: None,
+                    scheduled_items: BTreeSet::new(),
+                }),
+                (WorkdayMerge::Intersect, None) => continue,
+            };
+            if day.work_start_time.is_none() && day.work_end_time.is_none() {
+                day.work_start_time = other_day.work_start_time;
+                day.work_end_time = other_day.work_end_time;
+            }
+            day.scheduled_items.extend(other_day.scheduled_items);
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -108,6 +161,28 @@ struct Settings {
     default_working_time: WorkingTime,
     date_range: DateRange,
     holidays: Vec<NaiveDate>,
+    /// 重ねて読み込む別のカレンダー (チームの予定など)
+    #[serde(default)]
+    calendars: Vec<CalendarSource>,
+}
+
+/// 重ねるカレンダーの置き場所 (settings.yaml からの相対パス) と稼働日の合わせ方
+#[derive(Debug, Deserialize)]
+struct CalendarSource {
+    path: PathBuf,
+    #[serde(default)]
+    workdays: WorkdayMerge,
+}
+
+/// カレンダーを重ねるときの稼働日の決め方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkdayMerge {
+    /// どちらかが休みなら休み (チームの休日に合わせる)
+    #[default]
+    Intersect,
+    /// どちらかが稼働日なら稼働日
+    Union,
 }
 
 #[derive(Deserialize)]
@@ -116,19 +191,46 @@ struct OverridesConfig {
     override_workday_to_holiday: Vec<NaiveDate>,
 }
 
-#[derive(Deserialize)]
+#[derive(Default, Serialize, Deserialize)]
 struct DayScheduleConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     start_time: Option<NaiveTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     end_time: Option<NaiveTime>,
+    #[serde(default)]
     schedule: Vec<DayScheduleItem>,
 }
-#[derive(Deserialize)]
+#[derive(PartialEq, Serialize, Deserialize)]
 struct DayScheduleItem {
     start: NaiveTime,
     end: NaiveTime,
     note: Option<String>,
 }
 
+/// 初回起動時に作る settings.yaml。祝日は含めないので、必要なら holidays に書き足してもらう
+fn default_settings_yaml(today: NaiveDate) -> String {
+    let end = today + Duration::days(365);
+    let holidays: Vec<_> = today
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .filter(|date| date.weekday().number_from_monday() >= 6)
+        .map(|date| format!("- {}", date))
+        .collect();
+    [
+        "# 自動で作成した設定です。勤務時間・期間・休日は必要に応じて書き換えてください".to_string(),
+        "default_working_time: { start: \"09:00\", end: \"17:00\" }".to_string(),
+        format!("date_range: {{ start: \"{}\", end: \"{}\" }}", today, end),
+        "# 休日 (土日のみ。祝日や休暇はここに追加する)".to_string(),
+        "holidays:".to_string(),
+    ]
+    .into_iter()
+    .chain(holidays)
+    .chain(["working_time: { start: \"09:00\", end: \"17:00\" }".to_string()])
+    .collect::<Vec<_>>()
+    .join("\n")
+        + "\n"
+}
+
 pub enum TimeKind {
     Available,
     Busy(Box<Option<String>>),
@@ -163,11 +265,30 @@ impl TimeWindow {
 }
 
 impl Calendar {
-    /// settings.yaml, override.yaml, schedule/*.yaml を読み込んで Calendar を構築
+    /// settings.yaml, override.yaml, schedule/*.yaml を読み込んで Calendar を構築。
+    /// settings.yaml の `calendars` に書いたカレンダー (同じ構成のディレクトリ) も読み込んで重ねる
     pub fn import_from_yaml<P: AsRef<Path>>(settings_dirpath: P) -> Result<Self> {
+        let (mut cal, sources) = Self::import_source(settings_dirpath.as_ref())?;
+        for source in sources {
+            let dir = settings_dirpath.as_ref().join(&source.path);
+            // 重ねたカレンダーの calendars はたどらない
+            let (other, _) = Self::import_source(&dir).with_context(|| format!("failed to import calendar {:?}", dir))?;
+            cal.merge(other, source.workdays);
+        }
+        Ok(cal)
+    }
+    /// `import_from_yaml` が読み込むディレクトリ (設定のディレクトリと、`calendars` で重ねるカレンダー)
+    pub fn source_dirs<P: AsRef<Path>>(settings_dirpath: P) -> Result<Vec<PathBuf>> {
         let settings_path = settings_dirpath.as_ref().join("settings.yaml");
-        let overrides_path = settings_dirpath.as_ref().join("overrides.yaml");
-        let schedule_dir = settings_dirpath.as_ref().join("schedule");
+        let s = fs::read_to_string(&settings_path).with_context(|| format!("failed to read {:?}", settings_path))?;
+        let cfg: Settings = serde_yaml::from_str(&s).context("failed to parse settings.yaml")?;
+        let sources = cfg.calendars.into_iter().map(|source| settings_dirpath.as_ref().join(source.path));
+        Ok(std::iter::once(settings_dirpath.as_ref().to_path_buf()).chain(sources).collect())
+    }
+    fn import_source(settings_dirpath: &Path) -> Result<(Self, Vec<CalendarSource>)> {
+        let settings_path = settings_dirpath.join("settings.yaml");
+        let overrides_path = settings_dirpath.join("overrides.yaml");
+        let schedule_dir = settings_dirpath.join("schedule");
 
         // 1. 設定ファイル読み込み
         let s = fs::read_to_string(&settings_path).with_context(|| format!("failed to read {:?}", settings_path))?;
@@ -205,8 +326,9 @@ impl Calendar {
             cal.remove_working_day(h, false);
         }
 
-        // 5. schedule ディレクトリ内の *.yaml を読み込み
-        for entry in fs::read_dir(schedule_dir)? {
+        // 5. schedule ディレクトリ内の *.yaml を読み込み (重ねるカレンダーには予定がないこともある)
+        let entries: Vec<_> = if schedule_dir.exists() { fs::read_dir(schedule_dir)?.collect() } else { vec![] };
+        for entry in entries {
             let path: PathBuf = entry?.path();
             if path.extension().and_then(|e| e.to_str()) != Some("yaml") {
                 continue;
@@ -230,10 +352,46 @@ impl Calendar {
             }
         }
 
-        Ok(cal)
+        Ok((cal, cfg.calendars))
+    }
+    /// settings.yaml がなければ、既定の設定 (09:00–17:00、今日から 1 年間、土日休み) で作る。作った場合は true
+    pub fn create_default_settings<P: AsRef<Path>>(settings_dirpath: P, today: NaiveDate) -> Result<bool> {
+        let settings_path = settings_dirpath.as_ref().join("settings.yaml");
+        if settings_path.exists() {
+            return Ok(false);
+        }
+        fs::create_dir_all(settings_dirpath.as_ref().join("schedule")).with_context(|| format!("failed to create {:?}", settings_dirpath.as_ref()))?;
+        fs::write(&settings_path, default_settings_yaml(today)).with_context(|| format!("failed to write {:?}", settings_path))?;
+        Ok(true)
+    }
+    /// schedule/<date>.yaml に予定を書き足す (同じ予定がすでにあれば追加しない)。追加した件数を返す
+    pub fn append_to_day_schedule<P: AsRef<Path>>(settings_dirpath: P, date: NaiveDate, items: &[ScheduleItem]) -> Result<usize> {
+        let path = settings_dirpath.as_ref().join("schedule").join(format!("{}.yaml", date.format("%Y-%m-%d")));
+        let mut day_cfg: DayScheduleConfig = if path.exists() {
+            let txt = fs::read_to_string(&path)?;
+            serde_yaml::from_str(&txt).with_context(|| format!("failed to parse {:?}", path))?
+        } else {
+            DayScheduleConfig::default()
+        };
+        let mut added = 0;
+        for item in items {
+            let item = DayScheduleItem {
+                start: item.start,
+                end: item.start + item.duration,
+                note: item.note.clone(),
+            };
+            if !day_cfg.schedule.contains(&item) {
+                day_cfg.schedule.push(item);
+                added += 1;
+            }
+        }
+        if added > 0 {
+            fs::write(&path, serde_yaml::to_string(&day_cfg)?).with_context(|| format!("failed to write {:?}", path))?;
+        }
+        Ok(added)
     }
     pub fn official_workdays(&self, start_at: NaiveDate) -> impl Iterator<Item = &NaiveDate> {
-        self.official_days.iter().skip_while(move |date| *date < &start_at)
+        self.official_days.range(start_at..)
     }
     /// 指定の日付が全社公式稼働日か
     pub fn is_official_workday(&self, date: &NaiveDate) -> bool {
@@ -243,15 +401,21 @@ impl Calendar {
     pub fn previous_official_workday(&self, date: &NaiveDate) -> Option<NaiveDate> {
         self.official_days.range(..*date).cloned().next_back()
     }
+    /// 指定日から `days` 稼働日後の公式稼働日 (指定日が稼働日でなくても、翌稼働日を 1 日目と数える)
+    pub fn add_business_days(&self, date: NaiveDate, days: u16) -> Option<NaiveDate> {
+        if days == 0 {
+            return Some(date);
+        }
+        self.official_days.range(date.succ_opt()?..).nth(days as usize - 1).cloned()
+    }
     /// `from` 時点以降の公式稼働日について、時間ウィンドウを
     /// 日付順・時刻順に列挙するイテレータを返す
     pub fn time_windows(&self, from: NaiveDateTime) -> impl Iterator<Item = TimeWindow> {
         self.official_workdays(from.date()).flat_map(move |date| {
             // 1) 勤務時間帯を得る
             let (work_start, work_end) = self.working_time(*date).unwrap_or(self.working_time);
-            // 2) 当日の予定済みアイテムを start 時刻順で取得
-            let mut busy = self.calendar_days.get(date).map(|d| d.scheduled_items.iter().cloned().collect::<Vec<_>>()).unwrap_or_default();
-            busy.sort_by_key(|item| item.start);
+            // 2) 当日の予定済みアイテムを start 時刻順で取得 (重なる予定は 1 つにまとめる)
+            let busy = self.calendar_days.get(date).map(|d| d.busy_spans()).unwrap_or_default();
             // 3) 「from」と組み合わせて最初の window_start を決定
             let mut window_start = if *date == from.date() && from.time() > work_start { from.time() } else { work_start };
             // 4) 予定アイテム間のギャップを yield
@@ -265,6 +429,10 @@ impl Calendar {
                         start: window_start,
                         end: item_start,
                     });
+                }
+                // 直前の空き時間がなくても (勤務開始直後・予定の連続) 予定自体は列挙する。
+                // ただし from より前に終わっている予定は含めない
+                if item.start + item.duration > window_start {
                     windows.push(TimeWindow {
                         kind: TimeKind::Busy(Box::new(item.note)),
                         date: *date,
@@ -288,6 +456,22 @@ impl Calendar {
         })
     }
 
+    /// `begin`〜`end` の区間全体が、その日の空き時間 (勤務時間内かつ予定なし) に収まるか
+    pub fn is_available_between(&self, begin: NaiveDateTime, end: NaiveDateTime) -> bool {
+        if begin.date() != end.date() {
+            return false;
+        }
+        let mut covered_until = begin;
+        for window in self.time_windows(begin.date().and_time(NaiveTime::MIN)).take_while(|w| w.date == begin.date()) {
+            if !window.available() {
+                continue;
+            }
+            if window.start_datetime() <= covered_until && covered_until < window.end_datetime() {
+                covered_until = window.end_datetime();
+            }
+        }
+        end <= covered_until
+    }
     /// `until` までの公式稼働日について、時間ウィンドウを
     /// 日付順・時刻順に列挙するイテレータを逆順に返す (free_time_windows() の逆)
     pub fn time_windows_rev(&self, until: NaiveDateTime) -> impl Iterator<Item = TimeWindow> {
@@ -301,11 +485,11 @@ impl Calendar {
 
             // 逆順で busy アイテムを走査し、ギャップを順次プッシュ
             if let Some(day) = self.calendar_days.get(&date) {
-                for item in day.scheduled_items.iter().rev() {
+                for item in day.busy_spans().into_iter().rev() {
                     let item_end = (item.start + item.duration).min(window_end);
                     if item_end < window_end {
                         windows.push(TimeWindow {
-                            kind: TimeKind::Busy(Box::new(item.note.clone())),
+                            kind: TimeKind::Busy(Box::new(item.note)),
                             date,
                             start: item.start,
                             end: item.start + item.duration,
@@ -347,8 +531,9 @@ mod tests {
     use super::*;
     use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 
+    /// 空き時間のウィンドウだけを (開始, 終了) の組にする
     fn tupled(windows: impl Iterator<Item = TimeWindow>) -> Vec<(NaiveDateTime, NaiveDateTime)> {
-        windows.map(|w| (w.start_datetime(), w.end_datetime())).collect()
+        windows.filter(|w| w.available()).map(|w| (w.start_datetime(), w.end_datetime())).collect()
     }
 
     #[test]
@@ -461,4 +646,125 @@ mod tests {
         )];
         assert_eq!(fw_rev, expected);
     }
+
+    #[test]
+    fn test_add_business_days() {
+        // 5/1(木), 5/2(金), 5/7(水) が稼働日
+        let mut cal = Calendar::new((NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap()));
+        let d = |day| NaiveDate::from_ymd_opt(2025, 5, day).unwrap();
+        for day in [1, 2, 7] {
+            cal.add_working_day(d(day), true);
+        }
+        assert_eq!(cal.add_business_days(d(1), 0), Some(d(1)));
+        assert_eq!(cal.add_business_days(d(1), 1), Some(d(2)));
+        assert_eq!(cal.add_business_days(d(1), 2), Some(d(7)));
+        // 休日起点は翌稼働日が 1 日目
+        assert_eq!(cal.add_business_days(d(3), 1), Some(d(7)));
+        assert_eq!(cal.add_business_days(d(1), 3), None);
+    }
+
+    #[test]
+    fn test_is_available_between() {
+        let at = |d: u32, h: u32, m: u32| NaiveDate::from_ymd_opt(2025, 5, d).unwrap().and_hms_opt(h, m, 0).unwrap();
+        let mut cal = Calendar::new((NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap()));
+        let date = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+        cal.add_working_day(date, true);
+        cal.add_scheduled_item(
+            &date,
+            ScheduleItem {
+                start: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+                duration: Duration::hours(1),
+                note: None,
+            },
+        );
+        assert!(cal.is_available_between(at(1, 9, 0), at(1, 12, 0)));
+        assert!(cal.is_available_between(at(1, 13, 30), at(1, 17, 0)));
+        // 予定と重なる・勤務時間をはみ出す・稼働日でない
+        assert!(!cal.is_available_between(at(1, 11, 0), at(1, 13, 30)));
+        assert!(!cal.is_available_between(at(1, 16, 0), at(1, 23, 0)));
+        assert!(!cal.is_available_between(at(2, 10, 0), at(2, 11, 0)));
+    }
+
+    #[test]
+    fn test_merge_overlapping_busy_items() {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let meeting = |h, m, minutes, note: &str| ScheduleItem {
+            start: t(h, m),
+            duration: Duration::minutes(minutes),
+            note: Some(note.to_string()),
+        };
+        let d1 = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+        let d2 = NaiveDate::from_ymd_opt(2025, 5, 2).unwrap();
+        let d3 = NaiveDate::from_ymd_opt(2025, 5, 5).unwrap();
+        // 個人: 5/1・5/2 稼働、5/1 10:00–11:00 に予定
+        let mut personal = Calendar::new((t(9, 0), t(17, 0)));
+        personal.add_working_day(d1, true);
+        personal.add_working_day(d2, true);
+        personal.add_scheduled_item(&d1, meeting(10, 0, 60, "1on1"));
+        // チーム: 5/2 は休み、5/5 は稼働。5/1 10:30–11:30 に定例 (個人の予定と重なる)
+        let mut team = Calendar::new((t(10, 0), t(18, 0)));
+        team.add_working_day(d1, true);
+        team.add_working_day(d3, true);
+        team.add_scheduled_item(&d1, meeting(10, 30, 60, "定例"));
+
+        let mut merged = personal.clone();
+        merged.merge(team.clone(), WorkdayMerge::Intersect);
+        // チームの休みは休みになり、勤務時間は個人のものを使う
+        assert_eq!(merged.official_workdays(d1).cloned().collect::<Vec<_>>(), vec![d1]);
+        assert_eq!(merged.working_time(d1), Some((t(9, 0), t(17, 0))));
+        // 両方の予定を避ける: 10:00–11:30 は空かない
+        let fw = tupled(merged.time_windows(d1.and_time(t(9, 0))));
+        assert_eq!(fw, vec![(d1.and_time(t(9, 0)), d1.and_time(t(10, 0))), (d1.and_time(t(11, 30)), d1.and_time(t(17, 0)))]);
+
+        let mut merged = personal;
+        merged.merge(team, WorkdayMerge::Union);
+        assert_eq!(merged.official_workdays(d1).cloned().collect::<Vec<_>>(), vec![d1, d2, d3]);
+        assert_eq!(merged.calendar_days[&d1].scheduled_items.len(), 2);
+    }
+
+    #[test]
+    fn test_overlapping_meetings_are_merged() {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let mut cal = Calendar::new((t(9, 0), t(17, 0)));
+        let d1 = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+        cal.add_working_day(d1, true);
+        // 10:00–12:00 の中に 10:30–11:00、さらに 11:30–12:30 が重なる
+        for (start, minutes, note) in [(t(10, 0), 120, "設計レビュー"), (t(10, 30), 30, "1on1"), (t(11, 30), 60, "設計レビュー")] {
+            let note = Some(note.to_string());
+            cal.add_scheduled_item(
+                &d1,
+                ScheduleItem {
+                    start,
+                    duration: Duration::minutes(minutes),
+                    note,
+                },
+            );
+        }
+
+        // 予定は別々に残し、空き時間の計算では 10:00–12:30 の 1 区間になる
+        assert_eq!(cal.calendar_days[&d1].scheduled_items.len(), 3);
+        let busy: Vec<_> = cal.time_windows(d1.and_time(t(9, 0))).filter(|w| !w.available()).collect();
+        assert_eq!(busy.len(), 1);
+        assert_eq!((busy[0].start, busy[0].end), (t(10, 0), t(12, 30)));
+        assert_eq!(busy[0].note(), "設計レビュー / 1on1");
+
+        let expected = vec![(d1.and_time(t(9, 0)), d1.and_time(t(10, 0))), (d1.and_time(t(12, 30)), d1.and_time(t(17, 0)))];
+        let fw = tupled(cal.time_windows(d1.and_time(t(9, 0))));
+        assert_eq!(fw, expected);
+        let fw_rev = tupled(cal.time_windows_rev(d1.and_time(t(17, 0))));
+        assert_eq!(fw.iter().rev().cloned().collect::<Vec<_>>(), fw_rev);
+    }
+
+    #[test]
+    fn test_default_settings_yaml() {
+        let today = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+        let settings: Settings = serde_yaml::from_str(&default_settings_yaml(today)).unwrap();
+        assert_eq!(settings.date_range.start, today);
+        assert_eq!(settings.date_range.end, NaiveDate::from_ymd_opt(2026, 5, 7).unwrap());
+        assert_eq!(settings.default_working_time.start, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        // 休日は土日だけ
+        assert_eq!(settings.holidays.first(), NaiveDate::from_ymd_opt(2025, 5, 10).as_ref());
+        assert!(settings.holidays.iter().all(|d| d.weekday().number_from_monday() >= 6));
+        assert!(settings.calendars.is_empty());
+    }
 }
@@ -0,0 +1,297 @@
+use super::{
+    estimate::StoryPointScale,
+    schedule::{EstimateDecay, PriorityWeights, Strategy},
+    utils::parse_human_duration,
+};
+use anyhow::{Context, Result};
+use chrono::{
+    Duration, NaiveTime,
+    format::{Item, StrftimeItems},
+};
+use serde::{Deserialize, Deserializer};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// カテゴリの表示色 (端末の基本 6 色)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategoryColor {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+impl CategoryColor {
+    /// 色を指定していないカテゴリに順に割り振る色
+    pub const PALETTE: [CategoryColor; 6] = [
+        CategoryColor::Cyan,
+        CategoryColor::Green,
+        CategoryColor::Magenta,
+        CategoryColor::Yellow,
+        CategoryColor::Blue,
+        CategoryColor::Red,
+    ];
+}
+
+/// settings.yaml のうち、カレンダー以外 (見積・スケジューラ・シェル) の設定
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// ストーリーポイント → 所要時間の対応表
+    pub story_points: StoryPointScale,
+    /// 見積のない着手可能タスクがあればスケジュールを拒否する (既定の 5 分で埋めない)
+    pub strict: bool,
+    /// 優先度スコアの重み (緊急度・リスク・依存度、合計 1)
+    pub priority_weights: PriorityWeights,
+    /// スケジュール時の 1 回の割り当て単位
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub work_tick: Duration,
+    /// 割り当ての間に挟む休憩
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub buffer_time: Duration,
+    /// カレンダーに勤務時間がない日に使う勤務時間
+    pub working_time: WorkingTime,
+    /// 時刻のない期限に使う時刻
+    pub default_deadline_time: NaiveTime,
+    /// 割り当てを丸める単位 (5m / 15m など。1m なら丸めない)
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub allocation_granularity: Duration,
+    /// 古い見積のリスクを大きく見積もる設定
+    pub estimate_decay: EstimateDecay,
+    /// これより短い空き時間 (予定の合間など) には割り当てない (既定の 0 ならどんな空き時間にも割り当てる)
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub min_window: Duration,
+    /// 割り当てるタスクの選び方 (greedy: 優先度順 / fair:<k>: 上位 k 件を交代で)
+    pub schedule_strategy: Strategy,
+    /// 今日から何日先までスケジュールするか (未指定ならカレンダーの終わりまで)
+    pub schedule_horizon_days: Option<i64>,
+    /// 日時の表示形式 (strftime 形式)
+    pub datetime_format: String,
+    /// 余裕 (最遅開始までの稼働日数) がこれを下回ったタスクを警告する (0 なら最遅開始を過ぎた、余裕が負のタスクだけ警告する)
+    pub min_slack_days: f64,
+    /// カテゴリの表示色 (カテゴリ名 → red / green / yellow / blue / magenta / cyan)。未指定のカテゴリには順に色を割り振る
+    pub category_colors: BTreeMap<String, CategoryColor>,
+    /// `settings` コマンドで変更され、まだ保存していない項目 (キー → YAML の値)
+    #[serde(skip)]
+    changed: BTreeMap<&'static str, String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct WorkingTime {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            story_points: StoryPointScale::default(),
+            strict: false,
+            priority_weights: PriorityWeights::default(),
+            work_tick: Duration::minutes(25),
+            buffer_time: Duration::minutes(5),
+            working_time: WorkingTime {
+                start: NaiveTime::from_hms_opt(8, 45, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+            },
+            default_deadline_time: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            allocation_granularity: Duration::minutes(1),
+            estimate_decay: EstimateDecay::default(),
+            min_window: Duration::zero(),
+            schedule_strategy: Strategy::Greedy,
+            schedule_horizon_days: None,
+            datetime_format: "%m/%d %H:%M".to_string(),
+            min_slack_days: 1.0,
+            category_colors: BTreeMap::new(),
+            changed: BTreeMap::new(),
+        }
+    }
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_human_duration(&s).ok_or_else(|| serde::de::Error::custom(format!("invalid duration: {}", s)))
+}
+
+/// `settings <key> <value>` で変更できる項目
+pub const EDITABLE_SETTINGS: &[&str] = &[
+    "work_tick",
+    "buffer_time",
+    "working_time",
+    "default_deadline_time",
+    "allocation_granularity",
+    "min_window",
+    "schedule_strategy",
+    "strict",
+    "schedule_horizon_days",
+    "datetime_format",
+    "min_slack_days",
+];
+
+impl Config {
+    /// settings.yaml を読み込んで Config を構築 (未記載の項目は既定値)
+    pub fn import_from_yaml<P: AsRef<Path>>(settings_dirpath: P) -> Result<Self> {
+        let settings_path = settings_dirpath.as_ref().join("settings.yaml");
+        let s = fs::read_to_string(&settings_path).with_context(|| format!("failed to read {:?}", settings_path))?;
+        let config: Self = serde_yaml::from_str(&s).context("failed to parse settings.yaml")?;
+        config.priority_weights.validate().map_err(anyhow::Error::msg).context("invalid priority_weights in settings.yaml")?;
+        if !(config.min_slack_days >= 0.0 && config.min_slack_days.is_finite()) {
+            anyhow::bail!("min_slack_days in settings.yaml must be zero or greater");
+        }
+        if config.schedule_horizon_days.is_some_and(|days| days <= 0) {
+            anyhow::bail!("schedule_horizon_days in settings.yaml must be greater than zero");
+        }
+        validate_datetime_format(&config.datetime_format)
+            .map_err(anyhow::Error::msg)
+            .context("invalid datetime_format in settings.yaml")?;
+        config.estimate_decay.validate().map_err(anyhow::Error::msg).context("invalid estimate_decay in settings.yaml")?;
+        Ok(config)
+    }
+
+    /// 設定項目を 1 つ変更する。値は settings.yaml と同じ書式 (`30m`, `08:45-19:00`, `17:00`, `true`)
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let parse_time = |s: &str| NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| format!("時刻は HH:MM で指定してください: {}", s));
+        let parse_duration = |s: &str| match parse_human_duration(s) {
+            Some(d) if d > Duration::zero() => Ok(d),
+            _ => Err(format!("時間は 30m / 1h などで指定してください: {}", s)),
+        };
+        let (key, yaml) = match key {
+            "work_tick" => {
+                self.work_tick = parse_duration(value)?;
+                ("work_tick", value.to_string())
+            }
+            "buffer_time" => {
+                // 休憩なしは許す
+                self.buffer_time = if value == "0m" { Duration::zero() } else { parse_duration(value)? };
+                ("buffer_time", value.to_string())
+            }
+            "allocation_granularity" => {
+                self.allocation_granularity = parse_duration(value)?;
+                ("allocation_granularity", value.to_string())
+            }
+            "min_window" => {
+                // 0m ならどんなに短い空き時間にも割り当てる
+                self.min_window = if value == "0m" { Duration::zero() } else { parse_duration(value)? };
+                ("min_window", value.to_string())
+            }
+            "schedule_strategy" => {
+                self.schedule_strategy = value.parse()?;
+                ("schedule_strategy", self.schedule_strategy.to_string())
+            }
+            "working_time" => {
+                let Some((start, end)) = value.split_once('-') else {
+                    return Err(format!("勤務時間は HH:MM-HH:MM で指定してください: {}", value));
+                };
+                let (start, end) = (parse_time(start)?, parse_time(end)?);
+                if start >= end {
+                    return Err(format!("勤務時間の終了は開始より後にしてください: {}", value));
+                }
+                self.working_time = WorkingTime { start, end };
+                ("working_time", format!("{{ start: \"{}\", end: \"{}\" }}", start.format("%H:%M"), end.format("%H:%M")))
+            }
+            "default_deadline_time" => {
+                let time = parse_time(value)?;
+                self.default_deadline_time = time;
+                ("default_deadline_time", format!("\"{}\"", time.format("%H:%M")))
+            }
+            "strict" => {
+                self.strict = value.parse().map_err(|_| format!("true / false で指定してください: {}", value))?;
+                ("strict", self.strict.to_string())
+            }
+            "schedule_horizon_days" => {
+                self.schedule_horizon_days = match value {
+                    "none" => None,
+                    _ => Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|days| *days > 0)
+                            .ok_or_else(|| format!("日数 (1 以上) または none で指定してください: {}", value))?,
+                    ),
+                };
+                ("schedule_horizon_days", self.schedule_horizon_days.map_or("null".to_string(), |days| days.to_string()))
+            }
+            "datetime_format" => {
+                validate_datetime_format(value)?;
+                self.datetime_format = value.to_string();
+                ("datetime_format", format!("\"{}\"", value))
+            }
+            "min_slack_days" => {
+                self.min_slack_days = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|days| *days >= 0.0 && days.is_finite())
+                    .ok_or_else(|| format!("日数 (0 以上、0.5 なども可) で指定してください: {}", value))?;
+                ("min_slack_days", self.min_slack_days.to_string())
+            }
+            _ => return Err(format!("変更できない設定です: {} ({})", key, EDITABLE_SETTINGS.join(" / "))),
+        };
+        self.changed.insert(key, yaml);
+        Ok(())
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// 変更した項目だけを settings.yaml に書き戻す (コメントや他の項目はそのまま残す)
+    pub fn save_changes<P: AsRef<Path>>(&self, settings_dirpath: P) -> Result<()> {
+        let settings_path = settings_dirpath.as_ref().join("settings.yaml");
+        let s = fs::read_to_string(&settings_path).with_context(|| format!("failed to read {:?}", settings_path))?;
+        fs::write(&settings_path, apply_changes(&s, &self.changed)).with_context(|| format!("failed to write {:?}", settings_path))
+    }
+}
+
+/// strftime の書式として解釈できるか (不正な指定子があると表示時に panic するため、読み込み時に弾く)
+fn validate_datetime_format(format: &str) -> Result<(), String> {
+    if format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("日時の表示形式が不正です (strftime 形式で指定してください): {:?}", format));
+    }
+    Ok(())
+}
+
+/// YAML のトップレベルの `key: ...` 行を置き換え、なければ末尾に追記する
+fn apply_changes(yaml: &str, changed: &BTreeMap<&'static str, String>) -> String {
+    let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+    for (key, value) in changed {
+        let prefix = format!("{}:", key);
+        let line = format!("{}: {}", key, value);
+        match lines.iter_mut().find(|l| l.starts_with(&prefix)) {
+            Some(existing) => *existing = line,
+            None => lines.push(line),
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+#[test]
+fn test_set_and_apply_changes() {
+    let mut config = Config::default();
+    config.set("work_tick", "30m").unwrap();
+    config.set("working_time", "09:00-18:00").unwrap();
+    assert_eq!(config.work_tick, Duration::minutes(30));
+    assert!(config.set("working_time", "18:00-09:00").is_err());
+    assert!(config.set("story_points", "1").is_err());
+
+    let yaml = "# tick\nwork_tick: 25m\nstrict: false\n";
+    let saved = apply_changes(yaml, &config.changed);
+    assert_eq!(saved, "# tick\nwork_tick: 30m\nstrict: false\nworking_time: { start: \"09:00\", end: \"18:00\" }\n");
+    let reloaded: Config = serde_yaml::from_str(&saved).unwrap();
+    assert_eq!(reloaded.work_tick, Duration::minutes(30));
+    assert_eq!(reloaded.working_time.start, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+}
+
+#[test]
+fn test_datetime_format() {
+    let mut config = Config::default();
+    config.set("datetime_format", "%Y-%m-%d %H:%M").unwrap();
+    assert_eq!(config.datetime_format, "%Y-%m-%d %H:%M");
+    assert!(config.set("datetime_format", "%Q").is_err());
+    assert!(config.set("datetime_format", "").is_err());
+    assert_eq!(config.datetime_format, "%Y-%m-%d %H:%M");
+
+    let saved = apply_changes("strict: false\n", &config.changed);
+    let reloaded: Config = serde_yaml::from_str(&saved).unwrap();
+    assert_eq!(reloaded.datetime_format, "%Y-%m-%d %H:%M");
+}
@@ -62,6 +62,7 @@ impl FuzzyDeadline {
         let time = self.time.unwrap_or(default_deadline_time);
         deadline_date.and_time(time)
     }
+    /// カレンダーの稼働日に合わせて解決する。結果は保持しないので、休日を足すなどカレンダーが変われば解決し直した期限も変わる
     pub fn resolve_with_calendar(&self, calendar: &Calendar, default_deadline_time: NaiveTime) -> Result<NaiveDateTime, String> {
         use FuzzyDeadlineKind::*;
         let base_date = self.reference_date.date();
@@ -92,10 +93,10 @@ impl FuzzyDeadline {
         };
 
         // 2) 公式稼働日でなければ、直前の公式稼働日に丸め込む
-        if !calendar.is_official_workday(&deadline_date) {
-            if let Some(prev) = calendar.previous_official_workday(&deadline_date) {
-                deadline_date = prev;
-            }
+        if !calendar.is_official_workday(&deadline_date)
+            && let Some(prev) = calendar.previous_official_workday(&deadline_date)
+        {
+            deadline_date = prev;
         }
 
         let time = self.time.unwrap_or(default_deadline_time);
@@ -115,15 +116,46 @@ fn test_resolve_fuzzy_deadline() {
     let resolved_date = fuzzy_deadline.resolve(default_deadline_time);
     assert_eq!(resolved_date, NaiveDateTime::from_str("2025-05-03T17:00:00").unwrap());
 
+    // 時刻の指定がなければ、その日の 23:59:59 ではなく既定の期限時刻 (default_deadline_time) になる
     // FridayOfWeeks(0)
     let fuzzy_deadline = FuzzyDeadline::new(reference_date, FuzzyDeadlineKind::FridayOfWeeks(0), None);
     let resolved_date = fuzzy_deadline.resolve(default_deadline_time);
-    assert_eq!(resolved_date, NaiveDateTime::from_str("2025-05-02T23:59:59").unwrap());
+    assert_eq!(resolved_date, NaiveDateTime::from_str("2025-05-02T20:00:00").unwrap());
 
     // Weeks(n)
     let fuzzy_deadline = FuzzyDeadline::new(reference_date, FuzzyDeadlineKind::Weeks(2), None);
     let resolved_date = fuzzy_deadline.resolve(default_deadline_time);
-    assert_eq!(resolved_date, NaiveDateTime::from_str("2025-05-14T23:59:59").unwrap());
+    assert_eq!(resolved_date, NaiveDateTime::from_str("2025-05-14T20:00:00").unwrap());
+}
+
+#[test]
+fn test_business_days_follow_calendar_changes() {
+    let default_deadline_time = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+    let mut calendar = Calendar::new((NaiveTime::from_hms_opt(9, 0, 0).unwrap(), default_deadline_time));
+    // 2025-04-30 (水) から 2 週間、土日以外を稼働日にする
+    let reference_date = NaiveDateTime::from_str("2025-04-30T00:00:00").unwrap();
+    for date in reference_date.date().iter_days().take(14).filter(|d| d.weekday().number_from_monday() <= 5) {
+        calendar.add_working_day(date, true);
+    }
+    let deadline = FuzzyDeadline::new(reference_date, FuzzyDeadlineKind::BusinessDays(3), None);
+    // 4/30 の 3 稼働日後は、土日を飛ばして 5/5 (月)
+    assert_eq!(
+        deadline.resolve_with_calendar(&calendar, default_deadline_time).unwrap(),
+        NaiveDateTime::from_str("2025-05-05T17:00:00").unwrap()
+    );
+
+    // 途中の 5/1 を休日にすると 1 稼働日後ろにずれる
+    calendar.remove_working_day(NaiveDateTime::from_str("2025-05-01T00:00:00").unwrap().date(), true);
+    assert_eq!(
+        deadline.resolve_with_calendar(&calendar, default_deadline_time).unwrap(),
+        NaiveDateTime::from_str("2025-05-06T17:00:00").unwrap()
+    );
+    // 期限より後の休日は影響しない
+    calendar.remove_working_day(NaiveDateTime::from_str("2025-05-08T00:00:00").unwrap().date(), true);
+    assert_eq!(
+        deadline.resolve_with_calendar(&calendar, default_deadline_time).unwrap(),
+        NaiveDateTime::from_str("2025-05-06T17:00:00").unwrap()
+    );
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,5 +1,7 @@
+use super::utils::{format_human_duration, parse_human_duration};
 use chrono::Duration;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Estimate {
@@ -17,23 +19,62 @@ impl Estimate {
         }
     }
     pub fn from_mop(most_likely: Duration, optimistic: Duration, pessimistic: Duration) -> Result<Self, String> {
-        if optimistic > most_likely || most_likely > pessimistic {
-            return Err("Optimistic time must be less than or equal to Most Likely time, which must be less than or equal to Pessimistic time.".to_string());
+        // どの大小関係が崩れているかを具体的に示す (楽観 ≤ 最尤 ≤ 悲観)
+        let order_error = |smaller: &str, s: Duration, larger: &str, l: Duration| {
+            format!(
+                "{} time ({}) must be less than or equal to {} time ({}).",
+                smaller,
+                format_human_duration(s),
+                larger,
+                format_human_duration(l)
+            )
+        };
+        if optimistic > pessimistic {
+            return Err(order_error("Optimistic", optimistic, "Pessimistic", pessimistic));
+        }
+        if optimistic > most_likely {
+            return Err(order_error("Optimistic", optimistic, "Most Likely", most_likely));
+        }
+        if most_likely > pessimistic {
+            return Err(order_error("Most Likely", most_likely, "Pessimistic", pessimistic));
         }
         if optimistic.num_minutes() <= 0 || most_likely.num_minutes() <= 0 || pessimistic.num_minutes() <= 0 {
             return Err("All estimates must be greater than zero.".to_string());
         }
         Ok(Self { most_likely, optimistic, pessimistic })
     }
+    /// `est` にそのまま渡せる形 (`最尤 楽観 悲観`、幅がなければ最尤だけ) で表す。例: "1h 30m 2h"
+    pub fn to_command_args(&self) -> String {
+        // 1 語で書けて parse_human_duration で読み戻せる単位にする。
+        // est は先頭の `-` を減算と読むので、負の値 (以前に保存された不正な見積) は 0 として出す
+        let arg = |d: Duration| match d.num_minutes().max(0) {
+            m if m % 60 == 0 => format!("{}h", m / 60),
+            m => format!("{}m", m),
+        };
+        if self.optimistic == self.most_likely && self.pessimistic == self.most_likely {
+            return arg(self.most_likely);
+        }
+        format!("{} {} {}", arg(self.most_likely), arg(self.optimistic), arg(self.pessimistic))
+    }
+    /// 最尤値を `percent` % だけ上下に振って楽観値・悲観値とする
+    /// (e.g. 1d ±50% => 楽観 0.5d, 最尤 1d, 悲観 1.5d)
+    pub fn from_spread(most_likely: Duration, percent: u32) -> Result<Self, String> {
+        let delta = most_likely * percent as i32 / 100;
+        Self::from_mop(most_likely, most_likely - delta, most_likely + delta)
+    }
+    /// PERT の平均 (楽観 + 最尤×4 + 悲観) / 6。
+    /// 分単位の i128 で計算して桁あふれを避け、1 分未満は四捨五入する (Duration の上限で頭打ち)
     pub fn mean(&self) -> Duration {
-        (self.optimistic + self.most_likely * 4 + self.pessimistic) / 6
+        let total = self.optimistic.num_minutes() as i128 + self.most_likely.num_minutes() as i128 * 4 + self.pessimistic.num_minutes() as i128;
+        let mean = (total + 3).div_euclid(6);
+        Duration::minutes(mean.clamp(0, Duration::MAX.num_minutes() as i128) as i64)
     }
     pub fn stddev(&self) -> Duration {
         (self.pessimistic - self.optimistic) / 6
     }
     pub fn variance_minutes(&self) -> i64 {
         let stddev = self.stddev().num_minutes();
-        stddev * stddev
+        stddev.saturating_mul(stddev)
     }
 }
 
@@ -48,14 +89,165 @@ impl std::ops::Add for Estimate {
         }
     }
 }
+/// 負の時間にはせず 0 で止める。値ごとに引くと 楽観 ≤ 最尤 ≤ 悲観 が崩れることがあるので、最尤を挟むように直す
 impl std::ops::Sub for Estimate {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
+        let saturating_sub = |a: Duration, b: Duration| (a - b).max(Duration::zero());
+        let most_likely = saturating_sub(self.most_likely, other.most_likely);
         Self {
-            most_likely: self.most_likely - other.most_likely,
-            optimistic: self.optimistic - other.optimistic,
-            pessimistic: self.pessimistic - other.pessimistic,
+            most_likely,
+            optimistic: saturating_sub(self.optimistic, other.optimistic).min(most_likely),
+            pessimistic: saturating_sub(self.pessimistic, other.pessimistic).max(most_likely),
+        }
+    }
+}
+
+#[test]
+fn test_mean_precision_and_overflow() {
+    // 端数は四捨五入する (65 / 6 = 10.83 分)
+    let estimate = Estimate::from_mop(Duration::minutes(10), Duration::minutes(10), Duration::minutes(15)).unwrap();
+    assert_eq!(estimate.mean(), Duration::minutes(11));
+
+    // 悲観値が数か月でも正しく計算できる
+    let estimate = Estimate::from_mop(Duration::days(20), Duration::days(5), Duration::days(180)).unwrap();
+    assert_eq!(estimate.mean(), Duration::minutes((5 + 80 + 180) * 24 * 60 / 6));
+
+    // 最尤値×4 が Duration の範囲を超えても落ちず、上限で頭打ちになる
+    let huge = Duration::MAX / 2;
+    let estimate = Estimate::from_mop(huge, Duration::days(1), Duration::MAX).unwrap();
+    assert!(estimate.mean() > huge && estimate.mean() <= Duration::MAX);
+    assert!(estimate.variance_minutes() > 0);
+}
+
+#[test]
+fn test_from_mop_reports_misordered_input() {
+    // est #x 1h 2h 30m: 楽観 2h が悲観 30m より大きい
+    let err = Estimate::from_mop(Duration::hours(1), Duration::hours(2), Duration::minutes(30)).unwrap_err();
+    assert!(err.contains("Optimistic time (2h)") && err.contains("Pessimistic time (30min)"), "{}", err);
+    let err = Estimate::from_mop(Duration::hours(3), Duration::hours(1), Duration::hours(2)).unwrap_err();
+    assert!(err.contains("Most Likely time (3h)"), "{}", err);
+    let err = Estimate::from_mop(Duration::hours(1), Duration::hours(1), Duration::minutes(0)).unwrap_err();
+    assert!(err.contains("Optimistic time (1h)"), "{}", err);
+}
+
+#[test]
+fn test_to_command_args() {
+    let estimate = Estimate::from_mop(Duration::hours(1), Duration::minutes(30), Duration::hours(2)).unwrap();
+    assert_eq!(estimate.to_command_args(), "1h 30m 2h");
+    assert_eq!(Estimate::new(Duration::minutes(90)).to_command_args(), "90m");
+
+    // est と同じ解釈で読み戻せる
+    let reparsed: Vec<_> = estimate.to_command_args().split(' ').map(|arg| parse_human_duration(arg).unwrap()).collect();
+    assert_eq!(reparsed, vec![estimate.most_likely, estimate.optimistic, estimate.pessimistic]);
+
+    // 負の見積でも `-` から始めない (est では減算になってしまう)
+    assert_eq!(Estimate::new(Duration::minutes(-60)).to_command_args(), "0h");
+}
+
+#[test]
+fn test_from_spread() {
+    let estimate = Estimate::from_spread(Duration::hours(8), 50).unwrap();
+    assert_eq!(estimate.most_likely, Duration::hours(8));
+    assert_eq!(estimate.optimistic, Duration::hours(4));
+    assert_eq!(estimate.pessimistic, Duration::hours(12));
+    assert_eq!(estimate.mean(), Duration::hours(8));
+
+    let estimate = Estimate::from_spread(Duration::hours(1), 0).unwrap();
+    assert_eq!(estimate.stddev(), Duration::zero());
+
+    // 楽観値が 0 以下になる幅は不可
+    assert!(Estimate::from_spread(Duration::hours(1), 100).is_err());
+}
+
+/// ストーリーポイント → 所要時間の対応表
+///
+/// settings.yaml の `story_points` に `{ 1: 30m, 2: 1h, ... }` の形で記述する。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "BTreeMap<u32, String>")]
+pub struct StoryPointScale(BTreeMap<u32, Duration>);
+
+impl StoryPointScale {
+    /// ポイントを見積に展開する。
+    /// 最尤値はそのポイントの所要時間、楽観値・悲観値は前後のポイントの所要時間とする。
+    pub fn estimate(&self, points: u32) -> Option<Estimate> {
+        let most_likely = *self.0.get(&points)?;
+        let optimistic = self.0.range(..points).next_back().map(|(_, d)| *d).unwrap_or(most_likely);
+        let pessimistic = self.0.range(points + 1..).next().map(|(_, d)| *d).unwrap_or(most_likely);
+        Estimate::from_mop(most_likely, optimistic, pessimistic).ok()
+    }
+    pub fn points(&self) -> impl Iterator<Item = &u32> {
+        self.0.keys()
+    }
+}
+
+impl Default for StoryPointScale {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            (1, Duration::minutes(30)),
+            (2, Duration::hours(1)),
+            (3, Duration::hours(2)),
+            (5, Duration::hours(4)),
+            (8, Duration::hours(8)),
+            (13, Duration::hours(16)),
+        ]))
+    }
+}
+
+impl TryFrom<BTreeMap<u32, String>> for StoryPointScale {
+    type Error = String;
+    fn try_from(value: BTreeMap<u32, String>) -> Result<Self, Self::Error> {
+        let mut scale = BTreeMap::new();
+        let mut previous = Duration::zero();
+        for (points, duration_str) in value {
+            let duration = parse_human_duration(&duration_str).ok_or_else(|| format!("invalid duration for {} points: {}", points, duration_str))?;
+            if duration <= previous {
+                return Err(format!("story point durations must be increasing: {} points = {}", points, duration_str));
+            }
+            scale.insert(points, duration);
+            previous = duration;
         }
+        Ok(Self(scale))
     }
 }
+
+#[test]
+fn test_story_point_scale() {
+    let scale = StoryPointScale::default();
+    let estimate = scale.estimate(5).unwrap();
+    assert_eq!(estimate.most_likely, Duration::hours(4));
+    assert_eq!(estimate.optimistic, Duration::hours(2));
+    assert_eq!(estimate.pessimistic, Duration::hours(8));
+
+    // 両端は自分自身の所要時間で補う
+    let estimate = scale.estimate(1).unwrap();
+    assert_eq!(estimate.optimistic, Duration::minutes(30));
+    assert_eq!(estimate.pessimistic, Duration::hours(1));
+    let estimate = scale.estimate(13).unwrap();
+    assert_eq!(estimate.pessimistic, Duration::hours(16));
+
+    assert!(scale.estimate(4).is_none());
+
+    let scale: StoryPointScale = serde_yaml::from_str("{ 1: 1h, 2: 3h }").unwrap();
+    assert_eq!(scale.estimate(2).unwrap().mean(), Duration::minutes((60 + 4 * 180 + 180) / 6));
+    assert!(serde_yaml::from_str::<StoryPointScale>("{ 1: 3h, 2: 1h }").is_err());
+}
+
+#[test]
+fn test_sub_saturates_at_zero() {
+    let small = Estimate::from_mop(Duration::minutes(30), Duration::minutes(20), Duration::minutes(60)).unwrap();
+    let large = Estimate::from_mop(Duration::hours(2), Duration::hours(1), Duration::hours(4)).unwrap();
+    let diff = small.clone() - large.clone();
+    assert_eq!((diff.optimistic, diff.most_likely, diff.pessimistic), (Duration::zero(), Duration::zero(), Duration::zero()));
+    assert_eq!(diff.mean(), Duration::zero());
+    assert_eq!(diff.stddev(), Duration::zero());
+
+    // 一部だけ残る場合も 楽観 ≤ 最尤 ≤ 悲観 を保つ
+    let diff = large - Estimate::from_mop(Duration::minutes(30), Duration::minutes(10), Duration::hours(4)).unwrap();
+    assert_eq!(
+        (diff.optimistic, diff.most_likely, diff.pessimistic),
+        (Duration::minutes(50), Duration::minutes(90), Duration::minutes(90))
+    );
+    assert!(diff.mean() >= Duration::zero());
+}
@@ -0,0 +1,143 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+/// .ics の VEVENT のうち、1 日の中で時刻の決まった予定
+#[derive(Debug, PartialEq, Eq)]
+pub struct IcsEvent {
+    pub date: NaiveDate,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub summary: Option<String>,
+}
+
+/// 読み取り中の VEVENT
+#[derive(Default)]
+struct PendingEvent {
+    start: Option<NaiveDateTime>,
+    end: Option<NaiveDateTime>,
+    summary: Option<String>,
+    all_day: bool,
+}
+
+/// .ics のテキストから VEVENT の DTSTART / DTEND / SUMMARY を読み取る。
+///
+/// 終日の予定・日をまたぐ予定・DTEND のない予定は取り込まない (戻り値の 2 つ目に件数を返す)。
+/// TZID 付きの時刻はローカル時刻、末尾 `Z` の時刻は UTC からローカル時刻に変換して扱う。
+pub fn parse_events(text: &str) -> Result<(Vec<IcsEvent>, usize), String> {
+    let mut events = vec![];
+    let mut skipped = 0;
+    let mut current: Option<PendingEvent> = None;
+    for line in unfold_lines(text) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // `DTSTART;TZID=Asia/Tokyo` のようなパラメータを分ける
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match (name, &mut current) {
+            ("BEGIN", None) if value == "VEVENT" => current = Some(PendingEvent::default()),
+            ("END", Some(_)) if value == "VEVENT" => {
+                let Some(PendingEvent { start, end, summary, all_day }) = current.take() else {
+                    unreachable!();
+                };
+                if all_day {
+                    skipped += 1;
+                    continue;
+                }
+                let Some(start) = start else {
+                    return Err(format!("DTSTART のない予定があります: {}", summary.unwrap_or_default()));
+                };
+                // 日をまたぐ予定、DTEND のない (DURATION で長さを指定した) 予定も対象外
+                let Some(end) = end.filter(|end| end.date() == start.date() && start < *end) else {
+                    skipped += 1;
+                    continue;
+                };
+                events.push(IcsEvent {
+                    date: start.date(),
+                    start: start.time(),
+                    end: end.time(),
+                    summary,
+                });
+            }
+            ("DTSTART" | "DTEND", Some(event)) => {
+                if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") {
+                    event.all_day = true;
+                    continue;
+                }
+                let datetime = Some(parse_datetime(value)?);
+                if name == "DTSTART" {
+                    event.start = datetime;
+                } else {
+                    event.end = datetime;
+                }
+            }
+            ("SUMMARY", Some(event)) => event.summary = Some(unescape(value)),
+            _ => {}
+        }
+    }
+    Ok((events, skipped))
+}
+
+/// 折り返された行 (先頭が空白の行) を前の行につなげる
+fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_datetime(value: &str) -> Result<NaiveDateTime, String> {
+    let invalid = || format!("日時を解釈できません: {}", value);
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+            Ok(Utc.from_utc_datetime(&utc).with_timezone(&Local).naive_local())
+        }
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid()),
+    }
+}
+
+fn unescape(value: &str) -> String {
+    value.replace("\\n", " ").replace("\\N", " ").replace("\\,", ",").replace("\\;", ";").replace("\\\\", "\\")
+}
+
+#[test]
+fn test_parse_events() {
+    let ics = [
+        "BEGIN:VCALENDAR",
+        "BEGIN:VEVENT",
+        "DTSTART;TZID=Asia/Tokyo:20250507T100000",
+        "DTEND;TZID=Asia/Tokyo:20250507T110000",
+        "SUMMARY:チーム",
+        " MTG\\, 定例",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "DTSTART;VALUE=DATE:20250508",
+        "DTEND;VALUE=DATE:20250509",
+        "SUMMARY:終日",
+        "END:VEVENT",
+        "BEGIN:VEVENT",
+        "DTSTART:20250508T230000",
+        "DTEND:20250509T010000",
+        "END:VEVENT",
+        "END:VCALENDAR",
+    ]
+    .join("\r\n");
+    let (events, skipped) = parse_events(&ics).unwrap();
+    assert_eq!(
+        events,
+        vec![IcsEvent {
+            date: NaiveDate::from_ymd_opt(2025, 5, 7).unwrap(),
+            start: NaiveTime::from_hms_opt(10, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+            summary: Some("チームMTG, 定例".to_string()),
+        }]
+    );
+    // 終日の予定と日をまたぐ予定は取り込まない
+    assert_eq!(skipped, 2);
+
+    assert_eq!(parse_events("BEGIN:VEVENT\nDTSTART:20250507T100000\nEND:VEVENT\n").unwrap().1, 1);
+    assert!(parse_events("BEGIN:VEVENT\nDTEND:20250507T100000\nEND:VEVENT\n").is_err());
+}
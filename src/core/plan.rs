@@ -0,0 +1,134 @@
+use super::{slot::SlotMap, task::TaskID, work_log::WorkLog};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 予定との差 (予定より先行/遅延) を判定するための、日ごとの計画の基準値。
+/// その日最初にスケジュールしたときの割り当て (+ それまでの実績) を記録し、以降は変更しない。
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PlanBaseline {
+    days: BTreeMap<NaiveDate, BTreeMap<TaskID, Duration>>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl PlanBaseline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// `date` の計画がまだなければ、スケジュール結果とその日の実績から記録する
+    pub fn record_day(&mut self, date: NaiveDate, slots: &SlotMap, log: &WorkLog) {
+        if self.days.contains_key(&date) {
+            return;
+        }
+        let mut planned = slots.get(&date).clone();
+        for item in log.get_items(date).into_iter().flatten() {
+            *planned.entry(item.task_id).or_insert_with(Duration::zero) += item.duration;
+        }
+        self.days.insert(date, planned);
+        self.dirty = true;
+    }
+
+    /// `date` の計画と実績を突き合わせ、計画のあったタスクごとに (予定, 実績) を返す。
+    /// その日の計画が記録されていなければ `None`。
+    pub fn reconcile_day(&self, date: NaiveDate, log: &WorkLog) -> Option<Vec<(TaskID, Duration, Duration)>> {
+        let planned = self.days.get(&date)?;
+        let items = log.get_items(date).map(Vec::as_slice).unwrap_or_default();
+        let reconciled = planned
+            .iter()
+            .map(|(task_id, planned)| {
+                let actual = items.iter().filter(|item| item.task_id == *task_id).map(|item| item.duration).sum();
+                (*task_id, *planned, actual)
+            })
+            .collect();
+        Some(reconciled)
+    }
+
+    /// `now` までに予定していた作業時間と、同じ期間の実績を返す。
+    /// 当日分は勤務時間 (`working_time`) の経過割合で按分する。計画のないタスクは `None`。
+    pub fn planned_and_actual(&self, task_id: TaskID, now: NaiveDateTime, working_time: (NaiveTime, NaiveTime), log: &WorkLog) -> Option<(Duration, Duration)> {
+        let mut planned = Duration::zero();
+        let mut actual = Duration::zero();
+        let mut found = false;
+        for (date, tasks) in self.days.range(..=now.date()) {
+            let Some(&day_planned) = tasks.get(&task_id) else {
+                continue;
+            };
+            found = true;
+            planned += if *date < now.date() {
+                day_planned
+            } else {
+                let (start, end) = working_time;
+                let elapsed = (now.time().clamp(start, end) - start).num_seconds();
+                let total = (end - start).num_seconds().max(1);
+                day_planned * elapsed as i32 / total as i32
+            };
+            actual += log
+                .get_items(*date)
+                .into_iter()
+                .flatten()
+                .filter(|item| item.task_id == task_id)
+                .map(|item| item.duration)
+                .sum::<Duration>();
+        }
+        found.then_some((planned, actual))
+    }
+}
+
+#[test]
+fn test_planned_and_actual() {
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let d2 = NaiveDate::from_ymd_opt(2025, 5, 8).unwrap();
+    let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+    let working_time = (nine, NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let task_id = TaskID::new();
+    let other_id = TaskID::new();
+
+    let mut log = WorkLog::new();
+    // 計画記録前の実績も当日の計画に含める
+    log.add_item(d1, task_id, nine, Duration::minutes(30));
+    let mut slots = SlotMap::new();
+    slots.add(d1, task_id, Duration::minutes(90));
+    slots.add(d2, task_id, Duration::hours(2));
+    let mut baseline = PlanBaseline::new();
+    baseline.record_day(d1, &slots, &log);
+    // 2回目以降の記録では上書きしない
+    baseline.record_day(d1, &SlotMap::new(), &log);
+    baseline.record_day(d2, &slots, &log);
+    log.add_item(d1, task_id, NaiveTime::from_hms_opt(13, 0, 0).unwrap(), Duration::minutes(60));
+    log.add_item(d2, task_id, nine, Duration::minutes(30));
+
+    // 5/8 の 13:00 時点: 5/7 の 2h + 5/8 の 2h の半分 = 3h 予定、実績 2h
+    let now = d2.and_hms_opt(13, 0, 0).unwrap();
+    assert_eq!(baseline.planned_and_actual(task_id, now, working_time, &log), Some((Duration::hours(3), Duration::hours(2))));
+    assert_eq!(baseline.planned_and_actual(other_id, now, working_time, &log), None);
+}
+
+#[test]
+fn test_reconcile_day() {
+    let date = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+    let done_id = TaskID::new();
+    let behind_id = TaskID::new();
+
+    let mut slots = SlotMap::new();
+    slots.add(date, done_id, Duration::minutes(30));
+    slots.add(date, behind_id, Duration::hours(2));
+    let mut log = WorkLog::new();
+    let mut baseline = PlanBaseline::new();
+    assert_eq!(baseline.reconcile_day(date, &log), None);
+    baseline.record_day(date, &slots, &log);
+    log.add_item(date, done_id, nine, Duration::minutes(30));
+    log.add_item(date, behind_id, nine, Duration::minutes(45));
+
+    let mut reconciled = baseline.reconcile_day(date, &log).unwrap();
+    reconciled.sort_by_key(|(_, planned, _)| *planned);
+    assert_eq!(
+        reconciled,
+        vec![(done_id, Duration::minutes(30), Duration::minutes(30)), (behind_id, Duration::hours(2), Duration::minutes(45))]
+    );
+}
@@ -1,11 +1,13 @@
 use super::{
-    calendar::Calendar,
+    calendar::{Calendar, ScheduleItem},
     slot::SlotMap,
     task::{Task, TaskID, TaskStatus},
 };
 use crate::core::{deadline::Deadline, utils::format_human_duration};
-use chrono::{Duration, NaiveDateTime, NaiveTime};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Deserialize;
 use std::{
+    borrow::Cow,
     cmp::Reverse,
     collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet},
 };
@@ -40,13 +42,11 @@ fn compute_earliest_start_map(
         buffer,
     };
 
-    // 再帰的に個々のタスクの着手可能時刻を求める
-    fn dfs(task_id: &TaskID, ctx: &Context, memo: &mut HashMap<TaskID, NaiveDateTime>) -> NaiveDateTime {
-        if let Some(&t) = memo.get(task_id) {
-            return t; // メモにあればそれを返す
-        }
+    // 個々のタスクの着手可能時刻を、依存先を先に求めてから計算する
+    fn compute(task_id: &TaskID, ctx: &Context, memo: &HashMap<TaskID, NaiveDateTime>) -> NaiveDateTime {
         let task = &ctx.tasks[task_id];
-        let mut earliest = ctx.now;
+        // 着手を先送りしたタスクは、その日時より前には割り当てない
+        let mut earliest = task.not_before.map_or(ctx.now, |not_before| ctx.now.max(not_before));
         if let TaskStatus::Blocked(bs) = task.status() {
             // 1) 外部ブロッキング解除時刻
             // ExternalBlockingReason の may_unblock_at を解決して最大値を取る
@@ -56,80 +56,161 @@ fn compute_earliest_start_map(
                 };
                 earliest = earliest.max(unblock_time);
             }
-            // 2) 依存タスクの完了時刻 or 再帰的着手可能時刻
+            // 2) 依存タスクの完了時刻 or 着手可能時刻
             for dep_task_id in &bs.tasks {
                 // dep タスクが完了していればその完了日時、それ以外は
-                // 再帰的に「そのタスクが着手可能になる時刻」を使う
+                // 「そのタスクが着手可能になる時刻」を使う
                 let dep_task = &ctx.tasks[dep_task_id];
                 let unblock_time = match dep_task.status() {
                     TaskStatus::Completed(dt) => *dt,
                     _ => {
+                        // 循環している依存先はまだ計算されていないので無視する
+                        let Some(&dep_start) = memo.get(dep_task_id) else {
+                            continue;
+                        };
                         // まだ終わっていない依存タスクは、着手可能時刻 + 残作業時間をカレンダー＋労働時間でシミュレート
-                        let dep_start = dfs(dep_task_id, ctx, memo);
-                        project_finish(dep_start, dep_task.remaining(), ctx.calendar, ctx.work_tick, ctx.buffer)
+                        // 親タスクへの依存は、子タスクを含めた残り時間で見積もる
+                        project_finish(dep_start, dep_task.rolled_up_remaining(ctx.tasks), ctx.calendar, ctx.work_tick, ctx.buffer)
                     }
                 };
                 earliest = earliest.max(unblock_time);
             }
         }
-        memo.insert(*task_id, earliest);
         earliest
     }
+    let dependencies = |id: TaskID| match tasks[&id].status() {
+        TaskStatus::Blocked(bs) => bs.tasks.clone(),
+        _ => vec![],
+    };
 
-    for id in tasks.keys() {
-        dfs(id, &context, &mut earliest);
-        println!("earliest[{}] = {}", id, earliest[id]);
+    let mut visited = HashSet::new();
+    for &id in tasks.keys() {
+        visit_post_order(id, dependencies, &mut visited, |id| {
+            let start = compute(&id, &context, &earliest);
+            earliest.insert(id, start);
+        });
     }
     earliest
 }
 
+/// 時刻指定 (`fixed_at`) のタスクを、残り時間分の予定としてカレンダーに書き込む。
+/// 書き込んだタスクの割り当ても返す。既に過ぎた時刻のタスクは通常のタスクとして扱う。
+/// 時刻指定のタスクがなければ、カレンダーは複製せずにそのまま借りる
+fn inject_fixed_tasks<'a>(calendar: &'a Calendar, tasks: &BTreeMap<TaskID, Task>, now: NaiveDateTime) -> (Cow<'a, Calendar>, SlotMap) {
+    let mut calendar = Cow::Borrowed(calendar);
+    let mut slots = SlotMap::new();
+    for task in tasks.values().filter(|t| !t.is_completed() && !t.is_dropped()) {
+        let Some(fixed_at) = task.fixed_at.filter(|at| *at >= now) else {
+            continue;
+        };
+        let duration = task.remaining();
+        let item = ScheduleItem {
+            start: fixed_at.time(),
+            duration,
+            note: Some(format!("📌 {}", task.title)),
+        };
+        calendar.to_mut().add_scheduled_item(&fixed_at.date(), item);
+        slots.add(fixed_at.date(), task.id, duration);
+    }
+    (calendar, slots)
+}
+
+/// `root` から `edges` をたどって到達できるノードを、たどった先から順に (後順で) `visit` する。
+/// 再帰の代わりに明示的なスタックを使うので、長い依存チェーンでもスタックオーバーフローしない。
+/// `visited` に含まれるノードはたどらない。循環がある場合は、循環を閉じる辺を無視する。
+fn visit_post_order<I>(root: TaskID, edges: impl Fn(TaskID) -> I, visited: &mut HashSet<TaskID>, mut visit: impl FnMut(TaskID))
+where
+    I: IntoIterator<Item = TaskID>,
+{
+    let mut stack = vec![(root, false)];
+    while let Some((id, expanded)) = stack.pop() {
+        if expanded {
+            visit(id);
+            continue;
+        }
+        if !visited.insert(id) {
+            continue;
+        }
+        stack.push((id, true));
+        stack.extend(edges(id).into_iter().filter(|next| !visited.contains(next)).map(|next| (next, false)));
+    }
+}
+
+/// 各タスクの期限をカレンダーで解決した結果 (スケジュール 1 回分のキャッシュ)
+type DeadlineMap = HashMap<TaskID, Option<NaiveDateTime>>;
+
+/// 全タスクの期限を一度だけ解決する。
+/// 曖昧な期限の解決は稼働日をたどるため、スケジュール中に繰り返し呼ばずにこの結果を参照する。
+fn resolve_deadline_map(tasks: &BTreeMap<TaskID, Task>, calendar: &Calendar, default_time: NaiveTime) -> DeadlineMap {
+    tasks
+        .iter()
+        .map(|(&id, task)| (id, task.resolve_deadline(calendar, default_time).expect("カレンダーで解決失敗")))
+        .collect()
+}
+
 /// 全タスクの「最遅開始時刻」を計算する
 fn compute_latest_start_map(
     tasks: &BTreeMap<TaskID, Task>,
     rev_graph: &HashMap<TaskID, Vec<TaskID>>,
+    deadlines: &DeadlineMap,
     calendar: &Calendar,
-    default_time: NaiveTime,
     work_tick: Duration,
     buffer: Duration,
 ) -> HashMap<TaskID, NaiveDateTime> {
     // 締切を起点に、後ろ向きに propagate
     let mut latest: HashMap<_, NaiveDateTime> = HashMap::new();
+    // 子タスクを持つ親タスクの残り時間は子タスクの合計で見る
+    let remaining = |id: TaskID| tasks[&id].rolled_up_remaining(tasks);
 
     // 1) 末端（explicit deadline があるもの）はまず埋める
-    for (&id, task) in tasks {
-        if let Some(dl_dt) = task.deadline.resolve_with_calendar(calendar, default_time).expect("カレンダーで解決失敗") {
+    for &id in tasks.keys() {
+        if let Some(dl_dt) = inherited_deadline(id, tasks, deadlines) {
             // 締切時刻から逆シミュレートして開始時刻を算出
-            latest.insert(id, project_start_before(dl_dt, task.remaining(), calendar, work_tick, buffer));
-        }
-    }
-    // 2) 逆トポロジカル順で伝播
-    fn dfs(id: TaskID, tasks: &BTreeMap<TaskID, Task>, rev: &HashMap<TaskID, Vec<TaskID>>, latest: &mut HashMap<TaskID, NaiveDateTime>, calendar: &Calendar, work_tick: Duration, buffer: Duration) {
-        if latest.contains_key(&id) {
-            return;
-        }
-        // 子ノードを先に処理
-        if let Some(children) = rev.get(&id) {
-            for &ch in children {
-                dfs(ch, tasks, rev, latest, calendar, work_tick, buffer)
-            }
-            // 子タスクの earliest 最新を取る
-            let min_child = children.iter().filter_map(|&ch| latest.get(&ch)).cloned().min().unwrap();
-            // 自分の残作業から逆算
-            let start = project_start_before(min_child, tasks[&id].remaining(), calendar, work_tick, buffer);
-            latest.insert(id, start);
-        } else {
-            // 締切なし＆子もない → カレンダーの最大値を入れる
-            let last_window = calendar.time_windows_rev(NaiveDateTime::MAX).find(|w| w.available()).unwrap();
-            let start = last_window.end - tasks[&id].remaining();
-            latest.insert(id, last_window.date.and_time(start));
+            latest.insert(id, project_start_before(dl_dt, remaining(id), calendar, work_tick, buffer));
         }
     }
+    // 2) 逆トポロジカル順で伝播 (締切のあるタスクは計算済みなのでたどらない)
+    let children = |id: TaskID| rev_graph.get(&id).cloned().unwrap_or_default();
+    let mut visited: HashSet<TaskID> = latest.keys().cloned().collect();
     for &id in tasks.keys() {
-        dfs(id, tasks, rev_graph, &mut latest, calendar, work_tick, buffer);
+        visit_post_order(id, children, &mut visited, |id| {
+            // 子タスクの最遅開始の最も早いものから、自分の残作業を逆算
+            // (循環していて未計算の子は無視する)
+            let min_child = rev_graph.get(&id).and_then(|children| children.iter().filter_map(|ch| latest.get(ch)).min().cloned());
+            let start = match min_child {
+                Some(min_child) => project_start_before(min_child, remaining(id), calendar, work_tick, buffer),
+                None => {
+                    // 締切なし＆子もない → カレンダーの最大値を入れる
+                    let last_window = calendar.time_windows_rev(NaiveDateTime::MAX).find(|w| w.available()).unwrap();
+                    let start = last_window.end - remaining(id);
+                    last_window.date.and_time(start)
+                }
+            };
+            latest.insert(id, start);
+        });
     }
     latest
 }
 
+/// 自分と親タスク (祖先) の期限のうち最も早いもの。サブタスクは親の期限までに終わらせる
+fn inherited_deadline(id: TaskID, tasks: &BTreeMap<TaskID, Task>, deadlines: &DeadlineMap) -> Option<NaiveDateTime> {
+    let mut deadline = deadlines[&id];
+    let mut seen = HashSet::from([id]);
+    let mut parent = tasks[&id].parent;
+    // 親が循環していても止まるよう、たどった親は記録する
+    while let Some(parent_id) = parent.filter(|parent_id| seen.insert(*parent_id)) {
+        let Some(parent_task) = tasks.get(&parent_id) else {
+            break;
+        };
+        deadline = match (deadline, deadlines[&parent_id]) {
+            (Some(own), Some(inherited)) => Some(own.min(inherited)),
+            (own, inherited) => own.or(inherited),
+        };
+        parent = parent_task.parent;
+    }
+    deadline
+}
+
 /// タスクの逆依存グラフを構築する
 /// dep -> Vec<dependent>
 pub fn build_rev_graph(tasks: &BTreeMap<TaskID, Task>) -> HashMap<TaskID, Vec<TaskID>> {
@@ -148,30 +229,23 @@ pub fn build_rev_graph(tasks: &BTreeMap<TaskID, Task>) -> HashMap<TaskID, Vec<Ta
 pub fn compute_dependents_map(tasks: &BTreeMap<TaskID, Task>, rev_graph: &HashMap<TaskID, Vec<TaskID>>) -> HashMap<TaskID, usize> {
     // ID ごとに「下流ノード集合」を記憶するメモ
     let mut memo: HashMap<TaskID, HashSet<TaskID>> = HashMap::new();
-
-    fn dfs(id: TaskID, rev: &HashMap<TaskID, Vec<TaskID>>, memo: &mut HashMap<TaskID, HashSet<TaskID>>) -> HashSet<TaskID> {
-        if let Some(cached) = memo.get(&id) {
-            return cached.clone();
-        }
-        memo.insert(id, HashSet::new()); // サイクル防御
-        let mut all = HashSet::new();
-        if let Some(children) = rev.get(&id) {
-            for &ch in children {
+    let children = |id: TaskID| rev_graph.get(&id).cloned().unwrap_or_default();
+    let mut visited = HashSet::new();
+    for &id in tasks.keys() {
+        visit_post_order(id, children, &mut visited, |id| {
+            let mut all = HashSet::new();
+            for &ch in rev_graph.get(&id).into_iter().flatten() {
                 all.insert(ch);
-                all.extend(dfs(ch, rev, memo));
+                // 循環していて未計算の子は、子自身だけを数える
+                if let Some(downstream) = memo.get(&ch) {
+                    all.extend(downstream);
+                }
             }
-        }
-        memo.insert(id, all.clone());
-        all
+            memo.insert(id, all);
+        });
     }
 
-    tasks
-        .keys()
-        .map(|&id| {
-            let deps = dfs(id, rev_graph, &mut memo);
-            (id, deps.len())
-        })
-        .collect()
+    tasks.keys().map(|id| (*id, memo[id].len())).collect()
 }
 
 /// start: 着手可能時刻
@@ -275,8 +349,8 @@ struct ScheduleContext<'a> {
     now: NaiveDateTime,
     /// タスクマップ
     tasks: &'a BTreeMap<TaskID, Task>,
-    /// カレンダー
-    calendar: &'a Calendar,
+    /// 時刻指定のタスクを予定として書き込んだカレンダー (なければ元のカレンダーを借りる)
+    calendar: Cow<'a, Calendar>,
     /// 各タスクの着手可能時刻
     earliest: HashMap<TaskID, NaiveDateTime>,
     /// 各タスクの着手可能時刻（最遅）
@@ -292,6 +366,10 @@ struct ScheduleContext<'a> {
     /// 各タスクのリスク（平均・標準偏差）
     risk_map: HashMap<TaskID, (f64, f64)>,
     working_time: (NaiveTime, NaiveTime),
+    /// 優先度スコアの重み
+    weights: PriorityWeights,
+    /// 割り当てを丸める単位
+    granularity: Duration,
 
     /// スロットマップ
     slots: SlotMap,
@@ -302,12 +380,14 @@ struct ScheduleContext<'a> {
 impl<'a> ScheduleContext<'a> {
     /// 各タスクの「残り作業時間」を、(1日の勤務時間) で割って
     /// 必要な日数（端数は切り上げ）を f64 で返す。
+    /// 子タスクを持つ親タスクは、作業を子タスク側で割り当てるため 0 日とする。
     fn compute_need_days_map(tasks: &BTreeMap<TaskID, Task>, daily_minutes: f64) -> HashMap<TaskID, f64> {
         let mut map = HashMap::new();
+        let parents: HashSet<TaskID> = tasks.values().filter(|t| !t.is_dropped()).filter_map(|t| t.parent).collect();
 
         for (&id, task) in tasks.iter() {
             // まず残り時間（分）を取得
-            let rem_min = task.remaining().num_minutes() as f64;
+            let rem_min = if parents.contains(&id) { 0.0 } else { task.remaining().num_minutes() as f64 };
             // 0分以下なら 0 日
             let need_days = if rem_min <= 0.0 {
                 0.0
@@ -321,24 +401,40 @@ impl<'a> ScheduleContext<'a> {
         map
     }
 
-    fn build(now: NaiveDateTime, tasks: &'a BTreeMap<TaskID, Task>, calendar: &'a Calendar, working_time: &(NaiveTime, NaiveTime), work_tick: Duration, buffer_time: Duration) -> Self {
+    fn build(now: NaiveDateTime, tasks: &'a BTreeMap<TaskID, Task>, calendar: &'a Calendar, scheduler: &Scheduler) -> Self {
+        let working_time = &scheduler.working_time;
+        let (default_deadline_time, work_tick, buffer_time) = (scheduler.default_deadline_time, scheduler.work_tick, scheduler.buffer_time);
+        // 時刻指定のタスクはカレンダーの予定と同じく、その時間帯を埋める
+        let (calendar, fixed_slots) = inject_fixed_tasks(calendar, tasks, now);
         // 前準備：着手可能時刻・必要日数・依存度・リスクを一度計算
         let daily_minutes = (working_time.1 - working_time.0).num_minutes() as f64;
-        let now = calendar.official_workdays(now.date()).next().cloned().unwrap_or(now.date()).and_time(working_time.0);
+        // 今日の勤務時間中なら今から (記録済みの午前中を計画し直さない)、それ以外は次の稼働日の始業から
+        let now = match calendar.official_workdays(now.date()).next() {
+            Some(&date) if date == now.date() => now.max(date.and_time(working_time.0)),
+            Some(&date) => date.and_time(working_time.0),
+            None => now,
+        };
         let need = Self::compute_need_days_map(tasks, daily_minutes);
         let rev_graph = build_rev_graph(tasks);
-        let earliest = compute_earliest_start_map(tasks, calendar, now, working_time.0, work_tick, buffer_time);
-        let latest = compute_latest_start_map(tasks, &rev_graph, calendar, working_time.0, work_tick, buffer_time);
+        let earliest = compute_earliest_start_map(tasks, &calendar, now, default_deadline_time, work_tick, buffer_time);
+        let deadlines = resolve_deadline_map(tasks, &calendar, default_deadline_time);
+        let latest = compute_latest_start_map(tasks, &rev_graph, &deadlines, &calendar, work_tick, buffer_time);
         let dep_map = compute_dependents_map(tasks, &rev_graph);
         let max_dep = dep_map.values().cloned().fold(0, usize::max).max(1) as f64;
         let risk_map: HashMap<_, (f64, f64)> = tasks
             .iter()
             .map(|(&id, t)| {
                 let (m, s) = t.estimate().map(|e| (e.mean().num_minutes() as f64, e.stddev().num_minutes() as f64)).unwrap_or((0.0, 0.0));
+                // 長く見直していない見積ほど外れやすいとみなして幅を広げる
+                let s = s * scheduler.estimate_decay.factor(now - t.estimate_updated_at());
                 (id, (m, s))
             })
             .collect();
-        let remaining_minutes = need.iter().map(|(&id, &days)| ((id), (days * daily_minutes).ceil() as i64)).collect::<HashMap<_, _>>();
+        let mut remaining_minutes = need.iter().map(|(&id, &days)| ((id), (days * daily_minutes).ceil() as i64)).collect::<HashMap<_, _>>();
+        // 時刻指定のタスクは割り当て済みなので、空き時間には割り当てない
+        for task_id in tasks.keys().filter(|id| !fixed_slots.total(id).is_zero()) {
+            remaining_minutes.insert(*task_id, 0);
+        }
         let mut slots = SlotMap::new();
 
         Self {
@@ -353,8 +449,10 @@ impl<'a> ScheduleContext<'a> {
             max_dep,
             risk_map,
             working_time: *working_time,
+            weights: scheduler.weights,
+            granularity: scheduler.granularity,
             daily_minutes,
-            slots: SlotMap::new(),
+            slots: fixed_slots,
             remaining_minutes,
         }
     }
@@ -378,7 +476,7 @@ impl<'a> ScheduleContext<'a> {
     }
 
     /// タスクの優先度を計算する
-    fn calc_priority_score(&self, id: &TaskID, cursor: &NaiveDateTime, max_slack: f64) -> (f64, f64) {
+    fn calc_priority_score(&self, id: &TaskID, cursor: &NaiveDateTime, max_slack: f64) -> f64 {
         // 1) 依存度
         let d_score = self.dep_map.get(id).cloned().unwrap_or(0) as f64 / self.max_dep;
         // 2) リスク
@@ -387,12 +485,20 @@ impl<'a> ScheduleContext<'a> {
         // 3) 緊急度
         let slack = (self.latest[id] - *cursor).num_minutes() as f64 / self.daily_minutes;
         let urgency = if slack.is_finite() { (1.0 - (slack / max_slack)).clamp(0.001, 1.0) } else { 0.0 };
-        (urgency, 0.7 * r_score + 0.3 * d_score)
+        let score = self.weights.urgency * urgency + self.weights.risk * r_score + self.weights.dependents * d_score;
+        // 4) 緩い依存: 先にやりたいタスクが今割り当てられるなら、そちらを優先する (スコアは 0〜1 なので 1 下げれば必ず後回し)。
+        //    先行タスクが着手できないうちは下げないので、後続が止まることはない
+        let prefers_to_wait = self.tasks[id]
+            .prefer_after
+            .iter()
+            .any(|before| self.remaining_minutes.get(before).is_some_and(|&m| m > 0) && self.earliest[before] <= *cursor);
+        if prefers_to_wait { score - 1.0 } else { score }
     }
 
     /// タスクをスロットに割り当てる
     fn allocate(&mut self, task_id: &TaskID, work_tick: &Duration, cursor: &NaiveDateTime, capacity: &Duration) -> Duration {
-        let alloc = Duration::minutes(self.remaining_minutes[task_id]).min(*work_tick).min(*capacity);
+        let limit = Duration::minutes(self.remaining_minutes[task_id]).min(*work_tick).min(*capacity);
+        let alloc = round_allocation(limit, self.granularity);
         self.slots.add(cursor.date(), *task_id, alloc);
         self.remaining_minutes.entry(*task_id).and_modify(|m| *m = (*m - alloc.num_minutes()).max(0));
         alloc
@@ -409,71 +515,289 @@ impl<'a> ScheduleContext<'a> {
     }
 }
 
+/// 割り当てを `granularity` の倍数に揃える (計画の時刻を切りのよい値にするため)。
+/// `limit` (残り時間・work_tick・ウィンドウの空きのうち最小) を超えないよう、収まる倍数まで切り下げる
+fn round_allocation(limit: Duration, granularity: Duration) -> Duration {
+    let unit = granularity.num_minutes();
+    if unit <= 1 || limit.is_zero() {
+        return limit;
+    }
+    let rounded_down = Duration::minutes(limit.num_minutes() / unit * unit);
+    // 上限が 1 単位に満たなければ (端数の残り時間など) 丸めずにそのまま割り当てる
+    if rounded_down.is_zero() { limit } else { rounded_down }
+}
+
+/// 見積の鮮度による不確かさの補正。
+///
+/// 最後に見積もってから `after_days` 日を過ぎた見積は、30 日ごとに標準偏差を `per_month` 倍ずつ広げて
+/// リスクを計算する (最大 `max_factor` 倍)。保存している見積そのものは変えない
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct EstimateDecay {
+    pub after_days: i64,
+    pub per_month: f64,
+    pub max_factor: f64,
+}
+impl Default for EstimateDecay {
+    fn default() -> Self {
+        Self {
+            after_days: 30,
+            per_month: 0.25,
+            max_factor: 2.0,
+        }
+    }
+}
+impl EstimateDecay {
+    /// 見積からの経過時間に対する標準偏差の倍率
+    pub fn factor(&self, age: Duration) -> f64 {
+        let stale_days = age.num_days() - self.after_days;
+        if stale_days <= 0 {
+            return 1.0;
+        }
+        (1.0 + self.per_month * stale_days as f64 / 30.0).min(self.max_factor)
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        let finite = self.per_month.is_finite() && self.max_factor.is_finite();
+        if self.after_days < 0 || !finite || self.per_month < 0.0 || self.max_factor < 1.0 {
+            return Err("estimate_decay は after_days >= 0, per_month >= 0, max_factor >= 1 で指定してください".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 優先度スコアの重み (緊急度・リスク・依存度)。合計は 1
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct PriorityWeights {
+    /// 緊急度 (最遅開始までの余裕のなさ)
+    pub urgency: f64,
+    /// リスク (見積の標準偏差 / 平均)
+    pub risk: f64,
+    /// 依存度 (後続タスクの数)
+    pub dependents: f64,
+}
+impl Default for PriorityWeights {
+    fn default() -> Self {
+        Self {
+            urgency: 0.6,
+            risk: 0.28,
+            dependents: 0.12,
+        }
+    }
+}
+impl PriorityWeights {
+    /// 各重みが 0 以上で、合計が 1 になっているか確認する
+    pub fn validate(&self) -> Result<(), String> {
+        let weights = [self.urgency, self.risk, self.dependents];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err("優先度の重みは 0 以上で指定してください".to_string());
+        }
+        let sum: f64 = weights.iter().sum();
+        if (sum - 1.0).abs() > 1e-6 {
+            return Err(format!("優先度の重みの合計は 1 にしてください (現在: {})", sum));
+        }
+        Ok(())
+    }
+}
+
+/// 空き時間ごとに、どのタスクへ割り当てるかの決め方
+///
+/// settings.yaml の `schedule_strategy` に `greedy` / `fair` / `fair:<k>` の形で記述する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Strategy {
+    /// 毎回いちばん優先度の高いタスクに割り当てる (1 つのタスクが何日も占めることがある)
+    Greedy,
+    /// 優先度の上位 `top_k` 件のうち、その日の割り当てが最も少ないタスクに割り当てる (交代で少しずつ進める)
+    Fair { top_k: usize },
+}
+impl Strategy {
+    /// `fair` とだけ書いたときに交代させる件数
+    pub const DEFAULT_TOP_K: usize = 3;
+
+    /// 着手可能なタスクとその優先度スコアから、割り当てるタスクを選ぶ
+    fn choose(&self, mut candidates: Vec<(f64, TaskID)>, slots: &SlotMap, date: NaiveDate) -> Option<(f64, TaskID)> {
+        // 同点なら先に並んだタスクを選ぶ (安定ソート)
+        candidates.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        let &(top_score, _) = candidates.first()?;
+        match self {
+            Strategy::Greedy => candidates.first().copied(),
+            // 緩い依存で後回しにしたタスク (スコアが負) は、他に候補がある限り交代に加えない
+            Strategy::Fair { top_k } => candidates
+                .iter()
+                .take(*top_k)
+                .filter(|(score, _)| *score >= 0.0 || top_score < 0.0)
+                .min_by_key(|(_, id)| slots.remaining_at(&date, *id).unwrap_or_else(Duration::zero))
+                .copied(),
+        }
+    }
+}
+impl TryFrom<String> for Strategy {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+impl std::str::FromStr for Strategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "greedy" => Ok(Strategy::Greedy),
+            None if s == "fair" => Ok(Strategy::Fair { top_k: Self::DEFAULT_TOP_K }),
+            Some(("fair", k)) => match k.parse() {
+                Ok(top_k) if top_k >= 2 => Ok(Strategy::Fair { top_k }),
+                _ => Err(format!("fair:<k> の k は 2 以上の整数で指定してください: {}", s)),
+            },
+            _ => Err(format!("greedy / fair / fair:<k> で指定してください: {}", s)),
+        }
+    }
+}
+impl std::fmt::Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strategy::Greedy => write!(f, "greedy"),
+            Strategy::Fair { top_k } => write!(f, "fair:{}", top_k),
+        }
+    }
+}
+
+/// 優先度計算の元になる、タスクごとの着手可能時刻・最遅開始時刻・余裕
+#[derive(Debug, Clone, Copy)]
+pub struct TaskTiming {
+    pub earliest: NaiveDateTime,
+    /// 最早開始から空き時間だけを使って作業した場合の完了時刻 (これより前には終わらない)
+    pub earliest_finish: NaiveDateTime,
+    pub latest: NaiveDateTime,
+    /// スケジュール開始時点から最遅開始までの余裕 (稼働日数)
+    pub slack_days: f64,
+}
+
 #[derive(Debug)]
 pub struct Scheduler {
     pub work_tick: Duration,
     pub buffer_time: Duration,
     pub working_time: (NaiveTime, NaiveTime),
+    /// 日付のみ・相対指定の期限を解決するときの既定時刻
+    pub default_deadline_time: NaiveTime,
+    /// 見積のない着手可能タスクがあればスケジュールせずにエラーにする
+    pub strict: bool,
+    /// 優先度スコアの重み
+    pub weights: PriorityWeights,
+    /// 割り当てを丸める単位 (1 分なら丸めない)
+    pub granularity: Duration,
+    /// 古い見積の標準偏差の広げ方
+    pub estimate_decay: EstimateDecay,
+    /// 今からこの期間より先には割り当てない (None なら期間の終わりまで)。遠い先の計画は立て直すので計算を省く
+    pub horizon: Option<Duration>,
+    /// これより短い空き時間には割り当てない (予定の合間の数分に細切れの作業を入れない)
+    pub min_window: Duration,
+    /// 割り当てるタスクの選び方
+    pub strategy: Strategy,
 }
 
 impl Scheduler {
+    /// 未完了 (着手可能・ブロック中) のタスクについて、スケジュールに使う時刻と余裕を返す
+    pub fn timeline(&self, now: NaiveDateTime, tasks: &BTreeMap<TaskID, Task>, calendar: &Calendar) -> BTreeMap<TaskID, TaskTiming> {
+        let context = ScheduleContext::build(now, tasks, calendar, self);
+        tasks
+            .values()
+            .filter(|t| t.is_ready() || t.is_blocked())
+            .map(|t| {
+                let work_tick = t.work_tick.unwrap_or(self.work_tick);
+                let timing = TaskTiming {
+                    earliest: context.earliest[&t.id],
+                    earliest_finish: project_finish(context.earliest[&t.id], t.rolled_up_remaining(tasks), &context.calendar, work_tick, self.buffer_time),
+                    latest: context.latest[&t.id],
+                    slack_days: context.calc_slack(&t.id, &context.now),
+                };
+                (t.id, timing)
+            })
+            .collect()
+    }
+    /// 1 つのタスクの最短の完了見込み (依存タスク・ブロックの解除を待ってから残りを作業した場合)。
+    /// `timeline` と違い、他のタスクの完了見込みは計算しない。未完了でなければ None
+    pub fn earliest_finish(&self, now: NaiveDateTime, task_id: &TaskID, tasks: &BTreeMap<TaskID, Task>, calendar: &Calendar) -> Option<NaiveDateTime> {
+        let task = tasks.get(task_id).filter(|t| t.is_ready() || t.is_blocked())?;
+        let (calendar, _) = inject_fixed_tasks(calendar, tasks, now);
+        let earliest = compute_earliest_start_map(tasks, &calendar, now, self.default_deadline_time, self.work_tick, self.buffer_time);
+        let work_tick = task.work_tick.unwrap_or(self.work_tick);
+        Some(project_finish(earliest[task_id], task.rolled_up_remaining(tasks), &calendar, work_tick, self.buffer_time))
+    }
     /// 依存・外部ブロック・締切・不確実性を考慮して
     /// 空きウィンドウにタスクを貪欲割当します。
     ///
     /// - `now`：現在日時
     /// - `tasks`：全タスクマップ
-    /// - `calendar`：公式稼働日カレンダー
-    pub fn schedule(&self, now: NaiveDateTime, tasks: &BTreeMap<TaskID, Task>, calendar: &Calendar) -> anyhow::Result<SlotMap> {
-        let mut context = ScheduleContext::build(now, tasks, calendar, &self.working_time, self.work_tick, self.buffer_time);
+    /// - `calendar`：稼働日と予定のカレンダー (時刻指定のタスクは、その時刻の予定として書き込んでから割り当てる)
+    /// - `active`：作業中のタスク。最初の空き時間に、他のタスクより先に割り当てる
+    pub fn schedule(&self, now: NaiveDateTime, tasks: &BTreeMap<TaskID, Task>, calendar: &Calendar, active: Option<TaskID>) -> anyhow::Result<SlotMap> {
+        if self.strict {
+            // 子タスクを持つ親タスクは子の見積で埋まるので対象外
+            let unestimated: Vec<_> = tasks.values().filter(|t| t.is_ready() && t.estimate().is_none() && !t.has_children(tasks)).collect();
+            if !unestimated.is_empty() {
+                let id_len = TaskID::shortest_unique_len(tasks.keys());
+                let list: Vec<_> = unestimated.iter().map(|t| format!("  {} {}", t.id.short(id_len), t.title)).collect();
+                anyhow::bail!("strict モードのため、見積のないタスクがあるとスケジュールできません:\n{}", list.join("\n"));
+            }
+        }
+        let mut context = ScheduleContext::build(now, tasks, calendar, self);
+
+        // 割り当てる残り時間のあるタスクがなければ、空き時間を順に見ても何も起きない
+        if context.find_first_allocatable_time(&NaiveDateTime::MIN, &NaiveDateTime::MAX).is_none() {
+            return Ok(context.slots);
+        }
+        let mut pinned = active.filter(|id| tasks.get(id).is_some_and(|t| t.is_startable(tasks)));
 
         // free windows ループ
-        for window in calendar.time_windows(now) {
+        let horizon_end = self.horizon.map(|horizon| now + horizon);
+        let windows: Vec<_> = context.calendar.time_windows(now).take_while(|w| horizon_end.is_none_or(|end| w.start_datetime() < end)).collect();
+        for window in windows {
             if !window.available() {
-                println!("{} {}-{}: {}", window.date, window.start.format("%H:%M"), window.end.format("%H:%M"), window.note());
                 continue;
             }
+            let window_end = horizon_end.map_or(window.end_datetime(), |end| end.min(window.end_datetime()));
             let mut cursor = window.start_datetime();
-            let mut capacity = window.end - window.start;
+            let mut capacity = window_end - cursor;
+            // 短い空き時間には割り当てない
+            if capacity < self.min_window {
+                continue;
+            }
 
-            // 量子ごとに動的プライオリティ再計算
-            while capacity > Duration::zero() {
+            // 量子ごとに動的プライオリティ再計算 (ウィンドウ末尾の短い残りにも割り当てない)
+            while capacity > Duration::zero() && capacity >= self.min_window {
+                // 作業中のタスクは、優先度にかかわらず最初の空き時間に割り当てる
+                let mut best = pinned.take().filter(|id| context.remaining_minutes[id] > 0).map(|id| (f64::INFINITY, id));
                 // (A) 現時刻で着手可能かつ未完了なタスクだけ取り出す
-                let mut best = None;
                 // 最大スラックの取得（動的再計算用）
                 let max_slack = context.calc_max_slack_on(&cursor);
+                let pinned_now = best.is_some();
 
-                for &id in tasks.keys() {
+                let mut candidates = Vec::new();
+                for &id in tasks.keys().filter(|_| !pinned_now) {
                     let already_done = context.remaining_minutes[&id] <= 0;
                     let cannot_start_yet = context.earliest[&id] > cursor;
                     if already_done || cannot_start_yet {
                         continue;
                     }
-                    let score = context.calc_priority_score(&id, &cursor, max_slack);
-                    if best.as_ref().is_none_or(|&(bs, _)| score > bs) {
-                        best = Some((score, id));
-                    }
+                    candidates.push((context.calc_priority_score(&id, &cursor, max_slack), id));
+                }
+                if !pinned_now {
+                    best = self.strategy.choose(candidates, &context.slots, cursor.date());
                 }
 
                 // 割り当て
                 if let Some((_, chosen)) = best {
                     // 割り当て可能なタスクがあれば、スロットに追加して、残り時間を減らし、時間を進める
-                    let alloc = context.allocate(&chosen, &self.work_tick, &cursor, &capacity);
-                    println!(
-                        "{} {}-{}: {} ({}分)",
-                        cursor.date(),
-                        cursor.time().format("%H:%M"),
-                        (cursor + alloc).time().format("%H:%M"),
-                        context.tasks[&chosen].title,
-                        alloc.num_minutes()
-                    );
+                    let work_tick = tasks[&chosen].work_tick.unwrap_or(self.work_tick);
+                    let alloc = context.allocate(&chosen, &work_tick, &cursor, &capacity);
+                    // ウィンドウ末尾ではバッファがはみ出すことがあるので、残りは 0 で止める
                     let consumed = alloc + self.buffer_time;
-                    capacity -= consumed;
+                    capacity = (capacity - consumed).max(Duration::zero());
                     cursor += consumed;
                 } else {
                     // 現時点で割り当て可能なタスクがない場合: 最速で着手可能なタスクの開始時刻がウィンドウ内にあれば、その時刻に移動
-                    if let Some(earliest_allocatable_time) = context.find_first_allocatable_time(&cursor, &window.end_datetime()) {
-                        capacity = window.end_datetime() - cursor;
+                    if let Some(earliest_allocatable_time) = context.find_first_allocatable_time(&cursor, &window_end) {
+                        // 残りは移動後の時刻から数える (移動前から数えるとウィンドウの外まで割り当ててしまう)
                         cursor = earliest_allocatable_time;
+                        capacity = window_end - cursor;
                         continue;
                     }
                     // ウィンドウ内に新しい候補がなければ終了
@@ -485,3 +809,576 @@ impl Scheduler {
         Ok(context.slots)
     }
 }
+
+/// テスト用のスケジューラ (25 分 + バッファ 5 分、丸めなし)。テストごとに変える項目は `..test_scheduler(working_time)` で上書きする
+#[cfg(test)]
+fn test_scheduler(working_time: (NaiveTime, NaiveTime)) -> Scheduler {
+    Scheduler {
+        work_tick: Duration::minutes(25),
+        buffer_time: Duration::minutes(5),
+        working_time,
+        default_deadline_time: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+        strict: false,
+        weights: PriorityWeights::default(),
+        granularity: Duration::minutes(1),
+        estimate_decay: EstimateDecay::default(),
+        horizon: None,
+        min_window: Duration::zero(),
+        strategy: Strategy::Greedy,
+    }
+}
+
+#[test]
+fn test_schedule_with_per_task_work_tick() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    // 9:00–9:55 の 55 分、割り当てごとに 5 分のバッファ
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(9, 55, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    calendar.add_working_day(d1, true);
+    calendar.add_working_day(d1.succ_opt().unwrap(), true);
+    let scheduler = test_scheduler(working_time);
+    let now = d1.and_time(working_time.0);
+    let estimated = |title: &str, minutes, work_tick: Option<i64>| {
+        let mut task = Task::new(title.to_string(), None, None);
+        task.update_remaining(Estimate::new(Duration::minutes(minutes)), now).unwrap();
+        task.work_tick = work_tick.map(Duration::minutes);
+        task
+    };
+
+    // 既定の 25 分刻みだと A は 25 分 + 5 分に分割され、増えたバッファの分だけ B が当日に収まらない
+    let a = estimated("A", 30, None);
+    let b = estimated("B", 20, Some(20));
+    let tasks: BTreeMap<_, _> = [(a.id, a.clone()), (b.id, b.clone())].into_iter().collect();
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1)[&a.id], Duration::minutes(30));
+    assert_eq!(slots.get(&d1)[&b.id], Duration::minutes(15));
+
+    // A を 30 分刻みにすると 1 回で割り当てられ、A・B とも当日に収まる
+    let a = estimated("A", 30, Some(30));
+    let tasks: BTreeMap<_, _> = [(a.id, a.clone()), (b.id, b.clone())].into_iter().collect();
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1)[&a.id], Duration::minutes(30));
+    assert_eq!(slots.get(&d1)[&b.id], Duration::minutes(20));
+}
+
+#[test]
+fn test_long_dependency_chain() {
+    use chrono::NaiveDate;
+
+    // 2000 段の依存チェーン (t[i] は t[i-1] にブロックされる)
+    let tasks: Vec<Task> = (0..2000).map(|i| Task::new(format!("T{}", i), None, None)).collect();
+    let ids: Vec<TaskID> = tasks.iter().map(|t| t.id).collect();
+    let mut tasks: BTreeMap<_, _> = tasks.into_iter().map(|t| (t.id, t)).collect();
+    for pair in ids.windows(2) {
+        tasks.get_mut(&pair[1]).unwrap().block_by_task(vec![pair[0]]);
+    }
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+    let mut calendar = Calendar::new(working_time);
+    let first_day = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+    for day in first_day.iter_days().take(60) {
+        calendar.add_working_day(day, true);
+    }
+    let now = first_day.and_time(working_time.0);
+
+    // 再帰ならスタックを使い切る深さでも、小さいスタックで完走すること
+    let handle = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || {
+            let rev_graph = build_rev_graph(&tasks);
+            let dependents = compute_dependents_map(&tasks, &rev_graph);
+            let earliest = compute_earliest_start_map(&tasks, &calendar, now, working_time.1, Duration::minutes(25), Duration::minutes(5));
+            let deadlines = resolve_deadline_map(&tasks, &calendar, working_time.1);
+            let latest = compute_latest_start_map(&tasks, &rev_graph, &deadlines, &calendar, Duration::minutes(25), Duration::minutes(5));
+            (dependents, earliest, latest)
+        })
+        .unwrap();
+    let (dependents, earliest, latest) = handle.join().unwrap();
+
+    assert_eq!(dependents[&ids[0]], 1999);
+    assert_eq!(dependents[&ids[1999]], 0);
+    assert_eq!(earliest[&ids[0]], now);
+    // 後続ほど着手可能時刻が遅く、最遅開始時刻も遅い
+    assert!(ids.windows(2).all(|pair| earliest[&pair[0]] < earliest[&pair[1]]));
+    assert!(ids.windows(2).all(|pair| latest[&pair[0]] < latest[&pair[1]]));
+}
+
+#[test]
+fn test_latest_start_with_subtasks() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let mut calendar = Calendar::new(working_time);
+    let first_day = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+    for day in first_day.iter_days().take(10) {
+        calendar.add_working_day(day, true);
+    }
+    let now = first_day.and_time(working_time.0);
+    let deadline = (first_day + Duration::days(4)).and_time(working_time.1);
+    let (work_tick, buffer) = (Duration::minutes(25), Duration::minutes(5));
+
+    // 期限は親タスクだけに付け、見積は子タスクに付ける
+    let mut parent = Task::new("Parent".to_string(), None, None);
+    parent.deadline = Deadline::Exact(deadline);
+    let mut child_a = Task::new("Child A".to_string(), None, None);
+    child_a.parent = Some(parent.id);
+    child_a.update_remaining(Estimate::new(Duration::hours(6)), now).unwrap();
+    let mut child_b = Task::new("Child B".to_string(), None, None);
+    child_b.parent = Some(parent.id);
+    child_b.update_remaining(Estimate::new(Duration::hours(10)), now).unwrap();
+    let tasks: BTreeMap<_, _> = [parent.clone(), child_a.clone(), child_b.clone()].into_iter().map(|t| (t.id, t)).collect();
+
+    let rev_graph = build_rev_graph(&tasks);
+    let deadlines = resolve_deadline_map(&tasks, &calendar, working_time.1);
+    let latest = compute_latest_start_map(&tasks, &rev_graph, &deadlines, &calendar, work_tick, buffer);
+
+    // 親は子タスクの合計 (16 時間) から逆算する
+    assert_eq!(latest[&parent.id], project_start_before(deadline, Duration::hours(16), &calendar, work_tick, buffer));
+    // 子タスクは親の期限を引き継ぐ
+    assert_eq!(latest[&child_a.id], project_start_before(deadline, Duration::hours(6), &calendar, work_tick, buffer));
+    assert_eq!(latest[&child_b.id], project_start_before(deadline, Duration::hours(10), &calendar, work_tick, buffer));
+    assert!(latest[&parent.id] < latest[&child_b.id]);
+}
+
+#[test]
+fn test_strict_schedule_rejects_unestimated_tasks() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    calendar.add_working_day(d1, true);
+    let mut scheduler = Scheduler {
+        strict: true,
+        ..test_scheduler(working_time)
+    };
+    let now = d1.and_time(working_time.0);
+    let mut estimated = Task::new("見積あり".to_string(), None, None);
+    estimated.update_remaining(Estimate::new(Duration::minutes(30)), now).unwrap();
+    let unestimated = Task::new("見積なし".to_string(), None, None);
+    let tasks: BTreeMap<_, _> = [(estimated.id, estimated.clone()), (unestimated.id, unestimated.clone())].into_iter().collect();
+
+    let err = scheduler.schedule(now, &tasks, &calendar, None).unwrap_err().to_string();
+    let id_len = TaskID::shortest_unique_len(tasks.keys());
+    assert!(err.contains(&unestimated.id.short(id_len).to_string()), "{}", err);
+    assert!(!err.contains(&estimated.id.short(id_len).to_string()), "{}", err);
+
+    // strict でなければ既定の 5 分で割り当てる
+    scheduler.strict = false;
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1)[&unestimated.id], Duration::minutes(5));
+}
+
+#[test]
+fn test_dependents_weight_reorders_tasks() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    // 1 日 30 分 (25 分 + バッファ 5 分) なので、初日は優先度が最も高いタスクだけが割り当てられる
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    for day in d1.iter_days().take(20) {
+        calendar.add_working_day(day, true);
+    }
+    let now = d1.and_time(working_time.0);
+    // A: 見積の幅が広い (リスク大)、B: 後続タスク C を持つ (依存度大)
+    let mut a = Task::new("A".to_string(), None, None);
+    a.update_remaining(Estimate::from_mop(Duration::minutes(60), Duration::minutes(30), Duration::minutes(180)).unwrap(), now)
+        .unwrap();
+    let mut b = Task::new("B".to_string(), None, None);
+    b.update_remaining(Estimate::new(Duration::minutes(75)), now).unwrap();
+    let mut c = Task::new("C".to_string(), None, None);
+    c.block_by_task(vec![b.id]);
+    let tasks: BTreeMap<_, _> = [a.clone(), b.clone(), c].into_iter().map(|t| (t.id, t)).collect();
+
+    let scheduler_with = |weights| Scheduler {
+        weights,
+        ..test_scheduler(working_time)
+    };
+    let risk_heavy = PriorityWeights {
+        urgency: 0.0,
+        risk: 0.9,
+        dependents: 0.1,
+    };
+    let slots = scheduler_with(risk_heavy).schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).keys().collect::<Vec<_>>(), vec![&a.id]);
+
+    let dependents_heavy = PriorityWeights {
+        urgency: 0.0,
+        risk: 0.1,
+        dependents: 0.9,
+    };
+    let slots = scheduler_with(dependents_heavy).schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).keys().collect::<Vec<_>>(), vec![&b.id]);
+}
+
+#[test]
+fn test_schedule_with_fixed_task() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(10, 30, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    for day in d1.iter_days().take(10) {
+        calendar.add_working_day(day, true);
+    }
+    let scheduler = test_scheduler(working_time);
+    let now = d1.and_time(working_time.0);
+    let mut flexible = Task::new("作業".to_string(), None, None);
+    flexible.update_remaining(Estimate::new(Duration::hours(2)), now).unwrap();
+    let mut call = Task::new("30分の電話".to_string(), None, None);
+    call.update_remaining(Estimate::new(Duration::minutes(30)), now).unwrap();
+    call.fixed_at = Some(d1.and_hms_opt(9, 30, 0).unwrap());
+    let tasks: BTreeMap<_, _> = [flexible.clone(), call.clone()].into_iter().map(|t| (t.id, t)).collect();
+
+    // 9:30-10:00 は電話で埋まり、作業は 9:00-9:30 と 10:00-10:30 に 25 分ずつ入る
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1)[&call.id], Duration::minutes(30));
+    assert_eq!(slots.get(&d1)[&flexible.id], Duration::minutes(50));
+    assert_eq!(slots.total(&call.id), Duration::minutes(30));
+}
+
+#[test]
+fn test_round_allocation() {
+    let m = Duration::minutes;
+    // 丸めなし
+    assert_eq!(round_allocation(m(23), m(1)), m(23));
+    // 上限を超えないよう、収まる倍数まで切り下げる
+    assert_eq!(round_allocation(m(23), m(5)), m(20));
+    assert_eq!(round_allocation(m(23), m(15)), m(15));
+    assert_eq!(round_allocation(m(30), m(15)), m(30));
+    // 上限が 1 単位に満たなければそのまま (3 分の残りに 15 分を割り当てない)
+    assert_eq!(round_allocation(m(3), m(15)), m(3));
+}
+
+#[test]
+fn test_rounded_allocation_does_not_exceed_remaining() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    calendar.add_working_day(d1, true);
+    let scheduler = Scheduler {
+        granularity: Duration::minutes(15),
+        ..test_scheduler(working_time)
+    };
+    let now = d1.and_time(working_time.0);
+    let mut task = Task::new("端数".to_string(), None, None);
+    task.update_remaining(Estimate::new(Duration::minutes(40)), now).unwrap();
+    let tasks: BTreeMap<_, _> = [(task.id, task.clone())].into_iter().collect();
+
+    // 15 分単位に丸めても、割り当ての合計は残り時間を超えない
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1)[&task.id], Duration::minutes(40));
+}
+
+#[test]
+fn test_schedule_with_deferred_task() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    for day in d1.iter_days().take(10) {
+        calendar.add_working_day(day, true);
+    }
+    let scheduler = test_scheduler(working_time);
+    let now = d1.and_time(working_time.0);
+    let mut deferred = Task::new("後でやる".to_string(), None, None);
+    deferred.update_remaining(Estimate::new(Duration::hours(1)), now).unwrap();
+    let not_before = NaiveDate::from_ymd_opt(2025, 5, 10).unwrap();
+    deferred.not_before = Some(not_before.and_time(NaiveTime::MIN));
+    let tasks: BTreeMap<_, _> = [(deferred.id, deferred.clone())].into_iter().collect();
+
+    // 空きがあっても、5/10 より前には割り当てない
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    for day in d1.iter_days().take_while(|day| *day < not_before) {
+        assert!(!slots.get(&day).contains_key(&deferred.id), "{} に割り当てられている", day);
+    }
+    assert_eq!(slots.get(&not_before)[&deferred.id], Duration::hours(1));
+}
+
+#[test]
+fn test_prefer_after_orders_without_blocking() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    // 1 日 30 分 (25 分 + バッファ 5 分) なので、初日は優先度が最も高いタスクだけが割り当てられる
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 1).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    for day in d1.iter_days().take(20) {
+        calendar.add_working_day(day, true);
+    }
+    let scheduler = test_scheduler(working_time);
+    let now = d1.and_time(working_time.0);
+    // B は見積の幅が広く (リスク大)、通常は A より先に割り当てられる
+    let mut a = Task::new("A".to_string(), None, None);
+    a.update_remaining(Estimate::new(Duration::minutes(25)), now).unwrap();
+    let mut b = Task::new("B".to_string(), None, None);
+    b.update_remaining(Estimate::from_mop(Duration::minutes(25), Duration::minutes(10), Duration::minutes(120)).unwrap(), now)
+        .unwrap();
+    let tasks = |a: &Task, b: &Task| -> BTreeMap<_, _> { [a.clone(), b.clone()].into_iter().map(|t| (t.id, t)).collect() };
+    let slots = scheduler.schedule(now, &tasks(&a, &b), &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).keys().collect::<Vec<_>>(), vec![&b.id]);
+
+    // B を A の後にしたいなら、A が先に割り当てられる
+    b.prefer_after = vec![a.id];
+    let slots = scheduler.schedule(now, &tasks(&a, &b), &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).keys().collect::<Vec<_>>(), vec![&a.id]);
+
+    // A がまだ着手できなければ、B は待たずに割り当てられる
+    a.not_before = Some((d1 + Duration::days(5)).and_time(NaiveTime::MIN));
+    let slots = scheduler.schedule(now, &tasks(&a, &b), &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).keys().collect::<Vec<_>>(), vec![&b.id]);
+}
+
+#[test]
+fn test_estimate_decay_factor() {
+    let decay = EstimateDecay::default();
+    assert_eq!(decay.factor(Duration::days(10)), 1.0);
+    assert_eq!(decay.factor(Duration::days(30)), 1.0);
+    assert_eq!(decay.factor(Duration::days(60)), 1.25);
+    assert_eq!(decay.factor(Duration::days(400)), 2.0);
+    let off = EstimateDecay { per_month: 0.0, ..decay };
+    assert_eq!(off.factor(Duration::days(400)), 1.0);
+    assert!(EstimateDecay { max_factor: 0.5, ..decay }.validate().is_err());
+}
+
+#[test]
+fn test_no_allocation_past_window_end() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    // 9:00–9:30 は work_tick (25 分) + バッファ (10 分) の半分しかない
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    calendar.add_working_day(d1, true);
+    let scheduler = Scheduler {
+        buffer_time: Duration::minutes(10),
+        ..test_scheduler(working_time)
+    };
+    let now = d1.and_time(working_time.0);
+    let mut a = Task::new("A".to_string(), None, None);
+    a.update_remaining(Estimate::new(Duration::hours(2)), now).unwrap();
+    let mut b = Task::new("B".to_string(), None, None);
+    b.update_remaining(Estimate::new(Duration::hours(1)), now).unwrap();
+
+    // 1 回割り当てた後はバッファでウィンドウを使い切り、それ以上は割り当てない
+    let tasks: BTreeMap<_, _> = [(a.id, a.clone())].into_iter().collect();
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).values().copied().sum::<Duration>(), Duration::minutes(25));
+
+    // ウィンドウの途中 (9:20) から着手できるタスクには、残りの 10 分だけを割り当てる
+    b.not_before = Some(d1.and_hms_opt(9, 20, 0).unwrap());
+    let tasks: BTreeMap<_, _> = [(b.id, b.clone())].into_iter().collect();
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1)[&b.id], Duration::minutes(10));
+}
+
+#[test]
+fn test_active_task_gets_first_slot() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    // 1 日に 1 回しか割り当てられない (9:00–9:30、25 分 + バッファ 5 分)
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let d2 = d1.succ_opt().unwrap();
+    let mut calendar = Calendar::new(working_time);
+    calendar.add_working_day(d1, true);
+    calendar.add_working_day(d2, true);
+    let scheduler = test_scheduler(working_time);
+    let now = d1.and_time(working_time.0);
+    let mut urgent = Task::new("Urgent".to_string(), None, None);
+    urgent.update_remaining(Estimate::new(Duration::minutes(25)), now).unwrap();
+    urgent.deadline = Deadline::Exact(d1.and_hms_opt(17, 0, 0).unwrap());
+    let mut active = Task::new("Active".to_string(), None, None);
+    active.update_remaining(Estimate::new(Duration::minutes(25)), now).unwrap();
+    let tasks: BTreeMap<_, _> = [(urgent.id, urgent.clone()), (active.id, active.clone())].into_iter().collect();
+
+    // 普段は期限の近いタスクが先
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert!(slots.get(&d1).contains_key(&urgent.id));
+    assert!(slots.get(&d2).contains_key(&active.id));
+
+    // 作業中のタスクは今日の最初の枠に入る
+    let slots = scheduler.schedule(now, &tasks, &calendar, Some(active.id)).unwrap();
+    assert_eq!(slots.get(&d1)[&active.id], Duration::minutes(25));
+    assert!(!slots.get(&d1).contains_key(&urgent.id));
+    assert!(slots.get(&d2).contains_key(&urgent.id));
+}
+
+#[test]
+fn test_nothing_scheduled_past_horizon() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    for day in d1.iter_days().take(10) {
+        calendar.add_working_day(day, true);
+    }
+    let mut scheduler = Scheduler {
+        horizon: Some(Duration::days(2)),
+        ..test_scheduler(working_time)
+    };
+    let now = d1.and_time(working_time.0);
+    // 5 日分の作業量
+    let mut task = Task::new("Big".to_string(), None, None);
+    task.update_remaining(Estimate::new(Duration::hours(40)), now).unwrap();
+    let tasks: BTreeMap<_, _> = [(task.id, task.clone())].into_iter().collect();
+
+    // 2 日先 (5/9 9:00) より後には割り当てない
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert!(!slots.get(&d1).is_empty());
+    assert!(!slots.get(&(d1 + Duration::days(1))).is_empty());
+    for day in d1.iter_days().skip(2).take(8) {
+        assert!(slots.get(&day).is_empty(), "{} に割り当てられている", day);
+    }
+
+    // 期限なしなら最後まで割り当てる
+    scheduler.horizon = None;
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert!(!slots.get(&(d1 + Duration::days(4))).is_empty());
+}
+
+#[test]
+fn test_schedule_starts_from_now_within_the_day() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    for day in d1.iter_days().take(5) {
+        calendar.add_working_day(day, true);
+    }
+    let scheduler = test_scheduler(working_time);
+    let now = d1.and_hms_opt(14, 0, 0).unwrap();
+    let mut big = Task::new("Big".to_string(), None, None);
+    big.update_remaining(Estimate::new(Duration::hours(10)), now).unwrap();
+    let mut next = Task::new("Next".to_string(), None, None);
+    next.update_remaining(Estimate::new(Duration::hours(1)), now).unwrap();
+    next.block_by_task(vec![big.id]);
+    let tasks: BTreeMap<_, _> = [(big.id, big.clone()), (next.id, next.clone())].into_iter().collect();
+
+    // 14:00 からなら今日は 3 時間しか空いていない
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    let today: Duration = slots.get(&d1).values().copied().sum();
+    assert!(!today.is_zero());
+    assert!(today <= Duration::hours(3), "今日に {} 分割り当てられている", today.num_minutes());
+
+    // 着手可能時刻も始業ではなく今から数える
+    let timeline = scheduler.timeline(now, &tasks, &calendar);
+    assert_eq!(timeline[&big.id].earliest, now);
+    assert!(timeline[&next.id].earliest > project_finish(d1.and_time(working_time.0), Duration::hours(10), &calendar, Duration::minutes(25), Duration::minutes(5)));
+
+    // 始業前・休日は次の始業から
+    let early = d1.and_hms_opt(7, 0, 0).unwrap();
+    assert_eq!(scheduler.timeline(early, &tasks, &calendar)[&big.id].earliest, d1.and_time(working_time.0));
+}
+
+#[test]
+fn test_short_windows_are_skipped() {
+    use super::estimate::Estimate;
+    use chrono::NaiveDate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    calendar.add_working_day(d1, true);
+    calendar.add_working_day(d1.succ_opt().unwrap(), true);
+    // 1 日目は 57 分の会議が 3 分おきに続き、空きは 3 分ずつしかない
+    for hour in 9..12 {
+        let item = ScheduleItem {
+            start: NaiveTime::from_hms_opt(hour, 0, 0).unwrap(),
+            duration: Duration::minutes(57),
+            note: Some("会議".to_string()),
+        };
+        assert!(calendar.add_scheduled_item(&d1, item));
+    }
+    let mut scheduler = Scheduler {
+        buffer_time: Duration::zero(),
+        min_window: Duration::minutes(5),
+        ..test_scheduler(working_time)
+    };
+    let now = d1.and_time(working_time.0);
+    let mut task = Task::new("Task".to_string(), None, None);
+    task.update_remaining(Estimate::new(Duration::hours(1)), now).unwrap();
+    let tasks: BTreeMap<_, _> = [(task.id, task.clone())].into_iter().collect();
+
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert!(slots.get(&d1).is_empty());
+    assert_eq!(slots.total(&task.id), Duration::hours(1));
+
+    // 0 なら 3 分の空きにも割り当てる
+    scheduler.min_window = Duration::zero();
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).get(&task.id), Some(&Duration::minutes(9)));
+}
+
+#[test]
+fn test_fair_strategy_rotates_top_tasks() {
+    use super::estimate::Estimate;
+
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let mut calendar = Calendar::new(working_time);
+    for day in d1.iter_days().take(3) {
+        calendar.add_working_day(day, true);
+    }
+    let mut scheduler = Scheduler {
+        work_tick: Duration::minutes(30),
+        buffer_time: Duration::zero(),
+        ..test_scheduler(working_time)
+    };
+    let now = d1.and_time(working_time.0);
+    let mut urgent = Task::new("Urgent".to_string(), None, None);
+    urgent.deadline = Deadline::Exact((d1 + Duration::days(1)).and_hms_opt(12, 0, 0).unwrap());
+    let mut others = [Task::new("Other 1".to_string(), None, None), Task::new("Other 2".to_string(), None, None)];
+    for task in others.iter_mut().chain(std::iter::once(&mut urgent)) {
+        task.update_remaining(Estimate::new(Duration::hours(2)), now).unwrap();
+    }
+    let tasks: BTreeMap<_, _> = others.iter().chain(std::iter::once(&urgent)).map(|t| (t.id, t.clone())).collect();
+
+    // greedy: 期限のあるタスクが 1 日目を使い切るまで占め、残りのどちらかは 1 日目に進まない
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.remaining_at(&d1, urgent.id), Some(Duration::hours(2)));
+    assert!(others.iter().any(|t| slots.remaining_at(&d1, t.id).is_none()));
+
+    // fair: 上位のタスクを交代で進め、1 日目は 1 時間ずつになる
+    scheduler.strategy = Strategy::Fair { top_k: 3 };
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    for id in tasks.keys() {
+        assert_eq!(slots.remaining_at(&d1, *id), Some(Duration::hours(1)), "{}", tasks[id].title);
+    }
+    assert!(tasks.keys().all(|id| slots.total(id) == Duration::hours(2)));
+
+    // 上位 2 件だけで交代すると、3 件目は後回しになる
+    scheduler.strategy = Strategy::Fair { top_k: 2 };
+    let slots = scheduler.schedule(now, &tasks, &calendar, None).unwrap();
+    assert_eq!(slots.get(&d1).len(), 2);
+    assert_eq!(slots.remaining_at(&d1, urgent.id), Some(Duration::minutes(90)));
+}
+
+#[test]
+fn test_parse_strategy() {
+    assert_eq!("greedy".parse(), Ok(Strategy::Greedy));
+    assert_eq!("fair".parse(), Ok(Strategy::Fair { top_k: Strategy::DEFAULT_TOP_K }));
+    assert_eq!("fair:5".parse(), Ok(Strategy::Fair { top_k: 5 }));
+    assert_eq!(Strategy::Fair { top_k: 5 }.to_string(), "fair:5");
+    assert!("fair:1".parse::<Strategy>().is_err());
+    assert!("round-robin".parse::<Strategy>().is_err());
+}
@@ -1,63 +1,230 @@
 use super::{
-    calendar::Calendar,
+    calendar::{Calendar, ScheduleItem},
+    config::{CategoryColor, Config},
     deadline::Deadline,
     estimate::Estimate,
+    plan::PlanBaseline,
     schedule,
-    slot::SlotMap,
-    task::{ExternalBlockingReason, Progress, Task, TaskID},
-    utils::StopKind,
+    slot::{ScheduleSnapshot, SlotMap},
+    task::{ExternalBlockingReason, Progress, ShortTaskID, Task, TaskID, TaskStatus},
+    utils::{StopKind, format_human_duration_in, parse_human_duration_in},
     work_log::WorkLog,
 };
 use anyhow::bail;
 use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use core::task;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// `import worklog` で 1 件の作業記録を取り込んだ結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogImport {
+    /// 同じタイトルのタスクに記録した
+    Matched(TaskID),
+    /// 同じタイトルのタスクがないので、タスクを作って記録した
+    Created(TaskID),
+    /// 同じ日時・時間の記録が既にあるので取り込まなかった
+    Duplicate(TaskID),
+}
+
+/// `now set` によるセッション全体の現在時刻の上書き
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NowOverride {
+    /// 実時刻からのずれ (時計は進む)
+    Offset(Duration),
+    /// 固定時刻 (時計は進まない)
+    Fixed(NaiveDateTime),
+}
+
+/// 起動してから終了するまでの作業の集計 (終了時のサマリ用)
+#[derive(Debug, Default)]
+pub struct SessionStats {
+    /// 完了したタスク数
+    pub completed: usize,
+    /// 記録した作業時間の合計
+    pub logged: Duration,
+}
+
+/// 保存後に変更され、終了時に書き出す必要があるもの。各変更操作が該当するものだけを立てる
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DirtyFlags {
+    /// タスク (tasks.json)
+    pub tasks: bool,
+    /// 作業記録 (worklog.json)
+    pub worklog: bool,
+    /// カレンダーの予定 (取り込んだ予定など)
+    pub calendar: bool,
+    /// スケジュール (schedule.json)
+    pub schedule: bool,
+    /// 固定したタスク (pinned.json)
+    pub pinned: bool,
+}
 
 #[derive(Debug)]
 pub struct Session {
     pub calendar: Calendar,
+    pub config: Config,
     pub scheduler: schedule::Scheduler,
     pub tasks: BTreeMap<TaskID, Task>,
     pub slots: SlotMap,
     pub log: WorkLog,
+    pub baseline: PlanBaseline,
     pub active_task: Option<(TaskID, NaiveDateTime)>,
-    pub dirty_tasks: bool,
+    /// 保存後に変更されたもの
+    pub dirty: DirtyFlags,
+    /// `slots` を計算した時刻
+    pub scheduled_at: Option<NaiveDateTime>,
+    /// タスクが変更され、スケジュールの再計算が必要か
+    schedule_stale: bool,
+    pub now_override: Option<NowOverride>,
+    pub stats: SessionStats,
+    /// 直近の `todo` で表示した番号の順のタスク (`todo done <n>` で番号から引く)
+    pub todo_indices: Vec<TaskID>,
+    /// `pin` で今日の最優先に固定したタスク (スケジューラの順序より優先して todo の先頭に出す)
+    pub pinned: Option<TaskID>,
+    /// 追加したが、まだ schedule/<日付>.yaml に保存していない予定
+    pub added_busy_items: BTreeMap<NaiveDate, Vec<ScheduleItem>>,
+    /// 全タスクの ID を区別できる最短の表示桁数 (タスクを追加するたびに更新する)
+    id_display_len: usize,
 }
 impl Session {
-    pub fn new(calendar: Calendar, tasks: BTreeMap<TaskID, Task>, log: WorkLog) -> Self {
+    pub fn new(calendar: Calendar, config: Config, tasks: BTreeMap<TaskID, Task>, log: WorkLog, baseline: PlanBaseline) -> Self {
         let scheduler = schedule::Scheduler {
-            work_tick: Duration::minutes(25),
-            buffer_time: Duration::minutes(5),
-            working_time: (NaiveTime::from_hms_opt(8, 45, 0).unwrap(), NaiveTime::from_hms_opt(19, 0, 0).unwrap()),
+            work_tick: config.work_tick,
+            buffer_time: config.buffer_time,
+            working_time: (config.working_time.start, config.working_time.end),
+            default_deadline_time: config.default_deadline_time,
+            strict: config.strict,
+            weights: config.priority_weights,
+            granularity: config.allocation_granularity,
+            estimate_decay: config.estimate_decay,
+            horizon: config.schedule_horizon_days.map(Duration::days),
+            min_window: config.min_window,
+            strategy: config.schedule_strategy,
         };
         let mut slots = SlotMap::new();
+        let id_display_len = TaskID::shortest_unique_len(tasks.keys());
         Self {
             calendar,
+            config,
             scheduler,
             tasks,
             slots,
             log,
+            baseline,
             active_task: None,
-            dirty_tasks: false,
+            dirty: DirtyFlags::default(),
+            scheduled_at: None,
+            schedule_stale: true,
+            now_override: None,
+            stats: SessionStats::default(),
+            todo_indices: Vec::new(),
+            pinned: None,
+            added_busy_items: BTreeMap::new(),
+            id_display_len,
         }
     }
+    /// `now_override` を反映した現在時刻
+    pub fn now(&self) -> NaiveDateTime {
+        let real_now = chrono::Local::now().naive_local();
+        match self.now_override {
+            None => real_now,
+            Some(NowOverride::Offset(offset)) => real_now + offset,
+            Some(NowOverride::Fixed(fixed)) => fixed,
+        }
+    }
+    /// 勤務時間に合わせた単位 (1d = 1 日の勤務時間) で期間を表示する
+    pub fn format_duration(&self, duration: Duration) -> String {
+        format_human_duration_in(duration, self.calendar.duration_units())
+    }
+    /// 表示と同じ単位 (1d = 1 日の勤務時間) で期間の入力を解釈する
+    pub fn parse_duration(&self, input: &str) -> Option<Duration> {
+        parse_human_duration_in(input, self.calendar.duration_units())
+    }
+    /// 設定の表示形式 (datetime_format) で日時を表示する
+    pub fn format_datetime(&self, datetime: NaiveDateTime) -> String {
+        datetime.format(&self.config.datetime_format).to_string()
+    }
     pub fn add_task(&mut self, task: Task) -> &Task {
         let task_id = task.id;
         if self.tasks.contains_key(&task_id) {
-            panic!("Task with ID {} already exists", task_id);
+            panic!("Task with ID {:?} already exists", task_id);
         }
         self.tasks.insert(task_id, task);
-        self.dirty_tasks = true;
+        // 一致する桁が最も長いのは、追加した ID と前後に並ぶ ID の間
+        let before = self.tasks.range(..task_id).next_back().map(|(id, _)| id);
+        let after = self.tasks.range(task_id..).nth(1).map(|(id, _)| id);
+        let around = before.into_iter().chain([&task_id]).chain(after);
+        self.id_display_len = self.id_display_len.max(TaskID::shortest_unique_len(around));
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
         self.tasks.get(&task_id).expect("Task not found")
     }
+    /// 同じタイトル (大文字小文字・前後の空白を無視) の未完了タスクを探す
+    pub fn find_duplicate_task(&self, title: &str) -> Option<&Task> {
+        let title = title.trim().to_lowercase();
+        self.tasks.values().find(|t| !t.is_completed() && !t.is_dropped() && t.title.trim().to_lowercase() == title)
+    }
+    /// タスクを複製して追加する。タイトル未指定なら " (copy)" を付ける
+    pub fn clone_task(&mut self, task_id: &TaskID, title: Option<String>, now: NaiveDateTime) -> &Task {
+        let task = self.tasks.get(task_id).expect("Task not found");
+        let title = title.unwrap_or_else(|| format!("{} (copy)", task.title));
+        let copy = task.duplicate(title, now);
+        self.add_task(copy)
+    }
     pub fn iter_tasks(&self) -> impl Iterator<Item = &Task> {
         self.tasks.values()
     }
+    /// 全タスクの ID を区別できる最短の表示桁数
+    pub fn id_display_len(&self) -> usize {
+        self.id_display_len
+    }
+    /// 全タスクの ID を区別できる桁数で表示する ID
+    pub fn short_id(&self, task_id: &TaskID) -> ShortTaskID {
+        task_id.short(self.id_display_len)
+    }
+    pub fn timeline(&self, now: NaiveDateTime) -> BTreeMap<TaskID, schedule::TaskTiming> {
+        self.scheduler.timeline(now, &self.tasks, &self.calendar)
+    }
+    /// 依存タスクや外部ブロックを待つと、最短でも期限に間に合わないタスクの (完了見込み, 期限)。
+    /// 間に合う・期限がない・完了済みなら None
+    pub fn unreachable_deadline(&self, now: NaiveDateTime, task_id: &TaskID) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let deadline = self.tasks[task_id].resolve_deadline(&self.calendar, self.deadline_time_of(task_id)).ok()??;
+        let finish = self.scheduler.earliest_finish(now, task_id, &self.tasks, &self.calendar)?;
+        (finish > deadline).then_some((finish, deadline))
+    }
+    /// 余裕が設定 (min_slack_days) を下回った未完了のタスクを、余裕の少ない順に返す
+    pub fn low_slack_tasks(&self, now: NaiveDateTime) -> Vec<(TaskID, f64)> {
+        let mut tasks: Vec<_> = self
+            .timeline(now)
+            .into_iter()
+            .map(|(id, timing)| (id, timing.slack_days))
+            .filter(|(_, slack)| *slack < self.config.min_slack_days)
+            .collect();
+        tasks.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        tasks
+    }
+    /// 設定を変更し、スケジューラにもすぐ反映する (settings.yaml への保存は終了時)
+    pub fn update_setting(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.config.set(key, value).map_err(anyhow::Error::msg)?;
+        self.scheduler.work_tick = self.config.work_tick;
+        self.scheduler.buffer_time = self.config.buffer_time;
+        self.scheduler.working_time = (self.config.working_time.start, self.config.working_time.end);
+        self.scheduler.default_deadline_time = self.config.default_deadline_time;
+        self.scheduler.strict = self.config.strict;
+        self.scheduler.granularity = self.config.allocation_granularity;
+        self.scheduler.horizon = self.config.schedule_horizon_days.map(Duration::days);
+        self.scheduler.min_window = self.config.min_window;
+        self.scheduler.strategy = self.config.schedule_strategy;
+        self.schedule_stale = true;
+        Ok(())
+    }
+    /// 前方一致で一意に決まるタスクを探す (該当なし・複数該当なら None)。
+    /// ID の順序と16進表記の順序は一致するので、前方一致するキーは連続しており、範囲検索で済む。
     pub fn find_task_by_prefix(&self, id_prefix: &str) -> Option<TaskID> {
-        let found_keys = self.tasks.keys().filter(|id| id.starts_with(id_prefix)).cloned().collect::<Vec<_>>();
-        match found_keys.len() {
-            0 => None,
-            1 => Some(found_keys[0]),
+        let lower = TaskID::prefix_lower_bound(id_prefix)?;
+        let mut found_keys = self.tasks.range(lower..).map(|(id, _)| *id).take_while(|id| id.starts_with(id_prefix)).take(2);
+        match (found_keys.next(), found_keys.next()) {
+            (Some(id), None) => Some(id),
             _ => None,
         }
     }
@@ -65,57 +232,301 @@ impl Session {
         let mut task = self.tasks.get_mut(task_id).expect("Task not found");
         let task_title = task.title.clone();
         task.drop();
-        self.dirty_tasks = true;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
         task_title
     }
     pub fn set_deadline(&mut self, task_id: &TaskID, deadline: Deadline) -> &Task {
         let task = self.tasks.get_mut(task_id).expect("Task not found");
         task.deadline = deadline;
-        self.dirty_tasks = true;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        task
+    }
+    pub fn set_default_deadline_time(&mut self, task_id: &TaskID, time: Option<NaiveTime>) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.default_deadline_time = time;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
         task
     }
-    pub fn estimate_task(&mut self, task_id: &TaskID, estimate: Estimate) -> anyhow::Result<&Task> {
+    /// タスクの期限を解決するときの時刻 (タスク固有の設定 > スケジューラ既定)
+    pub fn deadline_time_of(&self, task_id: &TaskID) -> NaiveTime {
+        let task = self.tasks.get(task_id).expect("Task not found");
+        task.deadline_time(self.scheduler.default_deadline_time)
+    }
+    pub fn estimate_task(&mut self, task_id: &TaskID, estimate: Estimate, now: NaiveDateTime) -> anyhow::Result<&Task> {
         let mut task = self.tasks.get_mut(task_id).expect("Task not found");
-        task.update_remaining(estimate).map_err(anyhow::Error::msg)?;
-        self.dirty_tasks = true;
+        task.update_remaining(estimate, now).map_err(anyhow::Error::msg)?;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
         Ok(task)
     }
-    pub fn update_progress_task(&mut self, task_id: &TaskID, progress: Option<Progress>) -> &Task {
-        let mut task = self.tasks.get_mut(task_id).expect("Task not found");
+    /// 見積を設定し、入力された表記 (`input`) を表示用に残す
+    pub fn estimate_task_as_entered(&mut self, task_id: &TaskID, estimate: Estimate, input: String, now: NaiveDateTime) -> anyhow::Result<&Task> {
+        self.estimate_task(task_id, estimate, now)?;
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.estimate_input = Some(input);
+        Ok(task)
+    }
+    pub fn estimate_task_in_points(&mut self, task_id: &TaskID, points: u32, now: NaiveDateTime) -> anyhow::Result<&Task> {
+        let Some(estimate) = self.config.story_points.estimate(points) else {
+            let available = self.config.story_points.points().map(|p| p.to_string()).collect::<Vec<_>>();
+            bail!("{}pt は対応表にありません (指定可能: {})", points, available.join(", "));
+        };
+        self.estimate_task(task_id, estimate, now)?;
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.story_points = Some(points);
+        Ok(task)
+    }
+    /// 進捗を手動で設定する (None で自動算出に戻す)。完了・削除済みのタスクは reopen するまで変更できない
+    pub fn update_progress_task(&mut self, task_id: &TaskID, progress: Option<Progress>) -> anyhow::Result<&Task> {
+        let task = self.tasks.get(task_id).expect("Task not found");
+        match task.status() {
+            TaskStatus::Completed(_) => bail!("このタスクは完了済みです。進捗を変えるには先に reopen してください: {} - {}", self.short_id(&task.id), task.title),
+            TaskStatus::Dropped => bail!("このタスクは削除済みです。進捗を変えるには先に reopen してください: {} - {}", self.short_id(&task.id), task.title),
+            TaskStatus::Ready | TaskStatus::Blocked(_) => {}
+        }
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
         task.progress = progress;
-        self.dirty_tasks = true;
-        task
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        Ok(task)
     }
     pub fn schedule(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
-        self.slots = self.scheduler.schedule(now, &self.tasks, &self.calendar)?;
+        self.slots = self.scheduler.schedule(now, &self.tasks, &self.calendar, self.active_task.map(|(id, _)| id))?;
+        self.baseline.record_day(now.date(), &self.slots, &self.log);
+        self.scheduled_at = Some(now);
+        self.schedule_stale = false;
+        self.dirty.schedule = true;
+        Ok(())
+    }
+    /// タスクが変更されたか、前回の計算と日付が変わった場合だけスケジュールを再計算する
+    pub fn schedule_if_stale(&mut self, now: NaiveDateTime) -> anyhow::Result<()> {
+        let same_day = self.scheduled_at.is_some_and(|at| at.date() == now.date());
+        if self.schedule_stale || !same_day {
+            self.schedule(now)?;
+        }
         Ok(())
     }
-    pub fn start_task_at(&mut self, task_id: &TaskID, start_at: NaiveDateTime) -> (&Task, Duration) {
+    /// 未完了のタスクのうち、最も早く着手できるもの (タスク, 着手可能時刻)
+    pub fn first_startable_task(&self, now: NaiveDateTime) -> Option<(TaskID, NaiveDateTime)> {
+        self.timeline(now).into_iter().map(|(id, timing)| (id, timing.earliest)).min_by_key(|&(id, at)| (at, id))
+    }
+    /// 残り時間があるのに、スケジュールの全期間で一度も割り当てられなかったタスク
+    /// (優先度が低いまま後回しにされ続けた、またはカレンダーの範囲外にあふれたもの)
+    pub fn unscheduled_tasks(&self) -> Vec<&Task> {
+        self.tasks
+            .values()
+            .filter(|t| !t.is_completed() && !t.is_dropped() && !t.has_children(&self.tasks))
+            .filter(|t| t.remaining() > Duration::zero() && self.slots.total(&t.id).is_zero())
+            .collect()
+    }
+    /// 保存済みのスケジュールを復元する (タスクが変更されるまで再計算しない)
+    pub fn restore_schedule(&mut self, snapshot: ScheduleSnapshot) {
+        self.slots = snapshot.slots;
+        self.scheduled_at = Some(snapshot.scheduled_at);
+        self.schedule_stale = false;
+    }
+    /// 計画 (その日最初のスケジュール) と比べた実績の差。正なら先行、負なら遅延
+    pub fn plan_variance(&self, task_id: &TaskID, now: NaiveDateTime) -> Option<Duration> {
+        let working_time = self.calendar.working_time(now.date()).unwrap_or(self.scheduler.working_time);
+        let (planned, actual) = self.baseline.planned_and_actual(*task_id, now, working_time, &self.log)?;
+        Some(actual - planned)
+    }
+    /// `date` に予定していたのに手をつけなかったタスクに 0 分の作業記録を残す。
+    /// 残りは `remaining()` のまま翌日以降に再計画される。記録したタスクを返す (記録済みのタスクには二重に残さない)
+    pub fn record_untouched_plan(&mut self, date: NaiveDate, recorded_at: NaiveTime) -> anyhow::Result<Vec<TaskID>> {
+        let Some(reconciled) = self.baseline.reconcile_day(date, &self.log) else {
+            bail!("{} の計画が記録されていません", date);
+        };
+        let logged = self.log.get_items(date).map(|items| items.iter().map(|item| item.task_id).collect::<HashSet<_>>()).unwrap_or_default();
+        let untouched: Vec<_> = reconciled
+            .into_iter()
+            .filter(|(task_id, planned, actual)| {
+                planned > &Duration::zero() && actual.is_zero() && !logged.contains(task_id) && self.tasks.get(task_id).is_some_and(|t| !t.is_completed() && !t.is_dropped())
+            })
+            .map(|(task_id, _, _)| task_id)
+            .collect();
+        for task_id in &untouched {
+            self.log.add_item(date, *task_id, recorded_at, Duration::zero());
+        }
+        if !untouched.is_empty() {
+            self.dirty.worklog = true;
+            self.schedule_stale = true;
+        }
+        Ok(untouched)
+    }
+    pub fn start_task_at(&mut self, task_id: &TaskID, start_at: NaiveDateTime) -> anyhow::Result<(&Task, Duration)> {
         let task = self.tasks.get(task_id).expect("Task not found");
+        match task.status() {
+            TaskStatus::Ready => {}
+            // 依存先がすべて終わっていれば着手できる (完了した依存先は reopen の確認のため残している)
+            TaskStatus::Blocked(_) if task.is_startable(&self.tasks) => {}
+            TaskStatus::Blocked(_) => bail!("このタスクはブロック中です: {} - {}", self.short_id(&task.id), task.title),
+            TaskStatus::Completed(_) => bail!("このタスクは完了済みです: {} - {}", self.short_id(&task.id), task.title),
+            TaskStatus::Dropped => bail!("このタスクは削除済みです: {} - {}", self.short_id(&task.id), task.title),
+        }
+        // 着手だけではタスクは変わらない (実績は stop 時に記録する)。
+        // 割り当て時間は今のスケジュールから求め、その後で作業中のタスクを先頭にしたスケジュールに組み直す
         self.active_task = Some((task.id, start_at));
-        self.dirty_tasks = true;
-        let remaining = self.slots.remaining_at(&start_at.date(), *task_id).unwrap_or_else(|| task.remaining());
-        (task, remaining.min(self.scheduler.work_tick))
+        let allocated = self.allocation_at(task_id, start_at);
+        self.schedule_stale = true;
+        Ok((task, allocated))
+    }
+    /// `at` 時点で着手したときの割り当て時間。
+    /// 当日のスケジュールに割り当てがあればその時間を次の予定 (または勤務終了) までで打ち切った時間、なければ `tick_allocation_at`
+    pub fn allocation_at(&self, task_id: &TaskID, at: NaiveDateTime) -> Duration {
+        let task = self.tasks.get(task_id).expect("Task not found");
+        let Some(planned) = self.slots.remaining_at(&at.date(), *task_id) else {
+            return self.tick_allocation_at(task_id, at);
+        };
+        // `at` を含む (予定中ならその次の) 空き時間の残り
+        let block = self
+            .calendar
+            .time_windows(at)
+            .take_while(|w| w.start_datetime().date() == at.date())
+            .find(|w| w.available() && w.end_datetime() > at)
+            .map(|w| w.end_datetime() - w.start_datetime().max(at));
+        planned.min(task.remaining()).min(block.unwrap_or(planned))
+    }
+    /// `at` 時点で着手したときの 1 回分の作業時間 (当日のスロット残り or 残り時間を、タスクの work_tick で打ち切り)
+    pub fn tick_allocation_at(&self, task_id: &TaskID, at: NaiveDateTime) -> Duration {
+        let task = self.tasks.get(task_id).expect("Task not found");
+        let remaining = self.slots.remaining_at(&at.date(), *task_id).unwrap_or_else(|| task.remaining());
+        remaining.min(task.work_tick.unwrap_or(self.scheduler.work_tick))
+    }
+    pub fn set_work_tick(&mut self, task_id: &TaskID, work_tick: Option<Duration>) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.work_tick = work_tick;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        task
+    }
+    pub fn add_note(&mut self, task_id: &TaskID, at: NaiveDateTime, text: String) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.add_note(at, text);
+        self.dirty.tasks = true;
+        task
+    }
+    /// 全タスクのメモから `query` を含むもの (大文字小文字を区別しない) をタスク順・時刻順に返す
+    pub fn grep_notes(&self, query: &str) -> Vec<(&Task, &(NaiveDateTime, String))> {
+        let query = query.to_lowercase();
+        self.tasks
+            .values()
+            .flat_map(|task| task.notes.iter().filter(|(_, text)| text.to_lowercase().contains(&query)).map(move |note| (task, note)))
+            .collect()
+    }
+    /// タグを付け外しする (`add` を付けてから `remove` を外す)
+    pub fn update_tags(&mut self, task_id: &TaskID, add: &[String], remove: &[String]) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.tags.extend(add.iter().cloned());
+        task.tags.retain(|tag| !remove.contains(tag));
+        self.dirty.tasks = true;
+        task
+    }
+    /// カテゴリを設定する (None で外す)
+    pub fn set_category(&mut self, task_id: &TaskID, category: Option<String>) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.category = category;
+        self.dirty.tasks = true;
+        task
+    }
+    /// カテゴリ名 → 表示色。設定 (category_colors) になければ、使われているカテゴリの名前順に色を割り振る
+    pub fn category_colors(&self) -> BTreeMap<&str, CategoryColor> {
+        let mut colors: BTreeMap<&str, CategoryColor> = self.config.category_colors.iter().map(|(category, color)| (category.as_str(), *color)).collect();
+        let unassigned: BTreeSet<_> = self.tasks.values().filter_map(|t| t.category.as_deref()).filter(|c| !colors.contains_key(c)).collect();
+        for (index, category) in unassigned.into_iter().enumerate() {
+            colors.insert(category, CategoryColor::PALETTE[index % CategoryColor::PALETTE.len()]);
+        }
+        colors
+    }
+    /// タグの付いた未完了 (完了・削除済みでない) タスクを ID 順に返す
+    pub fn find_open_tasks_by_tag(&self, tag: &str) -> Vec<TaskID> {
+        self.tasks.values().filter(|t| !t.is_completed() && !t.is_dropped() && t.tags.contains(tag)).map(|t| t.id).collect()
+    }
+    /// カレンダーに予定 (作業できない時間) を追加する。稼働日でないか、同じ予定がすでにあれば追加せず false。
+    /// 追加した予定は終了時に schedule/<日付>.yaml に保存する
+    pub fn add_busy_item(&mut self, date: NaiveDate, item: ScheduleItem) -> bool {
+        let added = self.calendar.add_scheduled_item(&date, item.clone());
+        if added {
+            self.added_busy_items.entry(date).or_default().push(item);
+            self.dirty.calendar = true;
+            self.schedule_stale = true;
+        }
+        added
+    }
+    pub fn set_fixed_at(&mut self, task_id: &TaskID, fixed_at: Option<NaiveDateTime>) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.fixed_at = fixed_at;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        task
+    }
+    pub fn set_prefer_after(&mut self, task_id: &TaskID, prefer_after: Vec<TaskID>) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.prefer_after = prefer_after;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        task
+    }
+    pub fn set_not_before(&mut self, task_id: &TaskID, not_before: Option<NaiveDateTime>) -> &Task {
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.not_before = not_before;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        task
     }
     pub fn complete_task(&mut self, task_id: &TaskID, completed_at: NaiveDateTime, duration: Option<Duration>) -> &Task {
         let task = self.tasks.get_mut(task_id).expect("Task not found");
-        if let Some(duration) = duration {
-            task.record(duration);
+        match duration {
+            Some(duration) => {
+                task.record(duration);
+                self.stats.logged += duration;
+                task.complete(completed_at);
+            }
+            None => task.complete_without_time(completed_at),
         }
-        task.complete(completed_at);
+        self.stats.completed += 1;
         self.active_task = None;
-        self.dirty_tasks = true;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
         task
     }
+    /// 完了済みのタスクを着手可能に戻す。
+    /// 完了を前提に着手済みの後続タスクがあれば、整合しなくなるため取り消さない
+    pub fn reopen_task(&mut self, task_id: &TaskID) -> anyhow::Result<&Task> {
+        let task = self.tasks.get(task_id).expect("Task not found");
+        if !task.is_completed() && !task.is_dropped() {
+            bail!("このタスクは完了・削除されていません: {} - {}", self.short_id(&task.id), task.title);
+        }
+        let started_dependents: Vec<_> = self
+            .tasks
+            .values()
+            .filter(|t| matches!(t.status(), TaskStatus::Blocked(bs) if bs.tasks.contains(task_id)))
+            .filter(|t| !t.actual_total.is_zero() || self.active_task.is_some_and(|(id, _)| id == t.id))
+            .map(|t| format!("{} - {}", self.short_id(&t.id), t.title))
+            .collect();
+        if !started_dependents.is_empty() {
+            bail!("このタスクの完了を前提に着手済みのタスクがあります: {}", started_dependents.join(", "));
+        }
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.reopen();
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        Ok(task)
+    }
     pub fn stop_current_task(&mut self, kind: StopKind, complete: bool) -> anyhow::Result<&Task> {
         let Some((task_id, start_at)) = self.active_task else {
             bail!("No active task to stop");
         };
         let task = self.tasks.get_mut(&task_id).expect("Task not found");
+        // 記録なしで中断するだけならタスクも作業記録も変わらない
+        let changed = complete || !matches!(kind, StopKind::Immediately(_));
         match kind {
             StopKind::Immediately(now) => {
                 if complete {
-                    task.complete(now);
+                    task.complete_without_time(now);
                 }
             }
             StopKind::EndsAt(end_time) => {
@@ -125,7 +536,8 @@ impl Session {
                 assert!(end_time >= start_at, "End time must be after start time");
                 let duration = end_time - start_at;
                 self.log.add_item(start_at.date(), task_id, start_at.time(), duration);
-                self.slots.consume(&start_at.date(), task_id, duration);
+                self.dirty.worklog = true;
+                self.stats.logged += duration;
                 task.record(duration);
                 if complete {
                     task.complete(end_time);
@@ -134,43 +546,856 @@ impl Session {
             StopKind::EndsIn(duration) => {
                 let end_time = start_at + duration;
                 self.log.add_item(start_at.date(), task_id, start_at.time(), duration);
-                self.slots.consume(&start_at.date(), task_id, duration);
+                self.dirty.worklog = true;
+                self.stats.logged += duration;
                 task.record(duration);
                 if complete {
                     task.complete(end_time);
                 }
             }
         }
+        if complete {
+            self.stats.completed += 1;
+        }
         self.active_task = None;
-        self.dirty_tasks = true;
+        self.dirty.tasks |= changed;
+        self.schedule_stale = true;
         Ok(task)
     }
 
+    /// 他のツールの作業記録を 1 件取り込み、タスクの実績にも加える。
+    /// タイトルが同じ (大文字小文字・前後の空白を無視) 削除済みでないタスクに記録し、なければ着手可能なタスクとして作る
+    pub fn import_log_item(&mut self, date: NaiveDate, begin_at: NaiveTime, duration: Duration, title: &str) -> LogImport {
+        let normalized = title.trim().to_lowercase();
+        let existing = self.tasks.values().find(|t| !t.is_dropped() && t.title.trim().to_lowercase() == normalized).map(|t| t.id);
+        let (task_id, result) = match existing {
+            Some(task_id) => (task_id, LogImport::Matched(task_id)),
+            None => {
+                let mut task = Task::new(title.trim().to_string(), None, None);
+                task.created_at = date.and_time(begin_at);
+                let task_id = self.add_task(task).id;
+                (task_id, LogImport::Created(task_id))
+            }
+        };
+        let already_logged = self
+            .log
+            .get_items(date)
+            .is_some_and(|items| items.iter().any(|item| item.task_id == task_id && item.begin_at == begin_at && item.duration == duration));
+        if already_logged {
+            return LogImport::Duplicate(task_id);
+        }
+        self.log.add_item(date, task_id, begin_at, duration);
+        self.tasks.get_mut(&task_id).expect("Task not found").record(duration);
+        self.dirty.tasks = true;
+        self.dirty.worklog = true;
+        self.schedule_stale = true;
+        result
+    }
+
     pub fn record_task(&mut self, task_id: &TaskID, duration: Duration) -> &Task {
         let task = self.tasks.get_mut(task_id).expect("Task not found");
         task.record(duration);
-        self.dirty_tasks = true;
+        self.stats.logged += duration;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
         task
     }
 
+    /// 作業記録の時間を修正し、対応するタスクの実績時間にも差分を反映する
+    pub fn edit_log_item(&mut self, date: NaiveDate, index: usize, duration: Duration) -> anyhow::Result<&Task> {
+        let previous = self.log.edit_item(date, index, duration).map_err(anyhow::Error::msg)?;
+        let task = self.tasks.get_mut(&previous.task_id).expect("Task not found");
+        task.record(duration - previous.duration);
+        self.dirty.tasks = true;
+        self.dirty.worklog = true;
+        self.schedule_stale = true;
+        Ok(task)
+    }
+
+    /// 作業記録を削除し、対応するタスクの実績時間から差し引く
+    pub fn remove_log_item(&mut self, date: NaiveDate, index: usize) -> anyhow::Result<&Task> {
+        let removed = self.log.remove_item(date, index).map_err(anyhow::Error::msg)?;
+        let task = self.tasks.get_mut(&removed.task_id).expect("Task not found");
+        task.record(-removed.duration);
+        self.dirty.tasks = true;
+        self.dirty.worklog = true;
+        self.schedule_stale = true;
+        Ok(task)
+    }
+
+    /// 親タスクを設定 (None で解除) する。親子関係が循環する場合はエラー
+    pub fn set_parent(&mut self, task_id: &TaskID, parent: Option<TaskID>) -> anyhow::Result<&Task> {
+        let mut ancestor = parent;
+        while let Some(id) = ancestor {
+            if id == *task_id {
+                bail!("親子関係が循環するため設定できません");
+            }
+            ancestor = self.tasks.get(&id).and_then(|t| t.parent);
+        }
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        task.parent = parent;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        Ok(task)
+    }
+
+    /// 今日の最優先のタスクを固定する (None で解除)。完了済み・削除済みのタスクは固定できない
+    pub fn pin_task(&mut self, task_id: Option<TaskID>) -> anyhow::Result<Option<&Task>> {
+        if let Some(task) = task_id.map(|id| &self.tasks[&id]) {
+            if task.is_completed() {
+                bail!("完了済みのタスクは固定できません: {} - {}", self.short_id(&task.id), task.title);
+            }
+            if task.is_dropped() {
+                bail!("削除済みのタスクは固定できません: {} - {}", self.short_id(&task.id), task.title);
+            }
+        }
+        self.pinned = task_id;
+        self.dirty.pinned = true;
+        Ok(self.pinned_task())
+    }
+    /// 固定中のタスク (完了・削除されたら固定は外れたものとみなす)
+    pub fn pinned_task(&self) -> Option<&Task> {
+        self.pinned.and_then(|id| self.tasks.get(&id)).filter(|t| !t.is_completed() && !t.is_dropped())
+    }
+
+    /// フォローの間隔が過ぎた外部待ちのあるタスク
+    pub fn follow_ups(&self, now: NaiveDateTime) -> Vec<&Task> {
+        self.iter_tasks()
+            .filter(|t| matches!(t.status(), TaskStatus::Blocked(bs) if bs.externals.iter().any(|reason| reason.needs_follow_up(now))))
+            .collect()
+    }
+    /// 外部待ちをフォローした (先方に確認した) ことを記録し、次のフォローまでの間隔を数え直す
+    pub fn mark_followed_up(&mut self, task_id: &TaskID, now: NaiveDateTime) -> anyhow::Result<&Task> {
+        let short_id = self.short_id(task_id);
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        if !task.mark_followed_up(now) {
+            bail!("このタスクは外部待ちではありません: {} - {}", short_id, task.title);
+        }
+        self.dirty.tasks = true;
+        Ok(task)
+    }
+
+    /// `since` 以降に完了したタスクを完了日ごとにまとめる (各日は完了時刻順)
+    pub fn completed_by_day(&self, since: NaiveDate) -> BTreeMap<NaiveDate, Vec<(NaiveDateTime, &Task)>> {
+        let mut days: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for task in self.iter_tasks() {
+            if let TaskStatus::Completed(completed_at) = task.status()
+                && completed_at.date() >= since
+            {
+                days.entry(completed_at.date()).or_default().push((*completed_at, task));
+            }
+        }
+        for tasks in days.values_mut() {
+            tasks.sort_by_key(|(completed_at, task)| (*completed_at, task.id));
+        }
+        days
+    }
+
+    /// 作成から `days` 日以上経つのに一度も作業記録のない着手可能なタスク (古い順)
+    pub fn stale_tasks(&self, now: NaiveDateTime, days: i64) -> Vec<&Task> {
+        let mut tasks: Vec<_> = self
+            .iter_tasks()
+            .filter(|t| t.is_ready() && now - t.created_at >= Duration::days(days))
+            .filter(|t| self.log.total_recorded_duration(t.id).is_zero())
+            .collect();
+        tasks.sort_by_key(|t| (t.created_at, t.id));
+        tasks
+    }
+
+    /// タイトルの語を共有する、実績を記録して完了したタスク (実績の短い順)
+    pub fn similar_completed_tasks(&self, title: &str) -> Vec<&Task> {
+        let words = title_tokens(title);
+        let mut tasks: Vec<_> = self
+            .iter_tasks()
+            .filter(|t| t.is_completed() && !t.completed_without_time && !t.actual_total.is_zero())
+            .filter(|t| !title_tokens(&t.title).is_disjoint(&words))
+            .collect();
+        tasks.sort_by_key(|t| (t.actual_total, t.id));
+        tasks
+    }
+
+    /// 似たタイトルの完了タスクの実績から見積を提案する。
+    /// 実績の中央値を最尤、最短・最長を楽観・悲観とする (似たタスクがなければ None)
+    pub fn estimate_from_history(&self, title: &str) -> Option<Estimate> {
+        let actuals: Vec<_> = self.similar_completed_tasks(title).iter().map(|t| t.actual_total).collect();
+        let (&min, &max) = (actuals.first()?, actuals.last()?);
+        let mid = actuals.len() / 2;
+        let median = if actuals.len() % 2 == 0 { (actuals[mid - 1] + actuals[mid]) / 2 } else { actuals[mid] };
+        Some(Estimate::from_mop(median, min, max).expect("median is between min and max"))
+    }
+
     pub fn block_task_by_tasks(&mut self, task_id: &TaskID, dependencies: Vec<TaskID>) -> (&Task, Vec<&Task>) {
         let task = self.tasks.get_mut(task_id).expect("Task not found");
         task.block_by_task(dependencies.clone());
-        self.dirty_tasks = true;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
         let task = self.tasks.get(task_id).expect("Task not found");
         let dependencies: Vec<_> = dependencies.iter().filter_map(|id| self.tasks.get(id)).collect();
         (task, dependencies)
     }
 
-    pub fn block_task_by_external(&mut self, task_id: &TaskID, now: NaiveDateTime, until: Deadline, note: Option<String>) -> &Task {
+    /// 依存タスク `old` を `new` に差し替える。`new` が (間接的にでも) このタスクに依存していればエラー
+    pub fn replace_dependency(&mut self, task_id: &TaskID, old: &TaskID, new: &TaskID) -> anyhow::Result<&Task> {
+        if new == task_id {
+            bail!("自分自身には依存できません");
+        }
+        if self.tasks[new].is_dropped() {
+            bail!("削除済みのタスクには依存できません: {} - {}", self.short_id(new), self.tasks[new].title);
+        }
+        // new から依存をたどって task_id に着くなら循環する
+        let mut stack = vec![*new];
+        let mut visited = HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == *task_id {
+                bail!("依存関係が循環するため差し替えられません");
+            }
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(TaskStatus::Blocked(status)) = self.tasks.get(&id).map(|t| t.status()) {
+                stack.extend(status.tasks.iter().copied());
+            }
+        }
+        let (short_id, old_short_id) = (self.short_id(task_id), self.short_id(old));
+        let task = self.tasks.get_mut(task_id).expect("Task not found");
+        if !task.replace_dependency(*old, *new) {
+            bail!("{} は {} に依存していません", short_id, old_short_id);
+        }
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        Ok(task)
+    }
+
+    pub fn block_task_by_external(&mut self, task_id: &TaskID, now: NaiveDateTime, until: Deadline, note: Option<String>, remind_every: Option<Duration>) -> &Task {
         let task = self.tasks.get_mut(task_id).expect("Task not found");
         let reason = ExternalBlockingReason {
             may_unblock_at: until,
             note,
             last_updated: now,
+            remind_every,
         };
         task.block_by_external(reason);
-        self.dirty_tasks = true;
+        self.dirty.tasks = true;
+        self.schedule_stale = true;
+        task
+    }
+}
+
+/// 似たタスクを探すためにタイトルを語に分ける (小文字化)。
+/// 英数字は単語単位、日本語など空白で区切らない部分は 2 文字ずつに分ける (ひらがなだけの組は助詞・送り仮名なので使わない)
+fn title_tokens(title: &str) -> HashSet<String> {
+    let is_hiragana = |c: &char| ('\u{3041}'..='\u{309F}').contains(c);
+    let mut tokens = HashSet::new();
+    for word in title.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let chars: Vec<char> = word.chars().collect();
+        for run in chars.chunk_by(|a, b| a.is_ascii() == b.is_ascii()) {
+            if run[0].is_ascii() {
+                if run.len() >= 2 {
+                    tokens.insert(run.iter().collect());
+                }
+                continue;
+            }
+            tokens.extend(run.windows(2).filter(|pair| !pair.iter().all(is_hiragana)).map(|pair| pair.iter().collect()));
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+fn test_session(tasks: Vec<Task>) -> Session {
+    let calendar = Calendar::new((NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap()));
+    let tasks = tasks.into_iter().map(|t| (t.id, t)).collect();
+    Session::new(calendar, Config::default(), tasks, WorkLog::new(), PlanBaseline::new())
+}
+
+#[test]
+fn test_start_task_rejects_non_ready() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let ready = Task::new("Ready".into(), None, None);
+    let mut blocked = Task::new("Blocked".into(), None, None);
+    blocked.block_by_task(vec![ready.id]);
+    let mut completed = Task::new("Completed".into(), None, None);
+    completed.complete(now);
+    let mut dropped = Task::new("Dropped".into(), None, None);
+    dropped.drop();
+    let (ready_id, blocked_id, completed_id, dropped_id) = (ready.id, blocked.id, completed.id, dropped.id);
+    let mut session = test_session(vec![ready, blocked, completed, dropped]);
+
+    let err = session.start_task_at(&blocked_id, now).unwrap_err();
+    assert!(err.to_string().contains("ブロック中"));
+    let err = session.start_task_at(&completed_id, now).unwrap_err();
+    assert!(err.to_string().contains("完了済み"));
+    let err = session.start_task_at(&dropped_id, now).unwrap_err();
+    assert!(err.to_string().contains("削除済み"));
+    assert!(session.active_task.is_none());
+
+    session.start_task_at(&ready_id, now).unwrap();
+    assert_eq!(session.active_task, Some((ready_id, now)));
+}
+
+#[test]
+fn test_start_task_after_blocker_completed() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let blocker = Task::new("B".into(), None, None);
+    let mut dependent = Task::new("A".into(), None, None);
+    dependent.block_by_task(vec![blocker.id]);
+    let (blocker_id, dependent_id) = (blocker.id, dependent.id);
+    let mut session = test_session(vec![blocker, dependent]);
+
+    // blt A B; start A はまだ B が終わっていないので拒否
+    assert!(session.start_task_at(&dependent_id, now).unwrap_err().to_string().contains("ブロック中"));
+
+    // comp B; start A
+    session.complete_task(&blocker_id, now, Some(Duration::minutes(30)));
+    session.start_task_at(&dependent_id, now).unwrap();
+    assert_eq!(session.active_task, Some((dependent_id, now)));
+
+    // 外部待ちが残っていれば着手できない
+    session.active_task = None;
+    let reason = ExternalBlockingReason {
+        note: Some("返事待ち".into()),
+        may_unblock_at: Deadline::Unknown,
+        last_updated: now,
+        remind_every: None,
+    };
+    session.tasks.get_mut(&dependent_id).unwrap().block_by_external(reason);
+    assert!(session.start_task_at(&dependent_id, now).is_err());
+}
+
+#[test]
+fn test_set_parent_rejects_cycle() {
+    let a = Task::new("A".into(), None, None);
+    let b = Task::new("B".into(), None, None);
+    let c = Task::new("C".into(), None, None);
+    let (a_id, b_id, c_id) = (a.id, b.id, c.id);
+    let mut session = test_session(vec![a, b, c]);
+
+    session.set_parent(&b_id, Some(a_id)).unwrap();
+    session.set_parent(&c_id, Some(b_id)).unwrap();
+    assert!(session.set_parent(&a_id, Some(c_id)).is_err());
+    assert!(session.set_parent(&a_id, Some(a_id)).is_err());
+    session.set_parent(&c_id, None).unwrap();
+    assert!(session.tasks[&c_id].parent.is_none());
+}
+
+#[test]
+fn test_find_duplicate_task() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let open = Task::new("Review PR".into(), None, None);
+    let mut completed = Task::new("Write docs".into(), None, None);
+    completed.complete(now);
+    let open_id = open.id;
+    let session = test_session(vec![open, completed]);
+
+    assert_eq!(session.find_duplicate_task("  review pr ").map(|t| t.id), Some(open_id));
+    assert!(session.find_duplicate_task("write docs").is_none());
+    assert!(session.find_duplicate_task("Review").is_none());
+}
+
+#[test]
+fn test_schedule_if_stale() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let task = Task::new("A".into(), None, None);
+    let task_id = task.id;
+    let mut session = test_session(vec![task]);
+    session.calendar.add_working_day(now.date(), true);
+    session.calendar.add_working_day(now.date() + Duration::days(1), true);
+
+    session.schedule_if_stale(now).unwrap();
+    assert_eq!(session.scheduled_at, Some(now));
+
+    // 読み取りだけでは再計算しない
+    let later = now + Duration::hours(1);
+    session.schedule_if_stale(later).unwrap();
+    assert_eq!(session.scheduled_at, Some(now));
+    // 着手したら作業中のタスクを先頭にするため再計算する
+    session.start_task_at(&task_id, later).unwrap();
+    session.schedule_if_stale(later).unwrap();
+    assert_eq!(session.scheduled_at, Some(later));
+
+    // タスクを変更したら再計算する
+    let later = later + Duration::minutes(30);
+    session.estimate_task(&task_id, Estimate::new(Duration::hours(2)), later).unwrap();
+    session.schedule_if_stale(later).unwrap();
+    assert_eq!(session.scheduled_at, Some(later));
+
+    // 日付が変わったら再計算する
+    let tomorrow = later + Duration::days(1);
+    session.schedule_if_stale(tomorrow).unwrap();
+    assert_eq!(session.scheduled_at, Some(tomorrow));
+}
+
+#[test]
+fn test_find_task_by_prefix_large_backlog() {
+    let tasks: Vec<Task> = (0..10_000).map(|i| Task::new(format!("Task {}", i), None, None)).collect();
+    let ids: Vec<TaskID> = tasks.iter().map(|t| t.id).collect();
+    let session = test_session(tasks);
+
+    for id in &ids {
+        // 32 桁の完全な ID なら必ず一意に見つかる
+        assert_eq!(session.find_task_by_prefix(&id.hex()), Some(*id));
+    }
+    // 1 桁では 10k 件の中で必ず複数該当する
+    assert_eq!(session.find_task_by_prefix(&ids[0].hex()[..1]), None);
+    assert_eq!(session.find_task_by_prefix(""), None);
+    assert_eq!(session.find_task_by_prefix("xyz"), None);
+
+    // 旧実装 (全件走査) と同じ結果になること
+    for id in ids.iter().take(50) {
+        for len in 1..=6 {
+            let prefix = &id.hex()[..len];
+            let scanned: Vec<_> = session.tasks.keys().filter(|k| k.starts_with(prefix)).collect();
+            let expected = if scanned.len() == 1 { Some(*scanned[0]) } else { None };
+            assert_eq!(session.find_task_by_prefix(prefix), expected);
+        }
+    }
+}
+
+#[test]
+fn test_short_id_grows_on_collision() {
+    let task_with_id = |prefix: &[u8]| {
+        let mut bytes = [0u8; 16];
+        bytes[..prefix.len()].copy_from_slice(prefix);
+        let mut task = Task::new("T".into(), None, None);
+        task.id = TaskID::from(bytes);
         task
+    };
+    let mut session = test_session(vec![task_with_id(&[0x12, 0x34, 0x56, 0x00])]);
+    let first = *session.tasks.keys().next().unwrap();
+    assert_eq!(session.short_id(&first).to_string(), "#1234");
+    // 先頭 6 桁が同じタスクを追加すると、区別できるまで桁を伸ばす
+    let second = session.add_task(task_with_id(&[0x12, 0x34, 0x56, 0x10])).id;
+    assert_eq!(session.short_id(&first).to_string(), "#1234560");
+    assert_eq!(session.short_id(&second).to_string(), "#1234561");
+}
+
+#[test]
+fn test_find_open_tasks_by_tag() {
+    let tasks: Vec<_> = (0..4).map(|i| Task::new(format!("chore {}", i), None, None)).collect();
+    let chores: Vec<_> = tasks[..3].iter().map(|t| t.id).collect();
+    let other = tasks[3].id;
+    let mut session = test_session(tasks);
+    for task_id in &chores {
+        session.update_tags(task_id, &["chores".to_string()], &[]);
     }
+    session.update_tags(&other, &["chores".to_string(), "misc".to_string()], &["chores".to_string()]);
+    session.complete_task(&chores[2], chrono::Local::now().naive_local(), None);
+
+    let mut expected = chores[..2].to_vec();
+    expected.sort();
+    assert_eq!(session.find_open_tasks_by_tag("chores"), expected);
+    assert_eq!(session.find_open_tasks_by_tag("misc"), vec![other]);
+}
+
+#[test]
+fn test_category_colors() {
+    let tasks: Vec<_> = (0..3).map(|i| Task::new(format!("task {}", i), None, None)).collect();
+    let ids: Vec<_> = tasks.iter().map(|t| t.id).collect();
+    let mut session = test_session(tasks);
+    session.set_category(&ids[0], Some("Work".to_string()));
+    session.set_category(&ids[1], Some("Personal".to_string()));
+    session.set_category(&ids[2], Some("Learning".to_string()));
+    // 指定のないカテゴリは名前順に色を割り振る
+    let colors = session.category_colors();
+    assert_eq!(colors["Learning"], CategoryColor::PALETTE[0]);
+    assert_eq!(colors["Personal"], CategoryColor::PALETTE[1]);
+    assert_eq!(colors["Work"], CategoryColor::PALETTE[2]);
+
+    session.config.category_colors.insert("Learning".to_string(), CategoryColor::Red);
+    let colors = session.category_colors();
+    assert_eq!(colors["Learning"], CategoryColor::Red);
+    assert_eq!(colors["Personal"], CategoryColor::PALETTE[0]);
+
+    session.set_category(&ids[2], None);
+    assert!(session.tasks[&ids[2]].category.is_none());
+    assert!(session.dirty.tasks);
+}
+
+#[test]
+fn test_unscheduled_tasks() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T09:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    // A は 2 日分のカレンダーに収まらず、A を待つ B は一度も割り当てられない
+    let mut a = Task::new("A".into(), None, None);
+    a.update_remaining(Estimate::new(Duration::hours(100)), now).unwrap();
+    let mut b = Task::new("B".into(), None, None);
+    b.block_by_task(vec![a.id]);
+    let (a_id, b_id) = (a.id, b.id);
+    let mut session = test_session(vec![a, b]);
+    session.calendar.add_working_day(now.date(), true);
+    session.calendar.add_working_day(now.date() + Duration::days(1), true);
+
+    session.schedule(now).unwrap();
+    assert!(session.slots.total(&a_id) > Duration::zero());
+    assert_eq!(session.unscheduled_tasks().iter().map(|t| t.id).collect::<Vec<_>>(), vec![b_id]);
+}
+
+#[test]
+fn test_reopen_task() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let a = Task::new("A".into(), None, None);
+    let mut b = Task::new("B".into(), None, None);
+    b.block_by_task(vec![a.id]);
+    let (a_id, b_id) = (a.id, b.id);
+    let mut session = test_session(vec![a, b]);
+
+    assert!(session.reopen_task(&a_id).is_err(), "未完了のタスクは戻せない");
+    session.complete_task(&a_id, now, Some(Duration::minutes(30)));
+    let task = session.reopen_task(&a_id).unwrap();
+    assert!(task.is_ready());
+    // 完了時の 100% は取り消され、実績から算出される (見積がないので 0%)
+    assert_eq!(u8::from(task.progress()), 0);
+
+    // 後続の B に着手済みなら取り消せない
+    session.complete_task(&a_id, now, None);
+    session.tasks.get_mut(&b_id).unwrap().record(Duration::minutes(10));
+    assert!(session.reopen_task(&a_id).is_err());
+    assert!(session.tasks[&a_id].is_completed());
+
+    // 削除したタスクも戻せる
+    let c = Task::new("C".into(), None, None);
+    let c_id = c.id;
+    session.tasks.insert(c_id, c);
+    session.drop_task(&c_id);
+    assert!(session.update_progress_task(&c_id, None).is_err());
+    assert!(session.reopen_task(&c_id).unwrap().is_ready());
+}
+
+#[test]
+fn test_slots_are_rebuilt_after_recording() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T09:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut task = Task::new("A".into(), None, None);
+    task.update_remaining(Estimate::new(Duration::hours(2)), now).unwrap();
+    let task_id = task.id;
+    let mut session = test_session(vec![task]);
+    session.calendar.add_working_day(now.date(), true);
+    session.schedule(now).unwrap();
+    assert_eq!(session.slots.get(&now.date())[&task_id], Duration::hours(2));
+
+    // 1 時間作業して止めると、次の再計算で残りの 1 時間だけが割り当て直される
+    let stopped_at = now + Duration::hours(1);
+    session.start_task_at(&task_id, now).unwrap();
+    session.stop_current_task(StopKind::EndsAt(stopped_at), false).unwrap();
+    session.schedule_if_stale(stopped_at).unwrap();
+    assert_eq!(session.slots.get(&now.date())[&task_id], Duration::hours(1));
+    assert_eq!(session.slots.total(&task_id), Duration::hours(1));
+}
+
+#[test]
+fn test_replace_dependency() {
+    let a = Task::new("A".into(), None, None);
+    let a2 = Task::new("A'".into(), None, None);
+    let mut b = Task::new("B".into(), None, None);
+    b.block_by_task(vec![a.id]);
+    let mut c = Task::new("C".into(), None, None);
+    c.block_by_task(vec![b.id]);
+    let (a_id, a2_id, b_id, c_id) = (a.id, a2.id, b.id, c.id);
+    let mut session = test_session(vec![a, a2, b, c]);
+
+    session.replace_dependency(&b_id, &a_id, &a2_id).unwrap();
+    assert!(matches!(session.tasks[&b_id].status(), TaskStatus::Blocked(bs) if bs.tasks == vec![a2_id]));
+    // 依存していないタスクは差し替えられない
+    assert!(session.replace_dependency(&b_id, &a_id, &a2_id).is_err());
+    // C は B に依存しているので、B の依存を C にすると循環する
+    assert!(session.replace_dependency(&b_id, &a2_id, &c_id).is_err());
+    assert!(session.replace_dependency(&b_id, &a2_id, &b_id).is_err());
+    assert!(matches!(session.tasks[&b_id].status(), TaskStatus::Blocked(bs) if bs.tasks == vec![a2_id]));
+}
+
+#[test]
+fn test_follow_ups() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let waiting = Task::new("Waiting".into(), None, None);
+    let quiet = Task::new("Quiet".into(), None, None);
+    let (waiting_id, quiet_id) = (waiting.id, quiet.id);
+    let mut session = test_session(vec![waiting, quiet]);
+    session.block_task_by_external(&waiting_id, now, Deadline::Unknown, None, Some(Duration::days(2)));
+    session.block_task_by_external(&quiet_id, now, Deadline::Unknown, None, None);
+
+    assert!(session.follow_ups(now + Duration::days(1)).is_empty());
+    let later = now + Duration::days(2);
+    assert_eq!(session.follow_ups(later).iter().map(|t| t.id).collect::<Vec<_>>(), vec![waiting_id]);
+
+    // フォローしたら次の間隔まで出ない
+    session.mark_followed_up(&waiting_id, later).unwrap();
+    assert!(session.follow_ups(later + Duration::days(1)).is_empty());
+    assert!(!session.follow_ups(later + Duration::days(2)).is_empty());
+}
+
+#[test]
+fn test_stale_tasks() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut oldest = Task::new("Oldest".into(), None, None);
+    oldest.created_at = now - Duration::days(60);
+    let mut old = Task::new("Old".into(), None, None);
+    old.created_at = now - Duration::days(30);
+    let mut worked = Task::new("Worked".into(), None, None);
+    worked.created_at = now - Duration::days(40);
+    let mut fresh = Task::new("Fresh".into(), None, None);
+    fresh.created_at = now - Duration::days(3);
+    let mut completed = Task::new("Completed".into(), None, None);
+    completed.created_at = now - Duration::days(90);
+    completed.complete(now);
+    let (oldest_id, old_id, worked_id) = (oldest.id, old.id, worked.id);
+    let mut session = test_session(vec![oldest, old, worked, fresh, completed]);
+    session.log.add_item(now.date(), worked_id, now.time(), Duration::minutes(15));
+
+    let stale: Vec<_> = session.stale_tasks(now, 14).iter().map(|t| t.id).collect();
+    assert_eq!(stale, vec![oldest_id, old_id]);
+    assert_eq!(session.stale_tasks(now, 45).len(), 1);
+}
+
+#[test]
+fn test_estimate_from_history() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let done = |title: &str, minutes: i64| {
+        let mut task = Task::new(title.into(), None, None);
+        task.actual_total = Duration::minutes(minutes);
+        task.complete(now);
+        task
+    };
+    let mut no_time = done("週次レポート作成 (記録なし)", 0);
+    no_time.completed_without_time = true;
+    let open = Task::new("週次レポート作成".into(), None, None);
+    let session = test_session(vec![
+        done("週次レポート作成", 60),
+        done("月次レポートの作成", 30),
+        done("Weekly report", 90),
+        done("レポート提出", 120),
+        done("会議の準備", 10),
+        no_time,
+        open,
+    ]);
+
+    assert_eq!(session.similar_completed_tasks("週次レポート").len(), 3);
+    // 60m / 30m / 120m の中央値を最尤、最短・最長を楽観・悲観に
+    let estimate = session.estimate_from_history("週次レポート").unwrap();
+    assert_eq!(estimate.most_likely, Duration::minutes(60));
+    assert_eq!(estimate.optimistic, Duration::minutes(30));
+    assert_eq!(estimate.pessimistic, Duration::minutes(120));
+    // 英語は単語単位 (大文字小文字を区別しない)、件数が偶数なら中央の 2 件の平均
+    let estimate = session.estimate_from_history("REPORT review / 月次レポート").unwrap();
+    assert_eq!(estimate.most_likely, Duration::minutes(75));
+    assert!(session.estimate_from_history("のための").is_none());
+    assert!(session.estimate_from_history("新規タスク").is_none());
+}
+
+#[test]
+fn test_pin_task() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let task = Task::new("Must do".into(), None, None);
+    let mut dropped = Task::new("Dropped".into(), None, None);
+    dropped.drop();
+    let (task_id, dropped_id) = (task.id, dropped.id);
+    let mut session = test_session(vec![task, dropped]);
+
+    assert!(session.pin_task(Some(dropped_id)).is_err());
+    assert_eq!(session.pin_task(Some(task_id)).unwrap().map(|t| t.id), Some(task_id));
+    assert!(session.dirty.pinned);
+
+    // 完了したら固定は外れる
+    session.tasks.get_mut(&task_id).unwrap().complete(now);
+    assert!(session.pinned_task().is_none());
+}
+
+#[test]
+fn test_update_progress_rejects_closed_tasks() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut completed = Task::new("Completed".into(), None, None);
+    completed.complete(now);
+    let mut dropped = Task::new("Dropped".into(), None, None);
+    dropped.drop();
+    let open = Task::new("Open".into(), None, None);
+    let (completed_id, dropped_id, open_id) = (completed.id, dropped.id, open.id);
+    let mut session = test_session(vec![completed, dropped, open]);
+    let progress = Progress::try_from(80).ok();
+
+    assert!(session.update_progress_task(&completed_id, progress).unwrap_err().to_string().contains("reopen"));
+    assert!(session.update_progress_task(&dropped_id, progress).is_err());
+    // 完了済みのタスクは 100% のまま
+    assert_eq!(session.tasks[&completed_id].progress().to_string(), Progress::try_from(100).unwrap().to_string());
+    assert!(session.update_progress_task(&open_id, progress).unwrap().progress.is_some());
+}
+
+#[test]
+fn test_dirty_flags_are_precise() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let task = Task::new("A".into(), None, None);
+    let task_id = task.id;
+    let mut session = test_session(vec![task]);
+
+    // 記録なしの中断では何も保存しない
+    session.start_task_at(&task_id, now).unwrap();
+    session.stop_current_task(StopKind::Immediately(now), false).unwrap();
+    assert!(!session.dirty.tasks && !session.dirty.worklog);
+
+    // 時間を記録したらタスクと作業記録の両方
+    session.start_task_at(&task_id, now).unwrap();
+    session.stop_current_task(StopKind::EndsIn(Duration::minutes(30)), false).unwrap();
+    assert!(session.dirty.tasks && session.dirty.worklog);
+    assert!(!session.dirty.calendar && !session.dirty.pinned);
+
+    // 追加した予定は保存待ちに残り、同じ予定は二重に追加しない
+    let day = now.date();
+    session.calendar.add_working_day(day, true);
+    let item = ScheduleItem {
+        start: NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+        duration: Duration::hours(1),
+        note: Some("MTG".into()),
+    };
+    assert!(session.add_busy_item(day, item.clone()));
+    assert!(!session.add_busy_item(day, item));
+    assert!(session.dirty.calendar);
+    assert_eq!(session.added_busy_items[&day].len(), 1);
+}
+
+#[test]
+fn test_low_slack_tasks() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T09:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut tight = Task::new("Tight".into(), None, None);
+    tight.update_remaining(Estimate::new(Duration::hours(6)), now).unwrap();
+    tight.deadline = Deadline::Exact(now + Duration::hours(7));
+    let mut relaxed = Task::new("Relaxed".into(), None, None);
+    relaxed.update_remaining(Estimate::new(Duration::hours(1)), now).unwrap();
+    relaxed.deadline = Deadline::Exact(now + Duration::days(5));
+    let no_deadline = Task::new("No deadline".into(), None, None);
+    let tight_id = tight.id;
+    let mut session = test_session(vec![tight, relaxed, no_deadline]);
+    for day in now.date().iter_days().take(7) {
+        session.calendar.add_working_day(day, true);
+    }
+
+    let low = session.low_slack_tasks(now);
+    assert_eq!(low.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![tight_id]);
+    assert!(low[0].1 < 1.0);
+
+    // 0 にすると、余裕が残っているタスクは警告しない (最遅開始を過ぎたタスクだけ)
+    session.update_setting("min_slack_days", "0").unwrap();
+    assert!(session.low_slack_tasks(now).is_empty());
+}
+
+#[test]
+fn test_import_log_item() {
+    let existing = Task::new("週次レポート".into(), None, None);
+    let existing_id = existing.id;
+    let mut session = test_session(vec![existing]);
+    let date = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+    let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+
+    assert_eq!(session.import_log_item(date, nine, Duration::minutes(30), " 週次レポート "), LogImport::Matched(existing_id));
+    let LogImport::Created(created_id) = session.import_log_item(date, nine + Duration::hours(1), Duration::minutes(45), "Code review") else {
+        panic!("task should be created");
+    };
+    assert_eq!(
+        session.import_log_item(date, nine + Duration::hours(2), Duration::minutes(15), "code REVIEW"),
+        LogImport::Matched(created_id)
+    );
+    // 同じ記録を取り込み直しても二重にならない
+    assert_eq!(session.import_log_item(date, nine, Duration::minutes(30), "週次レポート"), LogImport::Duplicate(existing_id));
+
+    assert_eq!(session.tasks[&existing_id].actual_total, Duration::minutes(30));
+    assert_eq!(session.tasks[&created_id].actual_total, Duration::hours(1));
+    assert_eq!(session.tasks[&created_id].created_at, date.and_time(nine + Duration::hours(1)));
+    assert_eq!(session.log.total_recorded_duration(created_id), Duration::hours(1));
+    assert!(session.dirty.worklog && session.dirty.tasks);
+}
+
+#[test]
+fn test_completed_by_day() {
+    let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+    let done = |title: &str, completed_at: &str| {
+        let mut task = Task::new(title.into(), None, None);
+        task.complete(at(completed_at));
+        task
+    };
+    let session = test_session(vec![
+        done("Afternoon", "2025-05-07T15:00:00"),
+        done("Morning", "2025-05-07T09:30:00"),
+        done("Yesterday", "2025-05-06T18:00:00"),
+        done("Last week", "2025-04-30T12:00:00"),
+        Task::new("Open".into(), None, None),
+    ]);
+
+    let days = session.completed_by_day(NaiveDate::from_ymd_opt(2025, 5, 6).unwrap());
+    let titles: Vec<Vec<_>> = days.values().map(|tasks| tasks.iter().map(|(_, t)| t.title.as_str()).collect()).collect();
+    assert_eq!(titles, vec![vec!["Yesterday"], vec!["Morning", "Afternoon"]]);
+}
+
+#[test]
+fn test_start_allocation_follows_todays_plan() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T09:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut planned = Task::new("Planned".into(), None, None);
+    planned.update_remaining(Estimate::new(Duration::hours(3)), now).unwrap();
+    let mut later = Task::new("Later".into(), None, None);
+    later.update_remaining(Estimate::new(Duration::hours(2)), now).unwrap();
+    later.not_before = Some(now + Duration::days(1));
+    let (planned_id, later_id) = (planned.id, later.id);
+    let mut session = test_session(vec![planned, later]);
+    for day in now.date().iter_days().take(3) {
+        session.calendar.add_working_day(day, true);
+    }
+    let meeting = ScheduleItem {
+        start: NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+        duration: Duration::hours(1),
+        note: None,
+    };
+    session.add_busy_item(now.date(), meeting);
+    session.schedule(now).unwrap();
+
+    // 今日の計画 (3 時間) をそのまま割り当て時間にする (work_tick の 25 分で打ち切らない)
+    let (_, allocated) = session.start_task_at(&planned_id, now).unwrap();
+    assert_eq!(allocated, session.slots.remaining_at(&now.date(), planned_id).unwrap());
+    assert_eq!(allocated, Duration::hours(3));
+    // 次の予定までで打ち切る
+    let at_noon = now + Duration::hours(3);
+    assert_eq!(session.allocation_at(&planned_id, at_noon), Duration::hours(1));
+    // 予定中に着手したら予定の後の空き時間から数える
+    assert_eq!(session.allocation_at(&planned_id, at_noon + Duration::minutes(90)), Duration::hours(3));
+    // focus の区切りは work_tick のまま
+    assert_eq!(session.tick_allocation_at(&planned_id, now), session.scheduler.work_tick);
+
+    // 今日の計画にないタスクは work_tick まで
+    assert_eq!(session.allocation_at(&later_id, now), session.scheduler.work_tick);
+}
+
+#[test]
+fn test_record_untouched_plan_is_idempotent() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T09:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut task = Task::new("Planned".into(), None, None);
+    task.update_remaining(Estimate::new(Duration::hours(2)), now).unwrap();
+    let task_id = task.id;
+    let mut session = test_session(vec![task]);
+    session.calendar.add_working_day(now.date(), true);
+    session.schedule(now).unwrap();
+
+    let end_of_day = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+    assert_eq!(session.record_untouched_plan(now.date(), end_of_day).unwrap(), vec![task_id]);
+    // 2 回目は記録済みなので何も残さない
+    assert!(session.record_untouched_plan(now.date(), end_of_day).unwrap().is_empty());
+    assert_eq!(session.log.get_items(now.date()).unwrap().len(), 1);
+}
+
+#[test]
+fn test_unreachable_deadline() {
+    let now = chrono::NaiveDateTime::parse_from_str("2025-05-07T09:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut prerequisite = Task::new("Prerequisite".into(), None, None);
+    prerequisite.update_remaining(Estimate::new(Duration::hours(6)), now).unwrap();
+    let mut review = Task::new("Review".into(), None, None);
+    review.update_remaining(Estimate::new(Duration::hours(1)), now).unwrap();
+    review.block_by_task(vec![prerequisite.id]);
+    let review_id = review.id;
+    let mut session = test_session(vec![prerequisite, review]);
+    for day in now.date().iter_days().take(7) {
+        session.calendar.add_working_day(day, true);
+    }
+
+    // 前提のタスクが 6 時間かかるので、今日の昼には終わらない
+    session.set_deadline(&review_id, Deadline::Exact(now + Duration::hours(3)));
+    let (finish, deadline) = session.unreachable_deadline(now, &review_id).unwrap();
+    assert_eq!(deadline, now + Duration::hours(3));
+    assert!(finish > now + Duration::hours(7));
+
+    session.set_deadline(&review_id, Deadline::Exact(now + Duration::days(2)));
+    assert!(session.unreachable_deadline(now, &review_id).is_none());
+    session.set_deadline(&review_id, Deadline::None);
+    assert!(session.unreachable_deadline(now, &review_id).is_none());
 }
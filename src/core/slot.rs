@@ -4,11 +4,24 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use uuid::Uuid;
 
-#[derive(Debug)]
+/// 日付ごと・タスクごとの割り当て時間。
+///
+/// スロットは「その時点の残り時間をどう割り振るか」の計算結果で、作業を記録して減らすものではない。
+/// 実績の記録やタスクの変更のたびに `Session::schedule` で丸ごと作り直す
+/// (その日の当初の計画は `PlanBaseline` が保持する)。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SlotMap {
     slots: BTreeMap<NaiveDate, BTreeMap<TaskID, Duration>>,
+    #[serde(skip)]
     empty_slots: BTreeMap<TaskID, Duration>,
 }
+
+/// schedule.json に保存する、最後に計算したスケジュール
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleSnapshot {
+    pub scheduled_at: NaiveDateTime,
+    pub slots: SlotMap,
+}
 impl SlotMap {
     pub fn new() -> Self {
         Self {
@@ -30,15 +43,9 @@ impl SlotMap {
         }
     }
 
-    pub fn consume(&mut self, date: &NaiveDate, task_id: TaskID, duration: Duration) {
-        if let Some(tasks) = self.slots.get_mut(date) {
-            if let Some(allocated) = tasks.get_mut(&task_id) {
-                *allocated -= duration;
-                if *allocated <= Duration::zero() {
-                    tasks.remove(&task_id);
-                }
-            }
-        }
+    /// スケジュール全期間でタスクに割り当てた時間の合計
+    pub fn total(&self, task_id: &TaskID) -> Duration {
+        self.slots.values().filter_map(|tasks| tasks.get(task_id)).sum()
     }
 
     pub fn get(&self, date: &NaiveDate) -> &BTreeMap<TaskID, Duration> {
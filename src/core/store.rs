@@ -1,15 +1,19 @@
-use chrono::NaiveDate;
+use anyhow::{Context, bail};
+use chrono::{Duration, NaiveDate, NaiveTime};
 
 use super::{
-    slot::SlotMap,
-    task::{self, Task, TaskID},
+    burndown::BurndownSnapshot,
+    plan::PlanBaseline,
+    slot::{ScheduleSnapshot, SlotMap},
+    task::{self, Task, TaskID, TaskStatus},
+    utils::parse_human_duration,
     work_log::{WorkLog, WorkLogItem},
 };
 use std::{
     collections::{BTreeMap, HashMap},
-    fs::File,
-    io::{BufWriter, Write},
-    path::{self, Path},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{self, Path, PathBuf},
 };
 
 pub fn save_tasks<P: AsRef<Path>>(tasks: &BTreeMap<TaskID, Task>, path: P) -> anyhow::Result<()> {
@@ -25,11 +29,127 @@ pub fn load_tasks<P: AsRef<Path>>(path: P) -> anyhow::Result<BTreeMap<TaskID, Ta
         return Ok(BTreeMap::new()); // Return an empty vector if the file does not exist
     }
     let file = File::open(path)?;
-    let tasks: Vec<Task> = serde_json::from_reader(file)?;
+    let mut tasks: Vec<Task> = serde_json::from_reader(file)?;
+    for task in &mut tasks {
+        task.migrate_legacy_note();
+    }
     let tasks = tasks.into_iter().map(|task| (task.id, task)).collect();
     Ok(tasks)
 }
 
+/// 完了したタスクの見積と実績を CSV に書き出す (時間は分単位)。
+/// 見積のないタスクと、実績を記録せずに完了したタスクは含めない。書き出した件数を返す
+pub fn export_accuracy_csv<P: AsRef<Path>>(tasks: &BTreeMap<TaskID, Task>, path: P) -> anyhow::Result<usize> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "id,title,tags,completed_at,mean,optimistic,most_likely,pessimistic,actual")?;
+    let mut count = 0;
+    for task in tasks.values() {
+        let (TaskStatus::Completed(completed_at), Some(estimate), Some(actual)) = (task.status(), task.estimate(), task.actual_for_accuracy()) else {
+            continue;
+        };
+        if actual.is_zero() {
+            continue;
+        }
+        let tags = task.tags.iter().cloned().collect::<Vec<_>>().join(" ");
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            task.id.hex(),
+            csv_field(&task.title),
+            csv_field(&tags),
+            completed_at.format("%Y-%m-%d %H:%M"),
+            estimate.mean().num_minutes(),
+            estimate.optimistic.num_minutes(),
+            estimate.most_likely.num_minutes(),
+            estimate.pessimistic.num_minutes(),
+            actual.num_minutes()
+        )?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// 他のツールから取り込む作業記録の 1 行
+#[derive(Debug, Clone)]
+pub struct WorkLogCsvRow {
+    pub date: NaiveDate,
+    pub begin_at: NaiveTime,
+    pub duration: Duration,
+    pub title: String,
+}
+
+/// `date,begin,duration,task-title` の CSV (1 行目は見出しでもよい) を読み込む。
+/// date は YYYY-MM-DD、begin は HH:MM、duration は 30m / 1h のような表記か分数。解釈できない行があればその行番号でエラーにする
+pub fn load_worklog_csv<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<WorkLogCsvRow>> {
+    let path = path.as_ref();
+    let reader = BufReader::new(File::open(path).with_context(|| format!("{} を読み込めませんでした", path.display()))?);
+    let mut rows = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || (index == 0 && line.starts_with("date")) {
+            continue;
+        }
+        let row = parse_worklog_csv_row(&line).map_err(|err| anyhow::anyhow!("{} 行目を解釈できません ({}): {}", index + 1, err, line))?;
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn parse_worklog_csv_row(line: &str) -> anyhow::Result<WorkLogCsvRow> {
+    let fields = split_csv_line(line);
+    let [date, begin, duration, title] = fields.as_slice() else {
+        bail!("列は date,begin,duration,task-title の 4 つにしてください");
+    };
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").context("date は YYYY-MM-DD で指定してください")?;
+    let begin_at = NaiveTime::parse_from_str(begin.trim(), "%H:%M").context("begin は HH:MM で指定してください")?;
+    let duration = match duration.trim().parse::<i64>() {
+        Ok(minutes) => Duration::minutes(minutes),
+        Err(_) => parse_human_duration(duration.trim()).with_context(|| format!("duration を解釈できません: {}", duration))?,
+    };
+    if duration <= Duration::zero() {
+        bail!("duration は 0 より大きくしてください");
+    }
+    let title = title.trim();
+    if title.is_empty() {
+        bail!("task-title が空です");
+    }
+    Ok(WorkLogCsvRow {
+        date,
+        begin_at,
+        duration,
+        title: title.to_string(),
+    })
+}
+
+/// CSV の 1 行を列に分ける (`csv_field` の逆。`"` で囲んだ値の中のカンマと `""` を扱う)
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// カンマ・引用符・改行を含む値を `"` で囲む
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 pub fn save_worklog<P: AsRef<Path>>(worklog: &WorkLog, path: P) -> anyhow::Result<()> {
     let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
@@ -46,3 +166,122 @@ pub fn load_worklog<P: AsRef<Path>>(path: P) -> anyhow::Result<WorkLog> {
     let worklog = WorkLog::from_items(items);
     Ok(worklog)
 }
+
+pub fn save_schedule<P: AsRef<Path>>(snapshot: &ScheduleSnapshot, path: P) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, snapshot)?;
+    Ok(())
+}
+
+pub fn load_schedule<P: AsRef<Path>>(path: P) -> anyhow::Result<Option<ScheduleSnapshot>> {
+    if !path.as_ref().exists() {
+        return Ok(None);
+    }
+    let file = File::open(path)?;
+    Ok(Some(serde_json::from_reader(file)?))
+}
+
+pub fn save_baseline<P: AsRef<Path>>(baseline: &PlanBaseline, path: P) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, baseline)?;
+    Ok(())
+}
+
+pub fn load_baseline<P: AsRef<Path>>(path: P) -> anyhow::Result<PlanBaseline> {
+    if !path.as_ref().exists() {
+        return Ok(PlanBaseline::new());
+    }
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+pub fn save_pinned<P: AsRef<Path>>(pinned: Option<TaskID>, path: P) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, &pinned)?;
+    Ok(())
+}
+
+pub fn load_pinned<P: AsRef<Path>>(path: P) -> anyhow::Result<Option<TaskID>> {
+    if !path.as_ref().exists() {
+        return Ok(None);
+    }
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+pub fn append_burndown<P: AsRef<Path>>(snapshot: &BurndownSnapshot, path: P) -> anyhow::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, snapshot)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+pub fn load_burndown<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<BurndownSnapshot>> {
+    if !path.as_ref().exists() {
+        return Ok(Vec::new());
+    }
+    let file = File::open(path)?;
+    let mut snapshots = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        snapshots.push(serde_json::from_str(&line)?);
+    }
+    Ok(snapshots)
+}
+
+/// 複数起動による上書きを防ぐためのアドバイザリロック。
+/// ロックファイルには保持しているプロセスの PID を書き込み、Drop 時に削除する。
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// ロックを取得する。他のプロセスが保持している場合は `Ok(None)` を返す。
+    /// 保持していたプロセスが既に終了している (古いロック) 場合は取り直す。
+    pub fn acquire<P: AsRef<Path>>(path: P) -> anyhow::Result<Option<Self>> {
+        let path = path.as_ref();
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", std::process::id())?;
+                    return Ok(Some(Self { path: path.to_path_buf() }));
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if !Self::is_stale(path) {
+                        return Ok(None);
+                    }
+                    std::fs::remove_file(path)?;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(None)
+    }
+
+    /// ロックを保持していたプロセスが存在しないかどうか。
+    /// プロセスの生存確認ができない環境 (/proc がない) では古いロックとみなさない。
+    fn is_stale(path: &Path) -> bool {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return false;
+        };
+        let Ok(pid) = content.trim().parse::<u32>() else {
+            return false;
+        };
+        let proc = Path::new("/proc");
+        proc.is_dir() && !proc.join(pid.to_string()).exists()
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
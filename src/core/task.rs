@@ -1,21 +1,63 @@
-use super::{deadline::Deadline, estimate::Estimate};
-use chrono::{Duration, NaiveDateTime};
+use super::{
+    calendar::Calendar,
+    deadline::{Deadline, FuzzyDeadline},
+    estimate::Estimate,
+    utils::{DurationUnits, format_human_duration_in},
+};
+use chrono::{Duration, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
 
+/// 少なすぎると打ち間違いで別のタスクに当たりやすいため、最低限この桁数は表示する
+const MIN_ID_DISPLAY_LEN: usize = 4;
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct TaskID(Uuid);
 impl TaskID {
     pub fn new() -> Self {
         Self(Uuid::new_v4())
     }
+    /// ハイフンなしの16進表記
+    pub fn hex(&self) -> String {
+        self.0.simple().to_string()
+    }
+    /// 16進表記が `prefix` で始まるか (32 桁を超える部分は無視)
     pub fn starts_with(&self, prefix: &str) -> bool {
-        self.0
-            .as_bytes()
-            .iter()
-            .flat_map(|b| format!("{:02x}", b).chars().collect::<Vec<_>>())
-            .zip(prefix.chars())
-            .all(|(b, c)| b == c)
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        let bytes = self.0.as_bytes();
+        prefix.bytes().take(32).enumerate().all(|(i, c)| {
+            let nibble = if i % 2 == 0 { bytes[i / 2] >> 4 } else { bytes[i / 2] & 0x0f };
+            HEX[nibble as usize] == c
+        })
+    }
+    /// `prefix` で始まる ID のうち最小のもの (残りの桁を 0 で埋めた ID)。16進でなければ None
+    pub fn prefix_lower_bound(prefix: &str) -> Option<Self> {
+        let mut bytes = [0u8; 16];
+        for (i, c) in prefix.bytes().take(32).enumerate() {
+            let nibble = match c {
+                b'0'..=b'9' => c - b'0',
+                b'a'..=b'f' => c - b'a' + 10,
+                _ => return None,
+            };
+            bytes[i / 2] |= if i % 2 == 0 { nibble << 4 } else { nibble };
+        }
+        Some(Self::from(bytes))
+    }
+    /// 16進表記で先頭から何桁が一致するか
+    fn common_prefix_len(&self, other: &Self) -> usize {
+        self.hex().bytes().zip(other.hex().bytes()).take_while(|(a, b)| a == b).count()
+    }
+    /// 昇順に並んだ `ids` をすべて区別できる最短の桁数 (`MIN_ID_DISPLAY_LEN` 未満にはしない)
+    pub fn shortest_unique_len<'a>(ids: impl IntoIterator<Item = &'a TaskID>) -> usize {
+        let ids: Vec<_> = ids.into_iter().collect();
+        // 昇順なら、最も長く一致するのは隣同士のどこか
+        let longest_common = ids.windows(2).map(|pair| pair[0].common_prefix_len(pair[1])).max().unwrap_or(0);
+        (longest_common + 1).clamp(MIN_ID_DISPLAY_LEN, 32)
+    }
+    /// 先頭 `len` 桁で表示する ID (`#1d48` の形式)。桁数は `shortest_unique_len` で決める
+    pub fn short(&self, len: usize) -> ShortTaskID {
+        ShortTaskID { id: *self, len: len.clamp(1, 32) }
     }
 }
 impl From<[u8; 16]> for TaskID {
@@ -23,16 +65,21 @@ impl From<[u8; 16]> for TaskID {
         Self(Uuid::from_bytes(bytes))
     }
 }
-impl std::fmt::Display for TaskID {
+impl std::fmt::Debug for TaskID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let short_uuid = self.0.as_bytes()[..3].iter().map(|b| format!("{:02x}", b)).collect::<String>();
-        write!(f, "#{}", short_uuid)
+        write!(f, "#{}", self.hex())
     }
 }
-impl std::fmt::Debug for TaskID {
+
+/// 表示用に先頭の桁だけにした ID
+#[derive(Clone, Copy)]
+pub struct ShortTaskID {
+    id: TaskID,
+    len: usize,
+}
+impl std::fmt::Display for ShortTaskID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // use Display trait for better debugging
-        write!(f, "{}", self)
+        write!(f, "#{}", &self.id.hex()[..self.len])
     }
 }
 
@@ -51,6 +98,10 @@ impl Progress {
     pub fn full() -> Self {
         Self(100)
     }
+    /// 現在の進捗に `diff` ポイントを加算し、0〜100 に丸めた進捗を返す
+    pub fn offset(self, diff: i32) -> Self {
+        Self((self.0 as i32 + diff).clamp(0, 100) as u8)
+    }
 }
 impl TryFrom<u8> for Progress {
     type Error = String;
@@ -75,11 +126,52 @@ pub struct Task {
     pub title: String,
     pub created_at: NaiveDateTime,
     pub deadline: Deadline,
+    /// 日付のみ・相対指定の期限を解決するときに使う時刻 (未指定ならスケジューラ既定)
+    #[serde(default)]
+    pub default_deadline_time: Option<NaiveTime>,
     status: TaskStatus,
-    pub note: Option<String>,
+    /// 旧形式の単一のメモ (読み込み時に `notes` へ移す)
+    #[serde(default, rename = "note", skip_serializing)]
+    legacy_note: Option<String>,
+    /// 時刻付きのメモ (タスクごとの作業ジャーナル)
+    #[serde(default)]
+    pub notes: Vec<(NaiveDateTime, String)>,
     estimate: Option<Estimate>,
     pub progress: Option<Progress>,
     pub actual_total: Duration,
+    /// ストーリーポイントで見積もった場合の元のポイント
+    #[serde(default)]
+    pub story_points: Option<u32>,
+    /// 見積を入力したときの元の表記 (例: "3d", "1h 30m 4h")
+    #[serde(default)]
+    pub estimate_input: Option<String>,
+    /// 見積の改訂履歴 (改訂日時, 改訂後の見積)
+    #[serde(default)]
+    estimate_history: Vec<(NaiveDateTime, Estimate)>,
+    /// 親タスク (サブタスクの場合)
+    #[serde(default)]
+    pub parent: Option<TaskID>,
+    /// スケジュール時の 1 回の割り当て単位 (未指定ならスケジューラ既定)
+    #[serde(default)]
+    pub work_tick: Option<Duration>,
+    /// 時刻の決まったタスク (打ち合わせなど)。スケジュールではこの時刻に固定の予定として扱う
+    #[serde(default)]
+    pub fixed_at: Option<NaiveDateTime>,
+    /// この日時より前にはスケジュールしない (まだ着手できないタスク)
+    #[serde(default)]
+    pub not_before: Option<NaiveDateTime>,
+    /// できればこれらのタスクの後に着手したい (ブロックはしない緩い依存)
+    #[serde(default)]
+    pub prefer_after: Vec<TaskID>,
+    /// タスクをまとめて扱うためのタグ (`#` なしで保持)
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// 主な分類 (Work / Personal / Learning など)。タグと違い 1 つだけ付け、一覧をまとめて表示するのに使う
+    #[serde(default)]
+    pub category: Option<String>,
+    /// 作業時間を記録せずに完了した (実績 0 は「記録なし」で、見積精度の集計には使わない)
+    #[serde(default)]
+    pub completed_without_time: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,6 +179,14 @@ pub struct ExternalBlockingReason {
     pub note: Option<String>,
     pub may_unblock_at: Deadline,
     pub last_updated: NaiveDateTime,
+    /// 先方への確認 (フォロー) の間隔。`last_updated` からこの間隔が過ぎたら要フォロー
+    #[serde(default)]
+    pub remind_every: Option<Duration>,
+}
+impl ExternalBlockingReason {
+    pub fn needs_follow_up(&self, now: NaiveDateTime) -> bool {
+        self.remind_every.is_some_and(|every| self.last_updated + every <= now)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +216,15 @@ impl BlockingStatus {
         self.tasks.retain(|t| t != &task_id);
         self.is_ready()
     }
+    /// 依存タスク `old` を `new` に差し替える。`old` に依存していなければ false
+    pub fn replace_task(&mut self, old: TaskID, new: TaskID) -> bool {
+        if !self.tasks.contains(&old) {
+            return false;
+        }
+        self.unblock_task(old);
+        self.block_by_task(vec![new]);
+        true
+    }
     pub fn unblock_external(&mut self, reason_index: usize) -> bool {
         if reason_index < self.externals.len() {
             self.externals.remove(reason_index);
@@ -127,6 +236,21 @@ impl BlockingStatus {
     }
 }
 
+/// `Task::remaining` がどの入力から算出されたか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemainingBasis {
+    /// 見積 + 手動進捗 (実績なし)
+    EstimateAndProgress,
+    /// 手動進捗 + 実績のペース
+    ActualPace,
+    /// 見積 - 実績 (進捗は自動算出)
+    EstimateMinusActual,
+    /// 完了/削除済み
+    Closed,
+    /// 見積も進捗もない場合の既定値
+    Default,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskStatus {
     Ready,
@@ -137,51 +261,151 @@ pub enum TaskStatus {
 
 impl Task {
     pub fn new(title: String, deadline: Option<Deadline>, note: Option<String>) -> Self {
+        let created_at = chrono::Local::now().naive_local();
         Self {
             id: TaskID::new(),
             title,
-            created_at: chrono::Local::now().naive_local(),
+            created_at,
             deadline: deadline.unwrap_or(Deadline::Unknown),
+            default_deadline_time: None,
             status: TaskStatus::Ready,
-            note,
+            legacy_note: None,
+            notes: note.map(|note| vec![(created_at, note)]).unwrap_or_default(),
             estimate: None,
             progress: None,
             actual_total: Duration::zero(),
+            story_points: None,
+            estimate_input: None,
+            estimate_history: Vec::new(),
+            parent: None,
+            work_tick: None,
+            fixed_at: None,
+            not_before: None,
+            prefer_after: Vec::new(),
+            tags: BTreeSet::new(),
+            category: None,
+            completed_without_time: false,
         }
     }
-    pub fn remaining(&self) -> Duration {
+    /// 見積・期限・メモを引き継いだ新しい Ready タスクを作る (実績・進捗はリセット)
+    /// 相対期限は `now` を基準に付け直す
+    pub fn duplicate(&self, title: String, now: NaiveDateTime) -> Self {
+        let mut task = Self::new(title, None, None);
+        task.created_at = now;
+        task.notes = self.notes.clone();
+        task.deadline = match &self.deadline {
+            Deadline::Fuzzy(fuzzy) => Deadline::Fuzzy(FuzzyDeadline::new(now, fuzzy.kind.clone(), fuzzy.time)),
+            deadline => deadline.clone(),
+        };
+        task.default_deadline_time = self.default_deadline_time;
+        task.parent = self.parent;
+        task.work_tick = self.work_tick;
+        task.tags = self.tags.clone();
+        task.category = self.category.clone();
+        if let Some(estimate) = &self.estimate {
+            // 元タスクの見積は実績込みなので、複製側では見積全体を新規の残り時間とする
+            task.update_remaining(estimate.clone(), now).expect("new task is ready");
+            task.story_points = self.story_points;
+            task.estimate_input = self.estimate_input.clone();
+        }
+        task
+    }
+    /// 残り時間をどの入力から算出するかを判定する。
+    ///
+    /// 入力の優先順位は以下の通り:
+    /// 1. 見積 + 進捗 (実績なし): 見積の未消化分
+    /// 2. 進捗 + 実績: 実績のペースから外挿 (見積は無視)
+    /// 3. 見積のみ (進捗なし): 見積 - 実績
+    /// 4. いずれもなし: 完了/削除済みなら 0、それ以外は 5 分
+    ///
+    /// 進捗 (`progress`) は手動上書きで、`None` の場合は `progress()` が見積と実績から自動算出する。
+    pub fn remaining_basis(&self) -> RemainingBasis {
         match (&self.estimate, self.progress, self.actual_total) {
-            (Some(estimate), Some(progress), actual_total) if actual_total.is_zero() => {
+            (Some(_), Some(_), actual_total) if actual_total.is_zero() => RemainingBasis::EstimateAndProgress,
+            (_, Some(progress), _) if u8::from(progress) > 0 => RemainingBasis::ActualPace,
+            // 進捗 0% ではペースが分からないので、進捗がない場合と同じに扱う
+            (Some(_), _, _) => RemainingBasis::EstimateMinusActual,
+            _ if self.is_completed() || self.is_dropped() => RemainingBasis::Closed,
+            _ => RemainingBasis::Default,
+        }
+    }
+    pub fn remaining(&self) -> Duration {
+        match self.remaining_basis() {
+            RemainingBasis::EstimateAndProgress => {
                 // 見積と進捗があるが実績時間がない場合、残りの進捗率と見積から計算
-                let progress: u8 = progress.into();
-                let estimate = estimate.mean();
+                let progress: u8 = self.progress.unwrap().into();
+                let estimate = self.estimate.as_ref().unwrap().mean();
                 estimate - (estimate / 100 * progress.into())
             }
-            (_, Some(progress), actual_total) => {
+            RemainingBasis::ActualPace => {
                 // 進捗と実績時間がある場合、見積の有無にかかわらず実績時間と今までの進捗から今後のペースを計算
-                let progress: u8 = progress.into();
-                (actual_total / progress.into()) * (100 - progress).into()
+                let progress: u8 = self.progress.unwrap().into();
+                (self.actual_total / progress.into()) * (100 - progress).into()
             }
-            (Some(estimate), None, actual_total) => {
+            RemainingBasis::EstimateMinusActual => {
                 // 見積があるが進捗がない場合、見積から実績時間を引いたものを残り時間とする
-                estimate.mean() - actual_total
-            }
-            _ => {
-                if self.is_completed() || self.is_dropped() {
-                    // 完了またはドロップされたタスクは残り時間をゼロとする
-                    Duration::zero()
-                } else {
-                    // 見積も進捗も実績時間もない場合、5分を残り時間とする
-                    Duration::minutes(5)
-                }
+                self.estimate.as_ref().unwrap().mean() - self.actual_total
             }
+            // 完了またはドロップされたタスクは残り時間をゼロとする
+            RemainingBasis::Closed => Duration::zero(),
+            // 見積も進捗も実績時間もない場合、5分を残り時間とする
+            RemainingBasis::Default => Duration::minutes(5),
+        }
+    }
+    /// 子タスク (削除済みを除く) を列挙する
+    pub fn children<'a>(&self, tasks: &'a BTreeMap<TaskID, Task>) -> impl Iterator<Item = &'a Task> {
+        let id = self.id;
+        tasks.values().filter(move |t| t.parent == Some(id) && !t.is_dropped())
+    }
+    pub fn has_children(&self, tasks: &BTreeMap<TaskID, Task>) -> bool {
+        self.children(tasks).next().is_some()
+    }
+    /// 今すぐ着手できるか。依存先がすべて完了 (または削除) していれば、ブロック中のままでも着手できる
+    pub fn is_startable(&self, tasks: &BTreeMap<TaskID, Task>) -> bool {
+        match &self.status {
+            TaskStatus::Ready => true,
+            TaskStatus::Blocked(bs) => bs.externals.is_empty() && bs.tasks.iter().all(|id| tasks.get(id).is_none_or(|t| t.is_completed() || t.is_dropped())),
+            TaskStatus::Completed(_) | TaskStatus::Dropped => false,
+        }
+    }
+    /// 子タスクがあれば子タスクの見積の合計 (再帰的)、なければ自身の見積を返す。
+    /// 見積のない子タスクは合計に含めない。
+    pub fn rolled_up_estimate(&self, tasks: &BTreeMap<TaskID, Task>) -> Option<Estimate> {
+        if !self.has_children(tasks) {
+            return self.estimate.clone();
         }
+        self.children(tasks).filter_map(|child| child.rolled_up_estimate(tasks)).reduce(|sum, e| sum + e)
     }
-    pub fn update_remaining(&mut self, estimated_remaining: Estimate) -> Result<(), String> {
+    /// 子タスクがあれば子タスクの残り時間の合計 (再帰的)、なければ自身の残り時間を返す
+    pub fn rolled_up_remaining(&self, tasks: &BTreeMap<TaskID, Task>) -> Duration {
+        if !self.has_children(tasks) {
+            return self.remaining();
+        }
+        self.children(tasks).map(|child| child.rolled_up_remaining(tasks)).sum()
+    }
+    /// 残り時間がどの分岐・入力から算出されたかを説明する (デバッグ用)。期間は `units` の単位で表示する
+    pub fn remaining_explanation(&self, units: DurationUnits) -> String {
+        let remaining = format_human_duration_in(self.remaining(), units);
+        let progress: u8 = self.progress.unwrap_or_default().into();
+        let mean = self.estimate.as_ref().map(|e| format_human_duration_in(e.mean(), units)).unwrap_or_default();
+        let actual = format_human_duration_in(self.actual_total, units);
+        match self.remaining_basis() {
+            RemainingBasis::EstimateAndProgress => format!("見積×未進捗: {} × (100% - {}%) = {}", mean, progress, remaining),
+            RemainingBasis::ActualPace => format!("実績ペース: {} ÷ {}% × {}% = {} (見積は不使用)", actual, progress, 100 - progress, remaining),
+            RemainingBasis::EstimateMinusActual => format!("見積-実績: {} - {} = {}", mean, actual, remaining),
+            RemainingBasis::Closed => format!("完了/削除済み: {}", remaining),
+            RemainingBasis::Default => format!("見積なしの既定値: {}", remaining),
+        }
+    }
+    pub fn update_remaining(&mut self, estimated_remaining: Estimate, updated_at: NaiveDateTime) -> Result<(), String> {
         if !self.is_ready() && !self.is_blocked() {
             return Err("Cannot update estimate for a non-ready task".to_string());
         }
-        self.estimate = Some(estimated_remaining + Estimate::new(self.actual_total));
+        let estimate = estimated_remaining + Estimate::new(self.actual_total);
+        self.estimate_history.push((updated_at, estimate.clone()));
+        self.estimate = Some(estimate);
+        self.story_points = None;
+        self.estimate_input = None;
         self.progress = None; // 見積もりを更新したら進捗オーバーライドはリセット
         Ok(())
     }
@@ -189,11 +413,28 @@ impl Task {
         match self.progress {
             Some(progress) => progress,
             None => match &self.estimate {
-                Some(estimate) => Progress::new((self.actual_total.num_minutes() * 100 / estimate.mean().num_minutes()) as u8).unwrap(),
-                None => Progress::zero(),
+                // 見積超過でも 100% で頭打ちにする (以前に保存された 0 以下の見積では割り算しない)
+                Some(estimate) if estimate.mean().num_minutes() > 0 => Progress::new((self.actual_total.num_minutes() * 100 / estimate.mean().num_minutes()).clamp(0, 100) as u8).unwrap(),
+                Some(_) | None => Progress::zero(),
             },
         }
     }
+    /// タスク固有の期限時刻があればそれを、なければ `default_deadline_time` を返す
+    pub fn deadline_time(&self, default_deadline_time: NaiveTime) -> NaiveTime {
+        self.default_deadline_time.unwrap_or(default_deadline_time)
+    }
+    pub fn resolve_deadline(&self, calendar: &Calendar, default_deadline_time: NaiveTime) -> Result<Option<NaiveDateTime>, String> {
+        self.deadline.resolve_with_calendar(calendar, self.deadline_time(default_deadline_time))
+    }
+    /// 旧形式の単一のメモを、作成日時付きのメモに移す
+    pub fn migrate_legacy_note(&mut self) {
+        if let Some(note) = self.legacy_note.take() {
+            self.notes.insert(0, (self.created_at, note));
+        }
+    }
+    pub fn add_note(&mut self, at: NaiveDateTime, text: String) {
+        self.notes.push((at, text));
+    }
     pub fn status(&self) -> &TaskStatus {
         &self.status
     }
@@ -212,16 +453,44 @@ impl Task {
     pub fn estimate(&self) -> Option<&Estimate> {
         self.estimate.as_ref()
     }
+    pub fn estimate_history(&self) -> &[(NaiveDateTime, Estimate)] {
+        &self.estimate_history
+    }
+    /// 最後に見積もった日時 (履歴がなければ作成日時)
+    pub fn estimate_updated_at(&self) -> NaiveDateTime {
+        self.estimate_history.last().map(|(at, _)| *at).unwrap_or(self.created_at)
+    }
     pub fn drop(&mut self) {
         self.status = TaskStatus::Dropped;
     }
     pub fn record(&mut self, duration: Duration) {
         self.actual_total += duration;
+        // 記録なしで完了した後から実績を足した場合は、その実績を集計に使う
+        if duration > Duration::zero() {
+            self.completed_without_time = false;
+        }
     }
     pub fn complete(&mut self, completed_at: NaiveDateTime) {
         self.progress = Some(Progress::full());
         self.status = TaskStatus::Completed(completed_at);
     }
+    /// 作業時間を記録せずに完了する。それまでに実績があれば、その実績は有効なまま
+    pub fn complete_without_time(&mut self, completed_at: NaiveDateTime) {
+        self.complete(completed_at);
+        self.completed_without_time = self.actual_total.is_zero();
+    }
+    /// 見積精度の集計に使う実績。未完了や、時間を記録せずに完了したタスクは None
+    pub fn actual_for_accuracy(&self) -> Option<Duration> {
+        (self.is_completed() && !self.completed_without_time).then_some(self.actual_total)
+    }
+    /// 完了を取り消して着手可能に戻す。完了時に 100% にした進捗は実績から算出し直す
+    pub fn reopen(&mut self) {
+        if self.progress.is_some_and(|progress| u8::from(progress) == 100) {
+            self.progress = None;
+        }
+        self.status = TaskStatus::Ready;
+        self.completed_without_time = false;
+    }
     pub fn block_by_task(&mut self, task_ids: Vec<TaskID>) {
         if let TaskStatus::Blocked(status) = &mut self.status {
             status.block_by_task(task_ids);
@@ -244,6 +513,23 @@ impl Task {
             }
         }
     }
+    /// 外部待ちをフォローした日時を記録する。外部待ちがなければ false
+    pub fn mark_followed_up(&mut self, at: NaiveDateTime) -> bool {
+        let TaskStatus::Blocked(status) = &mut self.status else {
+            return false;
+        };
+        for reason in status.externals.iter_mut() {
+            reason.last_updated = at;
+        }
+        !status.externals.is_empty()
+    }
+    /// 依存タスク `old` を `new` に差し替える。`old` にブロックされていなければ false
+    pub fn replace_dependency(&mut self, old: TaskID, new: TaskID) -> bool {
+        match &mut self.status {
+            TaskStatus::Blocked(status) => status.replace_task(old, new),
+            _ => false,
+        }
+    }
     pub fn unblock_external(&mut self, reason_index: usize) {
         if let TaskStatus::Blocked(status) = &mut self.status {
             status.unblock_external(reason_index);
@@ -267,7 +553,7 @@ impl Task {
 fn test_simulate_progress() {
     let mut task = Task::new("Test Task".to_string(), None, None);
     let estimate = Estimate::new(Duration::minutes(200));
-    task.update_remaining(estimate);
+    task.update_remaining(estimate, task.created_at);
     task.progress = Some(Progress::new(20).unwrap());
     let duration = Duration::minutes(50);
     let progress = task.simulate_progress(&duration).unwrap();
@@ -280,26 +566,284 @@ fn test_remaining() {
     {
         // 見積も進捗も実績時間もない場合
         let task = task_base.clone();
+        assert_eq!(task.remaining(), Duration::minutes(5));
+    }
+    {
+        // 見積も進捗も実績時間もないが完了済みの場合
+        let mut task = task_base.clone();
+        task.status = TaskStatus::Completed(task.created_at);
         assert_eq!(task.remaining(), Duration::zero());
     }
     {
         // 見積と進捗はあるが実績時間がない場合
         let mut task = task_base.clone();
-        task.update_remaining(Estimate::new(Duration::minutes(200)));
+        task.update_remaining(Estimate::new(Duration::minutes(200)), task.created_at);
         task.progress = Some(Progress::new(20).unwrap());
         assert_eq!(task.remaining(), Duration::minutes(160));
     }
     {
         // 見積はあるが進捗も実績時間もない場合
         let mut task = task_base.clone();
-        task.update_remaining(Estimate::new(Duration::minutes(200)));
+        task.update_remaining(Estimate::new(Duration::minutes(200)), task.created_at);
         assert_eq!(task.remaining(), Duration::minutes(200));
     }
+    {
+        // 見積と実績時間があり進捗がない場合 (進捗は自動算出)
+        let mut task = task_base.clone();
+        task.update_remaining(Estimate::new(Duration::minutes(200)), task.created_at);
+        task.record(Duration::minutes(50));
+        assert_eq!(task.remaining(), Duration::minutes(150));
+        assert_eq!(u8::from(task.progress()), 25);
+    }
     {
         // 進捗と実績時間がある場合 (見積の有無は関係ない)
         let mut task = task_base.clone();
         task.progress = Some(Progress::new(20).unwrap());
         task.actual_total = Duration::minutes(40);
         assert_eq!(task.remaining(), Duration::minutes(160));
+        task.update_remaining(Estimate::new(Duration::minutes(500)), task.created_at);
+        task.progress = Some(Progress::new(20).unwrap());
+        assert_eq!(task.remaining(), Duration::minutes(160));
+    }
+    {
+        // 手動進捗を解除すると見積と実績からの自動算出に戻る
+        let mut task = task_base.clone();
+        task.update_remaining(Estimate::new(Duration::minutes(200)), task.created_at);
+        task.record(Duration::minutes(100));
+        task.progress = Some(Progress::new(80).unwrap());
+        assert_eq!(task.remaining(), Duration::minutes(25));
+        task.progress = None;
+        assert_eq!(task.remaining(), Duration::minutes(100));
+        assert_eq!(u8::from(task.progress()), 50);
+    }
+}
+
+#[test]
+fn test_progress_offset() {
+    let progress = Progress::new(30).unwrap();
+    assert_eq!(u8::from(progress.offset(20)), 50);
+    assert_eq!(u8::from(progress.offset(-20)), 10);
+    assert_eq!(u8::from(progress.offset(-50)), 0);
+    assert_eq!(u8::from(progress.offset(90)), 100);
+    assert_eq!(u8::from(progress.offset(300)), 100);
+}
+
+#[test]
+fn test_estimate_history() {
+    let mut task = Task::new("Test Task".to_string(), None, None);
+    assert!(task.estimate_history().is_empty());
+    let t1 = task.created_at;
+    let t2 = t1 + Duration::days(1);
+    task.update_remaining(Estimate::new(Duration::minutes(60)), t1).unwrap();
+    task.record(Duration::minutes(30));
+    task.update_remaining(Estimate::new(Duration::minutes(60)), t2).unwrap();
+
+    let history = task.estimate_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].0, t1);
+    assert_eq!(history[0].1.mean(), Duration::minutes(60));
+    assert_eq!(history[1].0, t2);
+    assert_eq!(history[1].1.mean(), Duration::minutes(90));
+}
+
+#[test]
+fn test_duplicate() {
+    use super::deadline::FuzzyDeadlineKind;
+    let now = NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let mut task = Task::new("Review PR #1".to_string(), None, Some("see wiki".to_string()));
+    task.deadline = Deadline::Fuzzy(FuzzyDeadline::new(now - Duration::days(7), FuzzyDeadlineKind::BusinessDays(2), None));
+    task.update_remaining(Estimate::new(Duration::minutes(60)), now).unwrap();
+    task.story_points = Some(2);
+    task.record(Duration::minutes(30));
+    task.progress = Some(Progress::new(50).unwrap());
+    task.complete(now);
+
+    let copy = task.duplicate("Review PR #2".to_string(), now);
+    assert_ne!(copy.id, task.id);
+    assert_eq!(copy.title, "Review PR #2");
+    assert!(copy.is_ready());
+    assert_eq!(copy.notes.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>(), vec!["see wiki"]);
+    assert_eq!(copy.actual_total, Duration::zero());
+    assert!(copy.progress.is_none());
+    assert_eq!(copy.estimate().unwrap().mean(), Duration::minutes(60));
+    assert_eq!(copy.story_points, Some(2));
+    let Deadline::Fuzzy(fuzzy) = &copy.deadline else {
+        panic!("deadline kind must be kept");
+    };
+    assert_eq!(fuzzy.reference_date, now);
+    assert!(matches!(fuzzy.kind, FuzzyDeadlineKind::BusinessDays(2)));
+}
+
+#[test]
+fn test_remaining_with_zero_progress() {
+    // progress <id> 0 の後でも、実績があるタスクの残り時間を計算できる
+    let mut task = Task::new("Test Task".to_string(), None, None);
+    task.update_remaining(Estimate::new(Duration::hours(2)), task.created_at).unwrap();
+    task.record(Duration::minutes(30));
+    task.progress = Some(Progress::zero());
+    assert_eq!(task.remaining_basis(), RemainingBasis::EstimateMinusActual);
+    assert_eq!(task.remaining(), Duration::minutes(90));
+
+    // 見積もなければ既定値
+    let mut task = Task::new("Test Task".to_string(), None, None);
+    task.record(Duration::minutes(30));
+    task.progress = Some(Progress::zero());
+    assert_eq!(task.remaining_basis(), RemainingBasis::Default);
+    assert_eq!(task.remaining(), Duration::minutes(5));
+}
+
+#[test]
+fn test_remaining_basis() {
+    let task_base = Task::new("Test Task".to_string(), None, None);
+    let estimated = |minutes| {
+        let mut task = task_base.clone();
+        task.update_remaining(Estimate::new(Duration::minutes(minutes)), task.created_at).unwrap();
+        task
+    };
+    {
+        let task = task_base.clone();
+        assert_eq!(task.remaining_basis(), RemainingBasis::Default);
+        assert_eq!(task.remaining_explanation(DurationUnits::default()), "見積なしの既定値: 5min");
+    }
+    {
+        let mut task = task_base.clone();
+        task.drop();
+        assert_eq!(task.remaining_basis(), RemainingBasis::Closed);
+        assert_eq!(task.remaining_explanation(DurationUnits::default()), "完了/削除済み: 0min");
+    }
+    {
+        let mut task = estimated(200);
+        task.progress = Some(Progress::new(20).unwrap());
+        assert_eq!(task.remaining_basis(), RemainingBasis::EstimateAndProgress);
+        assert_eq!(task.remaining_explanation(DurationUnits::default()), "見積×未進捗: 3h 20min × (100% - 20%) = 2h 40min");
+    }
+    {
+        // 実績をわずかでも記録すると、見積ではなく実績ペースで算出される
+        let mut task = estimated(200);
+        task.progress = Some(Progress::new(20).unwrap());
+        task.record(Duration::minutes(1));
+        assert_eq!(task.remaining_basis(), RemainingBasis::ActualPace);
+        assert_eq!(task.remaining(), Duration::minutes(4));
+        assert_eq!(task.remaining_explanation(DurationUnits::default()), "実績ペース: 1min ÷ 20% × 80% = 4min (見積は不使用)");
+    }
+    {
+        let mut task = estimated(200);
+        task.record(Duration::minutes(50));
+        assert_eq!(task.remaining_basis(), RemainingBasis::EstimateMinusActual);
+        assert_eq!(task.remaining_explanation(DurationUnits::default()), "見積-実績: 3h 20min - 50min = 2h 30min");
+    }
+    {
+        // 進捗と実績があるが見積がない場合も実績ペース
+        let mut task = task_base.clone();
+        task.progress = Some(Progress::new(50).unwrap());
+        task.record(Duration::minutes(30));
+        assert_eq!(task.remaining_basis(), RemainingBasis::ActualPace);
+        assert_eq!(task.remaining(), Duration::minutes(30));
+    }
+    {
+        // 実績のみ (見積も進捗もない) は既定値
+        let mut task = task_base.clone();
+        task.record(Duration::minutes(30));
+        assert_eq!(task.remaining_basis(), RemainingBasis::Default);
+        assert_eq!(task.remaining(), Duration::minutes(5));
     }
 }
+
+#[test]
+fn test_rolled_up_estimate() {
+    let now = NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    let estimated = |title: &str, minutes| {
+        let mut task = Task::new(title.to_string(), None, None);
+        task.update_remaining(Estimate::new(Duration::minutes(minutes)), now).unwrap();
+        task
+    };
+    let mut parent = estimated("parent", 999);
+    let mut child_a = estimated("a", 60);
+    let mut child_b = Task::new("b".to_string(), None, None);
+    let mut grandchild = estimated("b-1", 30);
+    let mut dropped = estimated("dropped", 120);
+    child_a.parent = Some(parent.id);
+    child_b.parent = Some(parent.id);
+    grandchild.parent = Some(child_b.id);
+    dropped.parent = Some(parent.id);
+    dropped.drop();
+    child_a.record(Duration::minutes(20));
+    let tasks: BTreeMap<_, _> = [&parent, &child_a, &child_b, &grandchild, &dropped].into_iter().map(|t| (t.id, t.clone())).collect();
+
+    // 子があれば自身の見積 (999min) ではなく子の合計を使う。削除済みの子は含めない
+    assert_eq!(parent.rolled_up_estimate(&tasks).unwrap().mean(), Duration::minutes(90));
+    assert_eq!(parent.rolled_up_remaining(&tasks), Duration::minutes(70));
+    assert_eq!(child_b.rolled_up_estimate(&tasks).unwrap().mean(), Duration::minutes(30));
+    // 子のない場合は自身の値
+    assert_eq!(child_a.rolled_up_estimate(&tasks).unwrap().mean(), Duration::minutes(60));
+    assert_eq!(child_a.rolled_up_remaining(&tasks), Duration::minutes(40));
+    // 子がすべて見積なしなら None
+    grandchild.estimate = None;
+    let tasks: BTreeMap<_, _> = [&parent, &child_b, &grandchild].into_iter().map(|t| (t.id, t.clone())).collect();
+    assert!(child_b.rolled_up_estimate(&tasks).is_none());
+    parent.estimate = None;
+    assert!(parent.rolled_up_estimate(&tasks).is_none());
+}
+
+#[test]
+fn test_migrate_legacy_note() {
+    let task = Task::new("Write report".to_string(), None, None);
+    let mut json = serde_json::to_value(&task).unwrap();
+    assert!(json.get("note").is_none());
+    // 旧形式: "note" に文字列が 1 つだけ
+    json.as_object_mut().unwrap().remove("notes");
+    json["note"] = serde_json::Value::String("draft in docs/".to_string());
+    let mut loaded: Task = serde_json::from_value(json).unwrap();
+    assert!(loaded.notes.is_empty());
+    loaded.migrate_legacy_note();
+    assert_eq!(loaded.notes, vec![(task.created_at, "draft in docs/".to_string())]);
+    // 2回目以降は何もしない
+    loaded.migrate_legacy_note();
+    assert_eq!(loaded.notes.len(), 1);
+}
+
+#[test]
+fn test_shortest_unique_len() {
+    let id = |prefix: &[u8]| {
+        let mut bytes = [0u8; 16];
+        bytes[..prefix.len()].copy_from_slice(prefix);
+        TaskID::from(bytes)
+    };
+    // 少数なら最低桁数で足りる
+    assert_eq!(TaskID::shortest_unique_len(&[id(&[0x12]), id(&[0xab])]), MIN_ID_DISPLAY_LEN);
+    assert_eq!(TaskID::shortest_unique_len(&[]), MIN_ID_DISPLAY_LEN);
+    // 先頭 6 桁 (3 バイト) が衝突する ID があれば 7 桁に伸ばす
+    let colliding = [id(&[0x12, 0x34, 0x56, 0x00]), id(&[0x12, 0x34, 0x56, 0x10]), id(&[0xab])];
+    assert_eq!(TaskID::shortest_unique_len(&colliding), 7);
+    // 隣り合わない組は比較しなくても、昇順なら隣同士の一致が最長になる
+    let ids = [id(&[0x11, 0x11, 0x11]), id(&[0x11, 0x11, 0x12]), id(&[0x11, 0x11, 0x13])];
+    assert_eq!(TaskID::shortest_unique_len(&ids), 6);
+    let shorts: Vec<_> = colliding.iter().map(|id| id.short(7).to_string()).collect();
+    assert_eq!(shorts[0], "#1234560");
+    assert_eq!(shorts.iter().collect::<BTreeSet<_>>().len(), colliding.len());
+}
+
+#[test]
+fn test_completed_without_time() {
+    let now = NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    // 時間を記録せずに完了したタスクは集計から除く
+    let mut untimed = Task::new("Untimed".to_string(), None, None);
+    untimed.complete_without_time(now);
+    assert!(untimed.completed_without_time);
+    assert_eq!(untimed.actual_for_accuracy(), None);
+    // 後から実績を記録すれば集計に入る
+    untimed.record(Duration::minutes(40));
+    assert_eq!(untimed.actual_for_accuracy(), Some(Duration::minutes(40)));
+
+    // 明示的に 0 分で完了したタスクは実績 0 として扱う
+    let mut zero = Task::new("Zero".to_string(), None, None);
+    zero.record(Duration::zero());
+    zero.complete(now);
+    assert_eq!(zero.actual_for_accuracy(), Some(Duration::zero()));
+
+    // それまでの実績があれば、時間なしで完了してもその実績を使う
+    let mut logged = Task::new("Logged".to_string(), None, None);
+    logged.record(Duration::minutes(30));
+    logged.complete_without_time(now);
+    assert_eq!(logged.actual_for_accuracy(), Some(Duration::minutes(30)));
+}
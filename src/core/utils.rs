@@ -1,8 +1,13 @@
-use chrono::{Duration, NaiveDateTime, NaiveTime};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 
 use super::work::{WORKDAYS_PER_WEEK, WORKHOURS_PER_DAY};
 
 pub fn parse_human_duration(input: &str) -> Option<Duration> {
+    parse_human_duration_in(input, DurationUnits::default())
+}
+
+/// `units` の 1 日・1 週間の長さで `d` / `w` を解釈する (表示の `format_human_duration_in` と同じ単位)
+pub fn parse_human_duration_in(input: &str, units: DurationUnits) -> Option<Duration> {
     let input = input.trim().to_lowercase();
     let (num_str, unit) = input.trim().split_at(input.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(input.len()));
 
@@ -10,15 +15,58 @@ pub fn parse_human_duration(input: &str) -> Option<Duration> {
     let mins = match unit.trim() {
         "m" | "min" | "mins" => value,
         "h" | "hr" | "hrs" => value * 60.0,
-        "d" | "day" | "days" => value * 60.0 * WORKHOURS_PER_DAY as f64,
-        "w" | "week" | "weeks" => value * 60.0 * (WORKHOURS_PER_DAY * WORKDAYS_PER_WEEK) as f64,
+        "d" | "day" | "days" => value * units.day_minutes as f64,
+        "w" | "week" | "weeks" => value * units.week_minutes as f64,
         _ => return None,
     };
 
     Some(Duration::minutes(mins.round() as i64))
 }
 
-pub fn parse_human_duration_with_sign(input: &str) -> Option<(Option<i32>, Duration)> {
+/// 見積など 0 より大きい時間でなければならない入力を解釈する
+/// (0 の見積は進捗・残り時間の計算で割り算が破綻する)
+pub fn parse_positive_duration(input: &str) -> Result<Duration, String> {
+    let duration = parse_human_duration(input).ok_or_else(|| format!("invalid duration: {:?}", input))?;
+    if duration <= Duration::zero() {
+        return Err(format!("Duration must be greater than zero: {}", input.trim()));
+    }
+    Ok(duration)
+}
+
+#[test]
+fn test_parse_positive_duration() {
+    assert_eq!(parse_positive_duration("30m"), Ok(Duration::minutes(30)));
+    assert!(parse_positive_duration("0m").unwrap_err().contains("greater than zero"));
+    assert!(parse_positive_duration("0.2m").unwrap_err().contains("greater than zero"));
+    assert!(parse_positive_duration("0h").is_err());
+    assert!(parse_positive_duration("").unwrap_err().contains("invalid duration"));
+    assert!(parse_positive_duration("   ").unwrap_err().contains("invalid duration"));
+}
+
+/// `±50%` / `+-50%` 形式の見積の幅指定を解釈する
+pub fn parse_spread_percent(input: &str) -> Option<u32> {
+    let input = input.trim();
+    let num_str = input.strip_prefix('±').or_else(|| input.strip_prefix("+-"))?.strip_suffix('%')?;
+    num_str.parse().ok()
+}
+
+#[test]
+fn test_parse_spread_percent() {
+    assert_eq!(parse_spread_percent("±50%"), Some(50));
+    assert_eq!(parse_spread_percent("+-20%"), Some(20));
+    assert_eq!(parse_spread_percent("50%"), None);
+    assert_eq!(parse_spread_percent("±50"), None);
+    assert_eq!(parse_spread_percent("±-5%"), None);
+}
+
+/// `5pt` / `5pts` 形式のストーリーポイント指定を解釈する
+pub fn parse_story_points(input: &str) -> Option<u32> {
+    let input = input.trim().to_lowercase();
+    let num_str = input.strip_suffix("pts").or_else(|| input.strip_suffix("pt"))?;
+    num_str.parse().ok()
+}
+
+pub fn parse_human_duration_with_sign(input: &str, units: DurationUnits) -> Option<(Option<i32>, Duration)> {
     let input = input.trim().to_lowercase();
     let sign = if input.starts_with('-') {
         Some(-1)
@@ -30,7 +78,7 @@ pub fn parse_human_duration_with_sign(input: &str) -> Option<(Option<i32>, Durat
 
     let duration_str = if sign.is_some() { &input[1..] } else { &input };
 
-    let duration = parse_human_duration(duration_str)?;
+    let duration = parse_human_duration_in(duration_str, units)?;
     Some((sign, duration))
 }
 
@@ -48,17 +96,161 @@ fn test_parse_human_duration() {
     assert_eq!(parse_human_duration("invalid"), None);
 }
 
+#[test]
+fn test_parse_story_points() {
+    assert_eq!(parse_story_points("5pt"), Some(5));
+    assert_eq!(parse_story_points("13pts"), Some(13));
+    assert_eq!(parse_story_points("pt"), None);
+    assert_eq!(parse_story_points("5h"), None);
+}
+
+/// 日付トークンを解釈する
+/// 1) today / tomorrow / yesterday
+/// 2) mon〜sun (曜日名): 明日以降で最初のその曜日
+/// 3) YYYY-MM-DD
+/// 4) YYYY/MM/DD
+/// 5) MM/DD (年省略 → now.year())
+pub fn parse_date(now: NaiveDateTime, tok: &str) -> Result<NaiveDate, String> {
+    let today = now.date();
+    match tok.to_lowercase().as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today + Duration::days(1)),
+        "yesterday" => return Ok(today - Duration::days(1)),
+        keyword => {
+            if let Some(weekday) = parse_weekday(keyword) {
+                let days_ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+                let days_ahead = if days_ahead == 0 { 7 } else { days_ahead };
+                return Ok(today + Duration::days(days_ahead as i64));
+            }
+        }
+    }
+    if tok.contains('-') {
+        return NaiveDate::parse_from_str(tok, "%Y-%m-%d").map_err(|_| "日付形式は YYYY-MM-DD で指定してください".to_string());
+    }
+    if !tok.contains('/') {
+        return Err(format!("日付形式が不正です: {}", tok));
+    }
+    let parts: Vec<_> = tok.split('/').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        return Err(format!("日付形式が不正です (空の要素があります): {}", tok));
+    }
+    let (year, month, day) = match parts.as_slice() {
+        [y, m, d] => (y.parse::<i32>().map_err(|_| format!("年が不正です: {}", y))?, *m, *d),
+        [m, d] => (now.year(), *m, *d),
+        _ => return Err("日付形式は YYYY-MM-DD, YYYY/MM/DD, MM/DD のいずれかです".to_string()),
+    };
+    let month: u32 = month.parse().map_err(|_| format!("月が不正です: {}", month))?;
+    if !(1..=12).contains(&month) {
+        return Err(format!("月は 1〜12 で指定してください: {}", month));
+    }
+    let day: u32 = day.parse().map_err(|_| format!("日が不正です: {}", day))?;
+    if !(1..=31).contains(&day) {
+        return Err(format!("日は 1〜31 で指定してください: {}", day));
+    }
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| format!("{}年{}月{}日は存在しない日付です", year, month, day))
+}
+
+fn parse_weekday(input: &str) -> Option<Weekday> {
+    let weekday = match input {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+#[test]
+fn test_parse_date() {
+    let now = NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    assert_eq!(parse_date(now, "2025-05-10").unwrap(), NaiveDate::from_ymd_opt(2025, 5, 10).unwrap());
+    assert_eq!(parse_date(now, "2026/01/02").unwrap(), NaiveDate::from_ymd_opt(2026, 1, 2).unwrap());
+    assert_eq!(parse_date(now, "5/20").unwrap(), NaiveDate::from_ymd_opt(2025, 5, 20).unwrap());
+
+    let err = parse_date(now, "13/40").unwrap_err();
+    assert!(err.contains("月は 1〜12"), "{}", err);
+    let err = parse_date(now, "12/40").unwrap_err();
+    assert!(err.contains("日は 1〜31"), "{}", err);
+    let err = parse_date(now, "2/30").unwrap_err();
+    assert!(err.contains("存在しない日付"), "{}", err);
+    let err = parse_date(now, "2025/13/40").unwrap_err();
+    assert!(err.contains("月は 1〜12"), "{}", err);
+    for tok in ["/5", "5/", "/2025/05/01", "2025/05/01/", "5//1"] {
+        let err = parse_date(now, tok).unwrap_err();
+        assert!(err.contains("日付形式"), "{}: {}", tok, err);
+    }
+}
+
+#[test]
+fn test_parse_date_keywords() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    // 2025-05-07 は水曜日
+    let now = date(2025, 5, 7).and_hms_opt(10, 0, 0).unwrap();
+    assert_eq!(parse_date(now, "today").unwrap(), date(2025, 5, 7));
+    assert_eq!(parse_date(now, "Tomorrow").unwrap(), date(2025, 5, 8));
+    assert_eq!(parse_date(now, "yesterday").unwrap(), date(2025, 5, 6));
+    assert_eq!(parse_date(now, "fri").unwrap(), date(2025, 5, 9));
+    assert_eq!(parse_date(now, "mon").unwrap(), date(2025, 5, 12));
+    // 当日の曜日は翌週を指す
+    assert_eq!(parse_date(now, "wednesday").unwrap(), date(2025, 5, 14));
+
+    // 月末・年末をまたぐ
+    let now = date(2025, 5, 30).and_hms_opt(10, 0, 0).unwrap(); // 金曜日
+    assert_eq!(parse_date(now, "mon").unwrap(), date(2025, 6, 2));
+    assert_eq!(parse_date(now, "thu").unwrap(), date(2025, 6, 5));
+    assert_eq!(parse_date(now, "sat").unwrap(), date(2025, 5, 31));
+    let now = date(2025, 12, 31).and_hms_opt(10, 0, 0).unwrap(); // 水曜日
+    assert_eq!(parse_date(now, "tomorrow").unwrap(), date(2026, 1, 1));
+    assert_eq!(parse_date(now, "tue").unwrap(), date(2026, 1, 6));
+
+    assert!(parse_date(now, "someday").is_err());
+}
+
+/// 期間を表示するときの 1 日・1 週間の長さ (分)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurationUnits {
+    pub day_minutes: i64,
+    pub week_minutes: i64,
+}
+impl Default for DurationUnits {
+    fn default() -> Self {
+        Self {
+            day_minutes: 60 * WORKHOURS_PER_DAY,
+            week_minutes: 60 * WORKHOURS_PER_DAY * WORKDAYS_PER_WEEK,
+        }
+    }
+}
+impl DurationUnits {
+    /// 1 日の勤務時間から表示単位を決める (1 週間は `WORKDAYS_PER_WEEK` 日)
+    pub fn from_working_time(working_time: (NaiveTime, NaiveTime)) -> Self {
+        let day_minutes = (working_time.1 - working_time.0).num_minutes().max(1);
+        Self {
+            day_minutes,
+            week_minutes: day_minutes * WORKDAYS_PER_WEEK,
+        }
+    }
+}
+
 pub fn format_human_duration(duration: Duration) -> String {
+    format_human_duration_in(duration, DurationUnits::default())
+}
+
+/// `units` の 1 日・1 週間の長さで期間を表示する
+pub fn format_human_duration_in(duration: Duration, units: DurationUnits) -> String {
     let mut total_minutes = duration.num_minutes();
 
     if total_minutes <= 0 {
         return "0min".to_string();
     }
 
-    let weeks = total_minutes / (60 * WORKHOURS_PER_DAY * WORKDAYS_PER_WEEK);
-    total_minutes -= weeks * (60 * WORKHOURS_PER_DAY * WORKDAYS_PER_WEEK);
-    let days = total_minutes / (60 * WORKHOURS_PER_DAY);
-    total_minutes -= days * (60 * WORKHOURS_PER_DAY);
+    let weeks = total_minutes / units.week_minutes;
+    total_minutes -= weeks * units.week_minutes;
+    let days = total_minutes / units.day_minutes;
+    total_minutes -= days * units.day_minutes;
     let hours = total_minutes / 60;
     total_minutes -= hours * 60;
     let minutes = total_minutes as f64 + (duration.num_seconds() % 60) as f64 / 60.0;
@@ -92,6 +284,19 @@ fn test_format_human_duration() {
     assert_eq!(format_human_duration(Duration::minutes(2402)), "1w 2min");
 }
 
+#[test]
+fn test_format_human_duration_in_working_day() {
+    // 9:00-19:15 (10.25h) の勤務日なら、10h の見積は 1 日に収まる
+    let units = DurationUnits::from_working_time((NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(19, 15, 0).unwrap()));
+    assert_eq!(format_human_duration_in(Duration::minutes(600), units), "10h");
+    assert_eq!(format_human_duration_in(Duration::minutes(615), units), "1d");
+    assert_eq!(format_human_duration_in(Duration::minutes(615 * 5 + 60), units), "1w 1h");
+    // 入力も同じ単位で解釈し、表示と読み戻しが一致する
+    assert_eq!(parse_human_duration_in("1d", units), Some(Duration::minutes(615)));
+    assert_eq!(parse_human_duration_in("1w", units), Some(Duration::minutes(615 * 5)));
+    assert_eq!(format_human_duration_in(parse_human_duration_in("2d", units).unwrap(), units), "2d");
+}
+
 pub enum StopKind {
     Immediately(NaiveDateTime),
     EndsAt(NaiveDateTime),
@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use super::task::{self, TaskID};
-use chrono::{Duration, NaiveDate, NaiveTime};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,21 +13,43 @@ pub struct WorkLogItem {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkLog {
-    dirty: bool,
     items: BTreeMap<NaiveDate, Vec<WorkLogItem>>,
 }
 impl WorkLog {
     pub fn new() -> Self {
-        Self { dirty: false, items: BTreeMap::new() }
+        Self { items: BTreeMap::new() }
     }
     pub fn from_items(items: BTreeMap<NaiveDate, Vec<WorkLogItem>>) -> Self {
-        Self { dirty: false, items }
+        Self { items }
     }
 
     pub fn add_item(&mut self, date: NaiveDate, task_id: TaskID, begin_at: NaiveTime, duration: Duration) {
         let item = WorkLogItem { begin_at, duration, task_id };
         self.items.entry(date).or_default().push(item);
-        self.dirty = true;
+    }
+
+    /// 指定日の `index` 番目の記録の作業時間を変更し、変更前の記録を返す
+    pub fn edit_item(&mut self, date: NaiveDate, index: usize, duration: Duration) -> Result<WorkLogItem, String> {
+        let item = self
+            .items
+            .get_mut(&date)
+            .and_then(|items| items.get_mut(index))
+            .ok_or_else(|| format!("{} に該当する作業記録がありません", date))?;
+        let previous = item.clone();
+        item.duration = duration;
+        Ok(previous)
+    }
+
+    /// 指定日の `index` 番目の記録を削除し、削除した記録を返す
+    pub fn remove_item(&mut self, date: NaiveDate, index: usize) -> Result<WorkLogItem, String> {
+        let Some(items) = self.items.get_mut(&date).filter(|items| index < items.len()) else {
+            return Err(format!("{} に該当する作業記録がありません", date));
+        };
+        let removed = items.remove(index);
+        if items.is_empty() {
+            self.items.remove(&date);
+        }
+        Ok(removed)
     }
 
     pub fn get_items(&self, date: NaiveDate) -> Option<&Vec<WorkLogItem>> {
@@ -43,11 +65,76 @@ impl WorkLog {
             .sum()
     }
 
-    pub fn is_dirty(&self) -> bool {
-        self.dirty
+    /// タスクの作業記録を日付・開始時刻の順に返す (手をつけなかった印の 0 分の記録は除く)
+    fn worked_items(&self, task_id: TaskID) -> impl Iterator<Item = (NaiveDateTime, &WorkLogItem)> {
+        self.items
+            .iter()
+            .flat_map(|(date, items)| items.iter().map(move |item| (date.and_time(item.begin_at), item)))
+            .filter(move |(_, item)| item.task_id == task_id && item.duration > Duration::zero())
+    }
+
+    /// タスクに最初に着手した日時
+    pub fn first_worked(&self, task_id: TaskID) -> Option<NaiveDateTime> {
+        self.worked_items(task_id).map(|(begin_at, _)| begin_at).min()
+    }
+
+    /// タスクの作業を最後に終えた日時
+    pub fn last_worked(&self, task_id: TaskID) -> Option<NaiveDateTime> {
+        self.worked_items(task_id).map(|(begin_at, item)| begin_at + item.duration).max()
+    }
+
+    /// タスクに作業した日数
+    pub fn worked_days(&self, task_id: TaskID) -> usize {
+        self.items
+            .iter()
+            .filter(|(_, items)| items.iter().any(|item| item.task_id == task_id && item.duration > Duration::zero()))
+            .count()
     }
 
     pub fn items(&self) -> &BTreeMap<NaiveDate, Vec<WorkLogItem>> {
         &self.items
     }
 }
+
+#[test]
+fn test_edit_and_remove_item() {
+    let date = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let task_id = TaskID::new();
+    let mut log = WorkLog::new();
+    log.add_item(date, task_id, NaiveTime::from_hms_opt(10, 0, 0).unwrap(), Duration::hours(3));
+    log.add_item(date, task_id, NaiveTime::from_hms_opt(14, 0, 0).unwrap(), Duration::minutes(20));
+
+    let previous = log.edit_item(date, 0, Duration::minutes(30)).unwrap();
+    assert_eq!(previous.duration, Duration::hours(3));
+    assert_eq!(log.total_recorded_duration(task_id), Duration::minutes(50));
+
+    assert!(log.edit_item(date, 2, Duration::minutes(30)).is_err());
+    assert!(log.remove_item(date + Duration::days(1), 0).is_err());
+
+    let removed = log.remove_item(date, 1).unwrap();
+    assert_eq!(removed.duration, Duration::minutes(20));
+    log.remove_item(date, 0).unwrap();
+    assert!(log.get_items(date).is_none());
+    assert!(log.remove_item(date, 0).is_err());
+}
+
+#[test]
+fn test_first_and_last_worked() {
+    let d1 = NaiveDate::from_ymd_opt(2025, 5, 7).unwrap();
+    let d5 = NaiveDate::from_ymd_opt(2025, 5, 11).unwrap();
+    let task_id = TaskID::new();
+    let other_id = TaskID::new();
+    let mut log = WorkLog::new();
+    assert!(log.first_worked(task_id).is_none());
+
+    log.add_item(d5, task_id, NaiveTime::from_hms_opt(13, 0, 0).unwrap(), Duration::minutes(45));
+    log.add_item(d1, task_id, NaiveTime::from_hms_opt(14, 0, 0).unwrap(), Duration::minutes(30));
+    log.add_item(d1, task_id, NaiveTime::from_hms_opt(10, 0, 0).unwrap(), Duration::minutes(20));
+    log.add_item(d1, other_id, NaiveTime::from_hms_opt(9, 0, 0).unwrap(), Duration::minutes(20));
+    // 手をつけなかった印 (0 分) は作業期間に含めない
+    log.add_item(d5 + Duration::days(1), task_id, NaiveTime::from_hms_opt(17, 0, 0).unwrap(), Duration::zero());
+
+    assert_eq!(log.first_worked(task_id), Some(d1.and_hms_opt(10, 0, 0).unwrap()));
+    assert_eq!(log.last_worked(task_id), Some(d5.and_hms_opt(13, 45, 0).unwrap()));
+    assert_eq!(log.worked_days(task_id), 2);
+}
@@ -1,40 +1,115 @@
 #![allow(unused)]
-use core::{calendar::Calendar, session::Session, slot, store};
-use std::io::{self, Write};
+use core::{burndown::BurndownSnapshot, calendar::Calendar, config::Config, session::Session, slot::ScheduleSnapshot, store};
+use std::io::{self, IsTerminal, Write};
 
-use rustyline::{config::Configurer, error::ReadlineError};
+use completer::ShellCompleter;
+use rustyline::{Cmd, CompletionType, KeyEvent, config::Configurer, error::ReadlineError, history::DefaultHistory};
+mod completer;
 mod core;
 mod shell;
+mod theme;
 
 const SETTINGS_DIR: &str = "./settings";
 const TASKS_FILE: &str = "tasks.json";
 const WORKLOG_FILE: &str = "worklog.json";
+const BURNDOWN_FILE: &str = "burndown.jsonl";
+const BASELINE_FILE: &str = "baseline.json";
+const SCHEDULE_FILE: &str = "schedule.json";
+const PINNED_FILE: &str = "pinned.json";
 const COMMAND_HISTORY_FILE: &str = ".history";
+const LOCK_FILE: &str = "tasks.json.lock";
 
 fn main() -> anyhow::Result<()> {
-    println!("🧠 LazyScheduler Shell - type 'help' to get started");
+    // 先頭の --ascii か NO_EMOJI で絵文字を使わずに出力する (コマンドの引数の --ascii はそのまま渡す)
+    let mut args = std::env::args().skip(1).peekable();
+    let ascii_flag = args.next_if(|arg| arg == "--ascii").is_some();
+    let argv: Vec<String> = args.collect();
+    theme::set_ascii(ascii_flag || std::env::var_os("NO_EMOJI").is_some_and(|v| !v.is_empty()));
+    // 色は端末に出力するときだけ使う (NO_COLOR で無効)
+    theme::set_color(!theme::is_ascii() && io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()));
+    // 他のインスタンスが起動中なら、変更を上書きしないよう読み取り専用で動作する (読み込みの途中で書き換えられないよう、読み込む前にロックする)
+    let lock = store::FileLock::acquire(LOCK_FILE)?;
+    let read_only = lock.is_none();
+    if read_only {
+        eprintln!(
+            "{} 他のインスタンスが起動中です ({})。読み取り専用で起動します (変更は保存されません)。",
+            theme::symbol("⚠️"),
+            LOCK_FILE
+        );
+    }
+    // 設定がなければ既定の設定を作って、そのまま使い始められるようにする
+    if Calendar::create_default_settings(SETTINGS_DIR, chrono::Local::now().date_naive())? {
+        eprintln!(
+            "{} {}/settings.yaml がなかったため、既定の設定 (09:00–17:00、土日休み) で作成しました。",
+            theme::symbol("📝"),
+            SETTINGS_DIR
+        );
+    }
+    let calendar = Calendar::import_from_yaml(SETTINGS_DIR)?;
+    let config = Config::import_from_yaml(SETTINGS_DIR)?;
+    // タスクファイルがなければ初回起動とみなす
+    let first_run = !std::path::Path::new(TASKS_FILE).exists();
+    let tasks = store::load_tasks(TASKS_FILE)?;
+    let log = store::load_worklog(WORKLOG_FILE)?;
+    let baseline = store::load_baseline(BASELINE_FILE)?;
+    let mut session = Session::new(calendar, config, tasks, log, baseline);
+    session.pinned = store::load_pinned(PINNED_FILE)?;
+    // 前回のスケジュールを復元する (壊れていれば次のコマンドで再計算される)
+    match store::load_schedule(SCHEDULE_FILE) {
+        Ok(Some(snapshot)) if !settings_changed_since(SCHEDULE_FILE) => session.restore_schedule(snapshot),
+        Ok(_) => {}
+        Err(err) => eprintln!("{} {} を読み込めませんでした。再計算します: {}", theme::symbol("⚠️"), SCHEDULE_FILE, err),
+    }
+
+    // 起動引数があればコマンドを 1 つ実行して終了する (cron などからの非対話実行用)
+    if !argv.is_empty() {
+        // 先頭の @<timestamp> で現在時刻を上書きできるのは対話モードと同じ
+        let input = argv.join(" ");
+        let now = shell::split_now_prefix(&input).ok().and_then(|(now, _)| now).unwrap_or_else(|| session.now());
+        // strict モードで見積が足りない場合も、見積を直すコマンドは実行できるようにする
+        if let Err(err) = session.schedule_if_stale(now) {
+            eprintln!("{} {}", theme::symbol("⚠️"), err);
+        }
+        let result = shell::handle_command(&mut session, &input);
+        save_session(&session, read_only);
+        if let Err(err) = result {
+            eprintln!("{} Error: {}", theme::symbol("❌"), err);
+            // process::exit はデストラクタを実行しないため、先にロックを解放する
+            drop(lock);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
-    let mut rl = rustyline::DefaultEditor::new()?;
+    println!("{} LazyScheduler Shell - type 'help' to get started", theme::symbol("🧠"));
+    if first_run {
+        shell::print_onboarding();
+    }
+    shell::print_follow_ups(&session, session.now());
+
+    let mut rl = rustyline::Editor::<ShellCompleter, DefaultHistory>::new()?;
     if std::path::Path::new(COMMAND_HISTORY_FILE).exists() {
         rl.load_history(COMMAND_HISTORY_FILE)?;
     }
     rl.set_auto_add_history(true);
     rl.set_max_history_size(1000);
-
-    let calendar = Calendar::import_from_yaml(SETTINGS_DIR)?;
-    let tasks = store::load_tasks(TASKS_FILE)?;
-    let log = store::load_worklog(WORKLOG_FILE)?;
-    let mut session = Session::new(calendar, tasks, log);
+    rl.set_completion_type(CompletionType::List);
+    rl.set_helper(Some(ShellCompleter::default()));
+    // Ctrl-R で履歴のインクリメンタル検索
+    rl.bind_sequence(KeyEvent::ctrl('R'), Cmd::ReverseSearchHistory);
 
     loop {
+        if let Some(completer) = rl.helper_mut() {
+            completer.update(&session);
+        }
         let prompt = match &session.active_task {
-            Some((task_id, started_at)) => format!("{} (started at {}) > ", task_id, started_at),
+            Some((task_id, started_at)) => format!("{} (started at {}) > ", session.short_id(task_id), started_at),
             None => "> ".to_owned(),
         };
         let line = rl.readline(&prompt);
         match line {
             Err(ReadlineError::Eof) => {
-                println!("👋 Bye!");
+                println!("{} Bye!", theme::symbol("👋"));
                 break;
             }
             Err(ReadlineError::Interrupted) => {
@@ -42,7 +117,7 @@ fn main() -> anyhow::Result<()> {
                 continue;
             }
             Err(err) => {
-                eprintln!("❌ Error reading input: {}", err);
+                eprintln!("{} Error reading input: {}", theme::symbol("❌"), err);
                 continue;
             }
             Ok(line) => {
@@ -52,12 +127,12 @@ fn main() -> anyhow::Result<()> {
                 }
                 match trimmed {
                     "exit" | "quit" => {
-                        println!("👋 Bye!");
+                        println!("{} Bye!", theme::symbol("👋"));
                         break;
                     }
                     _ => {
                         if let Err(err) = shell::handle_command(&mut session, trimmed) {
-                            eprintln!("❌ Error: {}", err);
+                            eprintln!("{} Error: {}", theme::symbol("❌"), err);
                         }
                     }
                 }
@@ -65,25 +140,138 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
+    print_session_summary(&session);
+    save_session(&session, read_only);
+    // Save history
+    rl.save_history(COMMAND_HISTORY_FILE)?;
+
+    Ok(())
+}
+
+/// 設定 (カレンダー) が `path` の保存後に更新されたか。schedule/ や重ねたカレンダーのディレクトリも含めて調べる
+fn settings_changed_since(path: &str) -> bool {
+    let Ok(saved_at) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+        return true;
+    };
+    let Ok(dirs) = Calendar::source_dirs(SETTINGS_DIR) else {
+        return true;
+    };
+    dirs.iter().any(|dir| modified_after(dir, saved_at))
+}
+
+/// `path` (ディレクトリなら中のファイルも再帰的に) が `at` より後に更新されたか。読めなければ更新されたとみなす
+fn modified_after(path: &std::path::Path, at: std::time::SystemTime) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return true;
+    };
+    if metadata.modified().map_or(true, |modified| modified > at) {
+        return true;
+    }
+    if !metadata.is_dir() {
+        return false;
+    }
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return true;
+    };
+    entries.flatten().any(|entry| modified_after(&entry.path(), at))
+}
+
+/// 終了時に、このセッションで完了したタスク数・記録した作業時間と今日の計画との比較を表示する
+fn print_session_summary(session: &Session) {
+    let stats = &session.stats;
+    println!(
+        "{} 今回のセッション: 完了 {}件 / 作業記録 {}",
+        theme::symbol("📊"),
+        stats.completed,
+        session.format_duration(stats.logged)
+    );
+    let today = session.now().date();
+    if let Some(reconciled) = session.baseline.reconcile_day(today, &session.log) {
+        let planned: chrono::Duration = reconciled.iter().map(|(_, planned, _)| *planned).sum();
+        let actual: chrono::Duration = reconciled.iter().map(|(_, _, actual)| *actual).sum();
+        if !planned.is_zero() {
+            let percent = actual.num_minutes() * 100 / planned.num_minutes().max(1);
+            println!("   今日の計画: {} / {} ({}%)", session.format_duration(actual), session.format_duration(planned), percent);
+        }
+    }
+}
+
+fn save_session(session: &Session, read_only: bool) {
+    if read_only {
+        if session.dirty.tasks || session.dirty.worklog || session.dirty.calendar || session.config.is_dirty() || session.dirty.pinned {
+            eprintln!("{} 読み取り専用のため、変更は保存されませんでした。", theme::symbol("⚠️"));
+        }
+        return;
+    }
     // Save tasks to file before exiting
-    if session.dirty_tasks {
+    if session.dirty.tasks {
         if let Err(err) = store::save_tasks(&session.tasks, TASKS_FILE) {
-            eprintln!("❌ Error saving tasks: {}", err);
+            eprintln!("{} Error saving tasks: {}", theme::symbol("❌"), err);
         } else {
-            println!("✅ Tasks saved to {}", TASKS_FILE);
+            println!("{} Tasks saved to {}", theme::symbol("✅"), TASKS_FILE);
+            // 保存のたびに残り時間の合計を記録する
+            let snapshot = BurndownSnapshot::take(chrono::Local::now().naive_local(), session.iter_tasks());
+            if let Err(err) = store::append_burndown(&snapshot, BURNDOWN_FILE) {
+                eprintln!("{} Error saving burndown: {}", theme::symbol("❌"), err);
+            }
         }
     }
 
     // Save log to file before exiting
-    if session.log.is_dirty() {
+    if session.dirty.worklog {
         if let Err(err) = store::save_worklog(&session.log, WORKLOG_FILE) {
-            eprintln!("❌ Error saving logs: {}", err);
+            eprintln!("{} Error saving logs: {}", theme::symbol("❌"), err);
         } else {
-            println!("✅ Worklogs saved to {}", WORKLOG_FILE);
+            println!("{} Worklogs saved to {}", theme::symbol("✅"), WORKLOG_FILE);
         }
     }
-    // Save history
-    rl.save_history(COMMAND_HISTORY_FILE)?;
 
-    Ok(())
+    // 取り込んだ予定を日ごとの予定ファイルに書き足す
+    if session.dirty.calendar {
+        let saved = session
+            .added_busy_items
+            .iter()
+            .try_for_each(|(date, items)| Calendar::append_to_day_schedule(SETTINGS_DIR, *date, items).map(|_| ()));
+        if let Err(err) = saved {
+            eprintln!("{} Error saving calendar: {}", theme::symbol("❌"), err);
+        } else {
+            println!("{} Calendar saved to {}/schedule", theme::symbol("✅"), SETTINGS_DIR);
+        }
+    }
+
+    // スケジュールも黙って保存する (次回起動時に再計算を省くため)
+    if session.dirty.schedule
+        && let Some(scheduled_at) = session.scheduled_at
+    {
+        let snapshot = ScheduleSnapshot {
+            scheduled_at,
+            slots: session.slots.clone(),
+        };
+        if let Err(err) = store::save_schedule(&snapshot, SCHEDULE_FILE) {
+            eprintln!("{} Error saving schedule: {}", theme::symbol("❌"), err);
+        }
+    }
+
+    // settings コマンドで変更した設定を書き戻す
+    if session.config.is_dirty() {
+        if let Err(err) = session.config.save_changes(SETTINGS_DIR) {
+            eprintln!("{} Error saving settings: {}", theme::symbol("❌"), err);
+        } else {
+            println!("{} Settings saved to {}/settings.yaml", theme::symbol("✅"), SETTINGS_DIR);
+        }
+    }
+
+    // 固定したタスクも黙って保存する
+    if session.dirty.pinned
+        && let Err(err) = store::save_pinned(session.pinned, PINNED_FILE)
+    {
+        eprintln!("{} Error saving pinned task: {}", theme::symbol("❌"), err);
+    }
+
+    // 計画の基準値は黙って保存する (日が変わるたびに更新されるため)
+    if session.baseline.is_dirty()
+        && let Err(err) = store::save_baseline(&session.baseline, BASELINE_FILE)
+    {
+        eprintln!("{} Error saving baseline: {}", theme::symbol("❌"), err);
+    }
 }
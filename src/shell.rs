@@ -2,33 +2,48 @@ use core::panic;
 use std::default;
 
 use crate::core::{
+    burndown::{self, BurndownSnapshot},
+    calendar::{Calendar, ScheduleItem},
+    config::CategoryColor,
     deadline::{self, Deadline, FuzzyDeadline, FuzzyDeadlineKind},
     estimate::Estimate,
-    session,
-    task::{ExternalBlockingReason, Progress, Task, TaskStatus},
-    utils::{StopKind, format_human_duration, parse_human_duration, parse_human_duration_with_sign, parse_stop_kind},
+    ics, session, store,
+    task::{ExternalBlockingReason, Progress, Task, TaskID, TaskStatus},
+    utils::{DurationUnits, StopKind, parse_date, parse_human_duration, parse_human_duration_with_sign, parse_positive_duration, parse_spread_percent, parse_stop_kind, parse_story_points},
 };
-use anyhow::{anyhow, bail};
+use crate::theme;
+use anyhow::{Context, anyhow, bail};
 use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, format, naive};
 use regex::Regex;
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+
+pub mod help;
 
 const TASKS_FILE: &str = "tasks.json";
+/// 見積なしのタスクに見積を促す、期限までの残り日数
+const ESTIMATE_NUDGE_DAYS: i64 = 3;
 
 fn task_status_symbol(task: &Task) -> &'static str {
     if task.is_ready() {
-        "⬜"
+        theme::symbol("⬜")
     } else if task.is_blocked() {
-        "⌛"
+        theme::symbol("⌛")
     } else if task.is_completed() {
-        "✅"
+        theme::symbol("✅")
     } else if task.is_dropped() {
-        "❌"
+        theme::symbol("❌")
     } else {
         panic!("Unknown task status");
     }
 }
 
-pub fn parse_deadline<'a>(now: NaiveDateTime, default_deadline_time: NaiveTime, mut parts: impl Iterator<Item = &'a str>) -> anyhow::Result<Deadline> {
+/// 引数の ID 前方一致でタスクを探す。表示どおりの `#1d48` もそのまま受け付ける
+fn find_task(session: &session::Session, key: &str) -> Option<TaskID> {
+    session.find_task_by_prefix(key.strip_prefix('#').unwrap_or(key))
+}
+
+pub fn parse_deadline<'a>(now: NaiveDateTime, calendar: &Calendar, default_deadline_time: NaiveTime, mut parts: impl Iterator<Item = &'a str>) -> anyhow::Result<Deadline> {
     let Some(first) = parts.next() else {
         bail!("deadline を指定してください");
     };
@@ -43,29 +58,8 @@ pub fn parse_deadline<'a>(now: NaiveDateTime, default_deadline_time: NaiveTime,
                 // time-only → 今日の日付 + 指定時刻
                 (now.date(), t)
             } else {
-                // 日付ありパターン
-                // 1) YYYY-MM-DD
-                // 2) YYYY/MM/DD
-                // 3) MM/DD (年省略 → now.year())
-                let date = if tok.contains('-') {
-                    NaiveDate::parse_from_str(tok, "%Y-%m-%d").map_err(|_| anyhow!("日付形式は YYYY-MM-DD で指定してください"))?
-                } else if tok.contains('/') {
-                    let parts: Vec<_> = tok.split('/').collect();
-                    match parts.as_slice() {
-                        [y, m, d] => {
-                            // YYYY/MM/DD
-                            NaiveDate::parse_from_str(tok, "%Y/%m/%d").map_err(|_| anyhow!("日付形式は YYYY/MM/DD で指定してください"))?
-                        }
-                        [m, d] => {
-                            // MM/DD → 今の年
-                            let year = now.year();
-                            NaiveDate::from_ymd_opt(year, m.parse().map_err(|_| anyhow!("月が不正です"))?, d.parse().map_err(|_| anyhow!("日が不正です"))?).ok_or_else(|| anyhow!("無効な日付です"))?
-                        }
-                        _ => bail!("日付形式は YYYY-MM-DD, YYYY/MM/DD, MM/DD のいずれかです"),
-                    }
-                } else {
-                    bail!("日付形式が不正です: {}", tok);
-                };
+                // 日付ありパターン (YYYY-MM-DD, YYYY/MM/DD, MM/DD, today, mon など)
+                let date = parse_date(now, tok).map_err(anyhow::Error::msg)?;
 
                 // オプションで続くトークンを時刻として解釈
                 let next_tok = parts.next();
@@ -82,12 +76,28 @@ pub fn parse_deadline<'a>(now: NaiveDateTime, default_deadline_time: NaiveTime,
 
             Ok(Deadline::Exact(date.and_time(time)))
         }
-        "none" => Ok(Deadline::None),
+        "none" | "clear" => Ok(Deadline::None),
         "unknown" => Ok(Deadline::Unknown),
         "in" => {
-            let duration_str = parts.next().ok_or_else(|| anyhow!("duration が必要です (例: 3d, 5h)"))?.trim().to_lowercase();
+            let mut duration_str = parts.next().ok_or_else(|| anyhow!("duration が必要です (例: 3d, 5h)"))?.trim().to_lowercase();
+            // `in 3 workdays` のように単位を分けて書いてもよい
+            if duration_str.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && let Some(unit) = parts.next()
+            {
+                duration_str.push_str(&unit.to_lowercase());
+            }
             let (num_str, unit) = duration_str.split_at(duration_str.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(duration_str.len()));
             let value: f64 = num_str.parse().map_err(|_| anyhow!("数値部分が不正です"))?;
+            if let "wd" | "workday" | "workdays" = unit.trim() {
+                // 24 時間単位ではなく公式稼働日で数える (休日をまたいでも n 稼働日後の既定時刻)
+                let Some(days) = num_str.parse::<u16>().ok() else {
+                    bail!("稼働日数は整数で指定してください: {}", num_str);
+                };
+                let Some(date) = calendar.add_business_days(now.date(), days) else {
+                    bail!("{}の{}稼働日後がカレンダーの範囲外です", now.date(), days);
+                };
+                return Ok(Deadline::Exact(date.and_time(default_deadline_time)));
+            }
             let mins = match unit.trim() {
                 "m" | "min" | "mins" => value,
                 "h" | "hr" | "hrs" => value * 60.0,
@@ -133,8 +143,8 @@ pub fn handle_block_by_task(session: &mut session::Session, args: Vec<&str>) ->
     if id_key.is_empty() {
         bail!("ID is required for block command");
     }
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
     let dependencies = args
         .iter()
@@ -144,8 +154,8 @@ pub fn handle_block_by_task(session: &mut session::Session, args: Vec<&str>) ->
             if id_key.is_empty() {
                 bail!("ID is required for block command");
             }
-            let Some(tid) = session.find_task_by_prefix(id_key) else {
-                bail!("⚠️タスク{}が見つかりません。", id_key);
+            let Some(tid) = find_task(session, id_key) else {
+                bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
             };
             if task_id == tid {
                 return Ok(None);
@@ -154,8 +164,9 @@ pub fn handle_block_by_task(session: &mut session::Session, args: Vec<&str>) ->
         })
         .filter_map(|x| x.transpose())
         .collect::<Result<Vec<_>, _>>()?;
+    let short_id = session.short_id(&task_id);
     let (task, dependencies) = session.block_task_by_tasks(&task_id, dependencies);
-    println!("⌛ ブロッキング: {} - {}", task.id, task.title);
+    println!("{} ブロッキング: {} - {}", theme::symbol("⌛"), short_id, task.title);
     if dependencies.is_empty() {
         println!("  依存タスクなし");
     } else {
@@ -167,127 +178,692 @@ pub fn handle_block_by_task(session: &mut session::Session, args: Vec<&str>) ->
     Ok(())
 }
 
+fn handle_replace_dependency(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let [id_key, old_key, new_key] = args[..] else {
+        bail!("Usage: redep <task-id> <old-dep> <new-dep>");
+    };
+    let mut ids = vec![];
+    for key in [id_key, old_key, new_key] {
+        let Some(id) = find_task(session, key) else {
+            bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), key);
+        };
+        ids.push(id);
+    }
+    let (task_id, old, new) = (ids[0], ids[1], ids[2]);
+    session.replace_dependency(&task_id, &old, &new)?;
+    let task = &session.tasks[&task_id];
+    println!("{} 依存を差し替え: {} - {}", theme::symbol("⌛"), session.short_id(&task.id), task.title);
+    println!("  - {}", session.tasks[&old].title);
+    println!("  + {}", session.tasks[&new].title);
+    Ok(())
+}
+
 fn handle_block_by_external(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
     let id_key = args.first().unwrap_or(&"");
     if id_key.is_empty() {
         bail!("ID is required for block command");
     }
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    // 末尾の `remind <interval>` はフォローの間隔
+    let (deadline_args, remind_every) = match args.iter().position(|arg| *arg == "remind") {
+        Some(i) => {
+            let Some(every) = args.get(i + 1).and_then(|arg| parse_remind_interval(arg)) else {
+                bail!("Usage: ble <task-id> <deadline> [remind <interval>] (interval 例: 3d, 1w, 4h)");
+            };
+            (&args[1..i], Some(every))
+        }
+        None => (&args[1..], None),
+    };
+    let deadline = parse_deadline(now, &session.calendar, session.scheduler.default_deadline_time, deadline_args.iter().copied())?;
+    session.block_task_by_external(&task_id, now, deadline, None, remind_every);
+    let task = &session.tasks[&task_id];
+    println!("{} ブロッキング: {} - {}", theme::symbol("⌛"), session.short_id(&task.id), task.title);
+    if let Some(every) = remind_every {
+        println!("  {} {}ごとにフォロー", theme::symbol("🔔"), format_remind_interval(every));
+    }
+    Ok(())
+}
+
+/// フォローの間隔。`d` / `w` は暦の日・週 (勤務時間換算ではない)、それ以外は時間の書式で解釈する
+fn parse_remind_interval(arg: &str) -> Option<Duration> {
+    let interval = if let Some(days) = arg.strip_suffix('d') {
+        Duration::days(days.parse().ok()?)
+    } else if let Some(weeks) = arg.strip_suffix('w') {
+        Duration::weeks(weeks.parse().ok()?)
+    } else {
+        parse_human_duration(arg)?
+    };
+    (interval > Duration::zero()).then_some(interval)
+}
+fn format_remind_interval(every: Duration) -> String {
+    if every.num_hours() % 24 == 0 {
+        format!("{}日", every.num_days())
+    } else {
+        format!("{}時間", every.num_hours().max(1))
+    }
+}
+
+fn handle_follow_up(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let Some(id_key) = args.first() else {
+        bail!("Usage: followup <task-id>");
     };
-    let deadline = parse_deadline(now, session.scheduler.working_time.0, args.iter().skip(1).copied())?;
-    let task = session.block_task_by_external(&task_id, now, deadline, None);
-    println!("⌛ ブロッキング: {} - {}", task.id, task.title);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    session.mark_followed_up(&task_id, now)?;
+    let task = &session.tasks[&task_id];
+    println!("{} フォロー済み: {} - {}", theme::symbol("📨"), session.short_id(&task.id), task.title);
     Ok(())
 }
 
-fn handle_add(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
-    let title: String = args.join(" ");
+/// フォローの間隔が過ぎた外部待ちを一覧する (起動時)
+pub fn print_follow_ups(session: &session::Session, now: NaiveDateTime) {
+    let follow_ups = session.follow_ups(now);
+    if follow_ups.is_empty() {
+        return;
+    }
+    println!("{} 要フォローの外部待ち ({}件):", theme::symbol("🔔"), follow_ups.len());
+    for task in follow_ups {
+        println!("  {} {}", session.short_id(&task.id), task.title);
+    }
+    println!("  先方に確認したら followup <tid> で記録してください。");
+}
+
+fn handle_add(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let force = args.contains(&"--force");
+    let args: Vec<_> = args.into_iter().filter(|arg| *arg != "--force").collect();
+    let (args, dependencies) = split_after_clause(session, &args)?;
+    let title = args.join(" ");
     if title.is_empty() {
         bail!("Title is required for add command");
     }
-    let task = Task::new(title.clone(), None, None);
-    let task = session.add_task(task);
-    println!("✅ 追加: {} - {}", task.id, task.title);
+    if !force && let Some(existing) = session.find_duplicate_task(&title) {
+        bail!(
+            "似たタスクがあります: {} - {}。追加する場合は --force を付けてください。",
+            session.short_id(&existing.id),
+            existing.title
+        );
+    }
+    let task_id = session.add_task(Task::new(title.clone(), None, None)).id;
+    let task = &session.tasks[&task_id];
+    println!("{} 追加: {} - {}", theme::symbol("✅"), session.short_id(&task.id), task.title);
+    if !dependencies.is_empty() {
+        let id_len = session.id_display_len();
+        let (_, dependencies) = session.block_task_by_tasks(&task_id, dependencies);
+        let titles: Vec<_> = dependencies.iter().map(|dep| format!("{} {}", dep.id.short(id_len), dep.title)).collect();
+        println!("  {} 依存タスク: {}", theme::symbol("⌛"), titles.join(", "));
+    }
+    nudge_estimate_if_due_soon(session, now, &task_id);
     Ok(())
 }
 
-fn handle_list(session: &mut session::Session, _now: NaiveDateTime, _args: Vec<&str>) -> anyhow::Result<()> {
-    if session.iter_tasks().next().is_none() {
-        println!("(タスクなし)");
+/// コマンドの書式を知らなくても使えるよう、タイトル・見積・期限・依存タスクを 1 つずつ尋ねてタスクを作る。
+/// 各項目は対応するコマンド (est / dl / add ... after) と同じ書式で、Enter で省略できる。すべて聞き終えてから作成する
+fn handle_new(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let force = args.contains(&"--force");
+    // 端末から実行していなければ (cron などからの非対話実行)、入力を待たずに終える
+    if !std::io::stdin().is_terminal() {
+        bail!("new は対話形式のため、端末から実行してください (非対話では add を使ってください)");
+    }
+    let mut editor = rustyline::DefaultEditor::new()?;
+    println!("{} 新しいタスクを作成します (各項目は Enter で省略、タイトルを空にすると中止)", theme::symbol("🧙"));
+    let Some(title) = ask(&mut editor, "タイトル", |line| Ok(line.to_string()))? else {
+        println!("中止しました");
+        return Ok(());
+    };
+    if !force && let Some(existing) = session.find_duplicate_task(&title) {
+        bail!(
+            "似たタスクがあります: {} - {}。追加する場合は new --force で作り直してください。",
+            session.short_id(&existing.id),
+            existing.title
+        );
+    }
+    let estimate_args = ask(&mut editor, "見積 (例: 1h / 1h 30m 2h / 1d ±50% / 3pt)", |line| {
+        let args: Vec<_> = line.split_whitespace().collect();
+        match args.iter().find_map(|arg| parse_story_points(arg)) {
+            Some(points) if session.config.story_points.estimate(points).is_none() => bail!("{}pt は対応表にありません", points),
+            Some(_) => {}
+            None if args.iter().any(|arg| arg.starts_with(['+', '-'])) => bail!("新しいタスクの見積に +/- は使えません"),
+            None => {
+                parse_estimate_times(&args, Estimate::new(Duration::zero()), session.calendar.duration_units())?;
+            }
+        }
+        Ok(line.to_string())
+    })?;
+    let deadline_time = session.scheduler.default_deadline_time;
+    let deadline = ask(&mut editor, "期限 (例: on 05/10 / in 3d / in 2 workdays / about 1fri)", |line| {
+        parse_deadline(now, &session.calendar, deadline_time, line.split_whitespace())
+    })?;
+    let dependencies = ask(&mut editor, "依存タスク (先に終わらせるタスクの ID、例: #1d48 #00fe)", |line| {
+        line.split_whitespace()
+            .map(|key| find_task(session, key).ok_or_else(|| anyhow!("タスク{}が見つかりません。", key)))
+            .collect::<anyhow::Result<Vec<_>>>()
+    })?;
+
+    let task_id = session.add_task(Task::new(title, deadline, None)).id;
+    let task = &session.tasks[&task_id];
+    println!("{} 追加: {} - {}", theme::symbol("✅"), session.short_id(&task.id), task.title);
+    if let Some(dependencies) = dependencies {
+        let id_len = session.id_display_len();
+        let (_, dependencies) = session.block_task_by_tasks(&task_id, dependencies);
+        let titles: Vec<_> = dependencies.iter().map(|dep| format!("{} {}", dep.id.short(id_len), dep.title)).collect();
+        println!("  {} 依存タスク: {}", theme::symbol("⌛"), titles.join(", "));
+    }
+    if let Ok(Some(deadline)) = session.tasks[&task_id].resolve_deadline(&session.calendar, session.deadline_time_of(&task_id)) {
+        println!("  期限: {}", session.format_datetime(deadline));
+    }
+    if let Some(estimate_args) = estimate_args {
+        apply_estimate(session, now, &task_id, &estimate_args.split_whitespace().collect::<Vec<_>>())?;
     } else {
-        let println_task = |task: &Task| {
-            println!("    {} {}", task.id, task.title);
-            let remaining = task.remaining();
-            if let Some(estimate) = task.estimate() {
-                if estimate.stddev().num_minutes() > 0 {
-                    println!(
-                        "      予想: {} (最尤{}, 楽観{}, 最悪{}, σ={})",
-                        format_human_duration(estimate.mean()),
-                        format_human_duration(estimate.most_likely),
-                        format_human_duration(estimate.optimistic),
-                        format_human_duration(estimate.pessimistic),
-                        format_human_duration(estimate.stddev())
-                    );
-                } else {
-                    println!("      予想: {}", format_human_duration(estimate.mean()));
-                }
+        nudge_estimate_if_due_soon(session, now, &task_id);
+    }
+    warn_unreachable_deadline(session, now, &task_id);
+    Ok(())
+}
+/// `new` の 1 項目を尋ねる。解釈できなければ理由を表示して尋ね直す。Enter だけなら None、入力が終わったら (Ctrl-D / Ctrl-C) 中止する
+fn ask<T>(editor: &mut rustyline::DefaultEditor, label: &str, mut parse: impl FnMut(&str) -> anyhow::Result<T>) -> anyhow::Result<Option<T>> {
+    loop {
+        let line = match editor.readline(&format!("  {}: ", label)) {
+            Ok(line) => line,
+            Err(rustyline::error::ReadlineError::Eof | rustyline::error::ReadlineError::Interrupted) => {
+                bail!("入力が終了したため、タスクの作成を中止しました")
             }
-            if !task.actual_total.is_zero() {
+            Err(err) => return Err(err.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        match parse(line) {
+            Ok(value) => return Ok(Some(value)),
+            Err(err) => println!("  {} {}", theme::symbol("⚠️"), err),
+        }
+    }
+}
+
+/// `add <title...> after <tid>...` の末尾の依存指定を切り出す。
+/// `after` 以降がすべて `#` 付きの ID のときだけ依存指定とみなす (`昼 after 会議` や `after cafe` のようなタイトルはそのまま)。
+/// ID が見つからない場合は、指定の誤りとしてエラーにする
+fn split_after_clause<'a>(session: &session::Session, args: &'a [&'a str]) -> anyhow::Result<(&'a [&'a str], Vec<TaskID>)> {
+    let Some(pos) = args.iter().rposition(|arg| *arg == "after") else {
+        return Ok((args, vec![]));
+    };
+    let (title, keys) = (&args[..pos], &args[pos + 1..]);
+    if keys.is_empty() || !keys.iter().all(|key| key.starts_with('#')) {
+        return Ok((args, vec![]));
+    }
+    let dependencies = keys
+        .iter()
+        .map(|key| find_task(session, key).ok_or_else(|| anyhow!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), key)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok((title, dependencies))
+}
+
+/// 依存タスクなどを待つと最短でも期限に間に合わない場合、schedule を待たずに知らせる
+fn warn_unreachable_deadline(session: &session::Session, now: NaiveDateTime, task_id: &TaskID) {
+    let Some((finish, deadline)) = session.unreachable_deadline(now, task_id) else {
+        return;
+    };
+    println!(
+        "{} 期限 {} に間に合いません: 依存タスク・ブロックの解除を待って残りを作業すると、最短でも {} の完了見込みです。期限か依存関係を見直してください。",
+        theme::symbol("⚠️"),
+        session.format_datetime(deadline),
+        session.format_datetime(finish)
+    );
+}
+/// 見積のないタスクの期限が近い場合、見積を促す。
+/// 見積がないと既定の残り時間 (5分) でスケジュールされるため、実態より楽観的な計画になりやすい。
+fn nudge_estimate_if_due_soon(session: &session::Session, now: NaiveDateTime, task_id: &TaskID) {
+    let task = session.tasks.get(task_id).unwrap();
+    if task.estimate().is_some() || !task.is_ready() {
+        return;
+    }
+    let Ok(Some(deadline)) = task.resolve_deadline(&session.calendar, session.deadline_time_of(task_id)) else {
+        return;
+    };
+    if deadline - now <= Duration::days(ESTIMATE_NUDGE_DAYS) {
+        println!(
+            "{} 期限まで{}日以内ですが見積がありません (既定の{}で計画中)。`est {} <time>` で見積を入力してください。",
+            theme::symbol("💡"),
+            ESTIMATE_NUDGE_DAYS,
+            session.format_duration(task.remaining()),
+            session.short_id(&task.id)
+        );
+    }
+}
+
+fn println_task(session: &session::Session, now: NaiveDateTime, task: &Task, colors: &BTreeMap<&str, CategoryColor>) {
+    println!("    {} {}{}", session.short_id(&task.id), task.title, format_category(colors, task));
+    println_task_details(session, now, task);
+    println!();
+}
+
+/// `#id 状態 タイトル  [期限] [進捗] [残り]` の 1 行で表示する
+fn println_task_compact(session: &session::Session, task: &Task, colors: &BTreeMap<&str, CategoryColor>) {
+    let status = task_status_symbol(task);
+    let mut fields = vec![];
+    match task.resolve_deadline(&session.calendar, session.deadline_time_of(&task.id)) {
+        Ok(Some(deadline)) => fields.push(format!("[期限 {}]", session.format_datetime(deadline))),
+        Ok(None) => {}
+        Err(_) => fields.push("[期限 ?]".to_string()),
+    }
+    fields.push(format!("[{}]", task.progress()));
+    if !task.is_completed() {
+        fields.push(format!("[残り {}]", session.format_duration(task.remaining())));
+    }
+    println!("  {} {} {}{}  {}", session.short_id(&task.id), status, task.title, format_category(colors, task), fields.join(" "));
+}
+
+/// タイトルの後ろに付けるカテゴリ (` [Work]`、カテゴリの色付き)。カテゴリがなければ空文字列
+fn format_category(colors: &BTreeMap<&str, CategoryColor>, task: &Task) -> String {
+    match &task.category {
+        Some(category) => format!(" {}", paint_category(colors, category)),
+        None => String::new(),
+    }
+}
+
+/// `[カテゴリ]` をカテゴリの色で表示する
+fn paint_category(colors: &BTreeMap<&str, CategoryColor>, category: &str) -> String {
+    let label = format!("[{}]", category);
+    match colors.get(category) {
+        Some(color) => theme::paint(&label, *color),
+        None => label,
+    }
+}
+
+fn println_task_details(session: &session::Session, now: NaiveDateTime, task: &Task) {
+    let remaining = task.remaining();
+    if let Some(fixed_at) = task.fixed_at {
+        println!("      時刻指定: {} {}", theme::symbol("📌"), session.format_datetime(fixed_at));
+    }
+    if !task.prefer_after.is_empty() {
+        let titles: Vec<_> = task
+            .prefer_after
+            .iter()
+            .filter_map(|id| session.tasks.get(id))
+            .map(|t| format!("{} {}", session.short_id(&t.id), t.title))
+            .collect();
+        println!("      できれば後に: {} {}", theme::symbol("🔀"), titles.join(", "));
+    }
+    if let Some(not_before) = task.not_before {
+        println!("      着手可能日: {} {} 以降", theme::symbol("⏭️"), not_before.date());
+    }
+    if !task.tags.is_empty() {
+        println!("      タグ: {}", format_tags(task));
+    }
+    if let Some(parent) = task.parent.and_then(|id| session.tasks.get(&id)) {
+        println!("      親タスク: {} {}", session.short_id(&parent.id), parent.title);
+    }
+    let children = task.children(&session.tasks).count();
+    if children > 0 {
+        let rolled_up = task.rolled_up_estimate(&session.tasks).map(|e| session.format_duration(e.mean())).unwrap_or_else(|| "なし".to_string());
+        println!(
+            "      子タスク: {}件 (見積合計: {}, 残り合計: {})",
+            children,
+            rolled_up,
+            session.format_duration(task.rolled_up_remaining(&session.tasks))
+        );
+    }
+    if let Some(estimate) = task.estimate() {
+        // 入力したときの表記 (ポイントまたは時間) も併記する
+        let points = match (task.story_points, &task.estimate_input) {
+            (Some(p), _) => format!(" [{}pt]", p),
+            (None, Some(input)) => format!(" [入力: {}]", input),
+            (None, None) => String::new(),
+        };
+        if estimate.stddev().num_minutes() > 0 {
+            println!(
+                "      予想: {}{} (最尤{}, 楽観{}, 最悪{}, σ={})",
+                session.format_duration(estimate.mean()),
+                points,
+                session.format_duration(estimate.most_likely),
+                session.format_duration(estimate.optimistic),
+                session.format_duration(estimate.pessimistic),
+                session.format_duration(estimate.stddev())
+            );
+        } else {
+            println!("      予想: {}{}", session.format_duration(estimate.mean()), points);
+        }
+    }
+    if !task.actual_total.is_zero() {
+        println!(
+            "      実績: {} (進捗{}, 予想残り時間: {})",
+            session.format_duration(task.actual_total),
+            task.progress(),
+            session.format_duration(task.remaining())
+        );
+    } else if task.completed_without_time {
+        println!("      実績: 記録なしで完了 (見積精度の集計から除外)");
+    }
+    let deadline = match &task.deadline {
+        Deadline::None => {
+            println!("      期限: なし");
+            None
+        }
+        Deadline::Unknown => {
+            println!("      期限: 不明");
+            None
+        }
+        Deadline::Exact(naive_date_time) => {
+            print!("      期限: {}(絶対)", session.format_datetime(*naive_date_time));
+            Some(*naive_date_time)
+        }
+        Deadline::Fuzzy(fuzzy_deadline) => {
+            let default_deadline_time = task.deadline_time(session.scheduler.default_deadline_time);
+            let dl = fuzzy_deadline.resolve_with_calendar(&session.calendar, default_deadline_time).unwrap();
+            print!("      期限: {}(相対)", session.format_datetime(dl));
+            Some(dl)
+        }
+    };
+    if let Some(deadline) = deadline {
+        let remaining = deadline.signed_duration_since(now);
+        if remaining.num_minutes() < 0 {
+            println!("({}超過{})", session.format_duration(-remaining), theme::symbol("⚠️"));
+        } else {
+            println!("(あと{})", session.format_duration(remaining));
+        }
+    }
+    if let TaskStatus::Blocked(bs) = task.status() {
+        if !bs.externals.is_empty() {
+            println!("      外部待ち:");
+            for reason in bs.externals.iter() {
+                let may_unblock_at = reason.may_unblock_at.resolve_with_calendar(&session.calendar, session.scheduler.default_deadline_time).unwrap();
+                let follow_up = match reason.remind_every {
+                    Some(every) if reason.needs_follow_up(now) => format!(
+                        " {} 要フォロー ({}ごと, 前回 {})",
+                        theme::symbol("🔔"),
+                        format_remind_interval(every),
+                        session.format_datetime(reason.last_updated)
+                    ),
+                    Some(every) => format!(" ({}ごとにフォロー, 次回 {})", format_remind_interval(every), session.format_datetime(reason.last_updated + every)),
+                    None => String::new(),
+                };
                 println!(
-                    "      実績: {} (進捗{}, 予想残り時間: {})",
-                    format_human_duration(task.actual_total),
-                    task.progress(),
-                    format_human_duration(task.remaining())
+                    "        {:?}: {}{}",
+                    reason.note,
+                    may_unblock_at.map(|d| session.format_datetime(d) + "まで").unwrap_or_else(|| "不明".to_string()),
+                    follow_up
                 );
             }
-            let deadline = match &task.deadline {
-                Deadline::None => {
-                    println!("      期限: なし");
-                    None
-                }
-                Deadline::Unknown => {
-                    println!("      期限: 不明");
-                    None
-                }
-                Deadline::Exact(naive_date_time) => {
-                    print!("      期限: {}(絶対)", naive_date_time);
-                    Some(*naive_date_time)
+        }
+        if !bs.tasks.is_empty() {
+            println!("      別タスク待ち:");
+            for task_id in bs.tasks.iter() {
+                println!("        {}: {}", session.short_id(task_id), session.tasks.get(task_id).unwrap().title);
+            }
+        }
+    }
+}
+
+/// 初回起動 (タスクファイルがない) 時に表示する、基本的な使い方の流れ
+pub fn print_onboarding() {
+    println!("{} はじめまして! まずは次の流れで使ってみてください:", theme::symbol("👋"));
+    println!("  1. add <タイトル>        タスクを追加         (例: add 会議資料まとめる)");
+    println!("  2. est <tid> <時間>      残り時間を見積もる   (例: est 1d48 2h)");
+    println!("  3. schedule              空き時間に割り当てる");
+    println!("  4. todo                  今日やることを確認する");
+    println!("  <tid> は list で表示される ID の先頭数文字です。コマンドの一覧は help で確認できます。");
+}
+
+/// 引数から `--json` を取り除き、指定されていたかを返す
+fn take_json_flag(args: Vec<&str>) -> (bool, Vec<&str>) {
+    let json = args.contains(&"--json");
+    (json, args.into_iter().filter(|arg| *arg != "--json").collect())
+}
+
+fn print_json(value: &serde_json::Value) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// スクリプトから扱いやすい形のタスク情報 (時間は分単位)
+fn task_json(session: &session::Session, task: &Task) -> serde_json::Value {
+    let status = match task.status() {
+        TaskStatus::Ready => "ready",
+        TaskStatus::Blocked(_) => "blocked",
+        TaskStatus::Completed(_) => "completed",
+        TaskStatus::Dropped => "dropped",
+    };
+    let deadline = task
+        .deadline
+        .resolve_with_calendar(&session.calendar, task.deadline_time(session.scheduler.default_deadline_time))
+        .ok()
+        .flatten();
+    serde_json::json!({
+        "id": task.id.hex(),
+        "short_id": session.short_id(&task.id).to_string(),
+        "title": task.title,
+        "status": status,
+        "completed_at": match task.status() {
+            TaskStatus::Completed(at) => Some(*at),
+            _ => None,
+        },
+        "deadline": deadline,
+        "estimate_minutes": task.estimate().map(|e| e.mean().num_minutes()),
+        "actual_minutes": task.actual_total.num_minutes(),
+        "completed_without_time": task.completed_without_time,
+        "remaining_minutes": task.remaining().num_minutes(),
+        "progress": u8::from(task.progress()),
+        "tags": task.tags,
+        "category": task.category,
+        "parent": task.parent.map(|id| id.hex()),
+        "fixed_at": task.fixed_at,
+        "not_before": task.not_before,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListSection {
+    Ready,
+    Blocked,
+    Completed,
+}
+
+#[derive(Debug, Default)]
+struct ListOptions {
+    /// 各セクションの最大表示件数
+    limit: Option<usize>,
+    /// 表示するセクション (未指定なら全て)
+    status: Option<ListSection>,
+    /// 表示の代わりに JSON を出力する
+    json: bool,
+    /// 1 タスク 1 行で表示する
+    compact: bool,
+    /// 各セクションをカテゴリごとにまとめて表示する
+    by_category: bool,
+}
+impl ListOptions {
+    fn parse(args: &[&str]) -> anyhow::Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match *arg {
+                "--limit" | "-n" => {
+                    let value = args.next().ok_or_else(|| anyhow!("--limit の後に件数を指定してください"))?;
+                    options.limit = Some(value.parse().map_err(|_| anyhow!("件数が不正です: {}", value))?);
                 }
-                Deadline::Fuzzy(fuzzy_deadline) => {
-                    let default_deadline_time = session.scheduler.working_time.0;
-                    let dl = fuzzy_deadline.resolve_with_calendar(&session.calendar, default_deadline_time).unwrap();
-                    print!("      期限: {}(相対)", dl);
-                    Some(dl)
+                "--status" | "-s" => {
+                    let value = args.next().ok_or_else(|| anyhow!("--status の後に ready|blocked|completed を指定してください"))?;
+                    options.status = Some(match *value {
+                        "ready" => ListSection::Ready,
+                        "blocked" => ListSection::Blocked,
+                        "completed" | "done" => ListSection::Completed,
+                        _ => bail!("不明なステータス: {} (ready|blocked|completed)", value),
+                    });
                 }
-            };
-            if let Some(deadline) = deadline {
-                let remaining = deadline.signed_duration_since(chrono::Local::now().naive_local());
-                if remaining.num_minutes() < 0 {
-                    println!("({}超過⚠️)", format_human_duration(-remaining));
+                "--json" => options.json = true,
+                "--compact" | "-c" => options.compact = true,
+                "--by-category" | "-g" => options.by_category = true,
+                _ => bail!("Usage: list [--limit N] [--status ready|blocked|completed] [--compact] [--by-category] [--json]"),
+            }
+        }
+        Ok(options)
+    }
+    fn shows(&self, section: ListSection) -> bool {
+        self.status.is_none_or(|s| s == section)
+    }
+}
+
+fn handle_clone(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let id_key = args.first().unwrap_or(&"");
+    if id_key.is_empty() {
+        bail!("<task-id> を指定してください");
+    }
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let title = args[1..].join(" ");
+    let title = if title.is_empty() { None } else { Some(title) };
+    let copy_id = session.clone_task(&task_id, title, now).id;
+    let task = &session.tasks[&copy_id];
+    println!("{} 複製: {} - {}", theme::symbol("✅"), session.short_id(&task.id), task.title);
+    Ok(())
+}
+
+fn handle_list(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let options = ListOptions::parse(&args)?;
+    if options.json {
+        let sections = [(ListSection::Ready, "ready"), (ListSection::Blocked, "blocked"), (ListSection::Completed, "completed")];
+        let mut value = serde_json::Map::new();
+        for (section, key) in sections.into_iter().filter(|(section, _)| options.shows(*section)) {
+            let tasks: Vec<_> = session
+                .iter_tasks()
+                .filter(|t| match section {
+                    ListSection::Ready => t.is_ready(),
+                    ListSection::Blocked => t.is_blocked(),
+                    ListSection::Completed => t.is_completed(),
+                })
+                .take(options.limit.unwrap_or(usize::MAX))
+                .map(|t| task_json(session, t))
+                .collect();
+            value.insert(key.to_string(), tasks.into());
+        }
+        return print_json(&value.into());
+    }
+    if session.iter_tasks().next().is_none() {
+        println!("(タスクなし) add <タイトル> でタスクを追加できます。");
+    } else {
+        // カテゴリの色はタスク全体から決まるので、表示の前に 1 度だけ求める
+        let colors = session.category_colors();
+        let print_tasks = |tasks: &[&Task]| {
+            let limit = options.limit.unwrap_or(tasks.len());
+            for task in tasks.iter().take(limit) {
+                if options.compact {
+                    println_task_compact(session, task, &colors);
                 } else {
-                    println!("(あと{})", format_human_duration(remaining));
+                    println_task(session, now, task, &colors);
                 }
             }
-            if let TaskStatus::Blocked(bs) = task.status() {
-                if !bs.externals.is_empty() {
-                    println!("      外部待ち:");
-                    for reason in bs.externals.iter() {
-                        let may_unblock_at = reason.may_unblock_at.resolve_with_calendar(&session.calendar, session.scheduler.working_time.0).unwrap();
-                        println!("        {:?}: {}", reason.note, may_unblock_at.map(|d| d.to_string() + "まで").unwrap_or_else(|| "不明".to_string()));
-                    }
-                }
-                if !bs.tasks.is_empty() {
-                    println!("      別タスク待ち:");
-                    for task_id in bs.tasks.iter() {
-                        println!("        {}: {}", task_id, session.tasks.get(task_id).unwrap().title);
-                    }
+            if tasks.len() > limit {
+                println!("  (他{}件)", tasks.len() - limit);
+            }
+        };
+        let print_section = |tasks: Vec<&Task>, empty_message: &str| {
+            if tasks.is_empty() {
+                println!("  {}", empty_message);
+                return;
+            }
+            if !options.by_category {
+                print_tasks(&tasks);
+                return;
+            }
+            // カテゴリ名順、カテゴリなしは最後
+            let mut groups: BTreeMap<(bool, Option<&str>), Vec<&Task>> = BTreeMap::new();
+            for task in tasks {
+                groups.entry((task.category.is_none(), task.category.as_deref())).or_default().push(task);
+            }
+            for ((_, category), tasks) in groups {
+                match category {
+                    Some(category) => println!("  {} ({}件)", paint_category(&colors, category), tasks.len()),
+                    None => println!("  (カテゴリなし) ({}件)", tasks.len()),
                 }
+                print_tasks(&tasks);
             }
-            println!();
         };
 
         // Ready
-        println!("📝 進行中のタスク:");
-        for task in session.iter_tasks().filter(|t| t.is_ready()) {
-            println_task(task);
+        if options.shows(ListSection::Ready) {
+            println!("{} 進行中のタスク:", theme::symbol("📝"));
+            print_section(session.iter_tasks().filter(|t| t.is_ready()).collect(), "(進行中のタスクはありません)");
         }
         // Blocked
-        println!("\n⌛ ブロッキング中のタスク:");
-        let blocked_tasks = session.iter_tasks().filter(|t| t.is_blocked()).collect::<Vec<_>>();
-        if blocked_tasks.is_empty() {
-            println!("  (ブロッキング中のタスクはありません)");
-        } else {
-            for task in blocked_tasks.iter() {
-                println_task(task);
-            }
+        if options.shows(ListSection::Blocked) {
+            println!("\n{} ブロッキング中のタスク:", theme::symbol("⌛"));
+            print_section(session.iter_tasks().filter(|t| t.is_blocked()).collect(), "(ブロッキング中のタスクはありません)");
         }
         // Completed
-        println!("\n✅ 完了したタスク:");
-        for task in session.iter_tasks().filter(|t| t.is_completed()) {
-            println_task(task);
+        if options.shows(ListSection::Completed) {
+            println!("\n{} 完了したタスク:", theme::symbol("✅"));
+            print_section(session.iter_tasks().filter(|t| t.is_completed()).collect(), "(完了したタスクはありません)");
+        }
+    }
+    Ok(())
+}
+/// 計画との差を「予定より◯◯先行/遅延」の形で表す (5分未満の差は予定どおりとみなす)
+fn format_plan_variance(session: &session::Session, variance: Duration) -> String {
+    if variance.abs() < Duration::minutes(5) {
+        format!("{} 予定どおり", theme::symbol("🎯"))
+    } else if variance > Duration::zero() {
+        format!("{} 予定より{}先行", theme::symbol("📈"), session.format_duration(variance))
+    } else {
+        format!("{} 予定より{}遅延", theme::symbol("📉"), session.format_duration(-variance))
+    }
+}
+
+fn handle_show(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let (json, args) = take_json_flag(args);
+    let id_key = args.first().unwrap_or(&"");
+    if id_key.is_empty() {
+        bail!("<task-id> を指定してください");
+    }
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let task = session.tasks.get(&task_id).unwrap();
+    if json {
+        let mut value = task_json(session, task);
+        value["created_at"] = serde_json::json!(task.created_at);
+        value["notes"] = task.notes.iter().map(|(at, text)| serde_json::json!({ "at": at, "text": text })).collect();
+        value["first_worked"] = serde_json::json!(session.log.first_worked(task_id));
+        value["last_worked"] = serde_json::json!(session.log.last_worked(task_id));
+        value["plan_variance_minutes"] = serde_json::json!(session.plan_variance(&task_id, now).map(|v| v.num_minutes()));
+        return print_json(&value);
+    }
+    println!(
+        "{} {} {}{}",
+        task_status_symbol(task),
+        session.short_id(&task.id),
+        task.title,
+        format_category(&session.category_colors(), task)
+    );
+    println!("      作成: {}", session.format_datetime(task.created_at));
+    if !task.notes.is_empty() {
+        println!("      メモ:");
+        for (at, text) in &task.notes {
+            println!("        {} {}", session.format_datetime(*at), text);
         }
     }
+    println_task_details(session, now, task);
+    if let Some(estimate) = task.estimate() {
+        println!("      見積コマンド: est {} {}", session.short_id(&task.id), estimate.to_command_args());
+    }
+    // 作業時間 (実績) とは別に、着手から最後の作業までのカレンダー上の期間
+    if let (Some(first), Some(last)) = (session.log.first_worked(task_id), session.log.last_worked(task_id)) {
+        let span_days = (last.date() - first.date()).num_days() + 1;
+        println!(
+            "      作業期間: {} 〜 {} ({}日間のうち{}日作業)",
+            session.format_datetime(first),
+            session.format_datetime(last),
+            span_days,
+            session.log.worked_days(task_id)
+        );
+    }
+    println!("      残り時間の根拠: {}", task.remaining_explanation(session.calendar.duration_units()));
+    if let Some(variance) = session.plan_variance(&task_id, now) {
+        println!("      {}", format_plan_variance(session, variance));
+    }
     Ok(())
 }
 fn handle_start(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
@@ -296,7 +872,11 @@ fn handle_start(session: &mut session::Session, now: NaiveDateTime, args: Vec<&s
         bail!("<task-id> を指定してください");
     }
     if let Some((tid, _)) = session.active_task {
-        println!("ℹ️ 既にタスク{}が開始されています。いずれかのコマンドで中断/完了してください: ", tid);
+        println!(
+            "{} 既にタスク{}が開始されています。いずれかのコマンドで中断/完了してください: ",
+            theme::symbol("ℹ️"),
+            session.short_id(&tid)
+        );
         println!("  stop : 現在時刻で中断 (日付またいで5h以上になる場合はエラー)");
         println!("  done  : 現在時刻で完了");
         println!("  stop in <duration> : 作業時間のみ記録して中断");
@@ -307,42 +887,178 @@ fn handle_start(session: &mut session::Session, now: NaiveDateTime, args: Vec<&s
         println!("  done immediately : なにも記録せず即完了");
         return Ok(());
     }
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
-    let (task, allocated) = session.start_task_at(&task_id, now);
-    println!("🔥タスク{}を開始しました。", task.id);
-    println!("  割り当て時間: {}", format_human_duration(allocated));
-    println!("  予想完了時間: {}", now + allocated);
+    let (_, allocated) = session.start_task_at(&task_id, now)?;
+    println!("{}タスク{}を開始しました。", theme::symbol("🔥"), session.short_id(&task_id));
+    println!("  割り当て時間: {}", session.format_duration(allocated));
+    println!("  予想完了時間: {}", session.format_datetime(now + allocated));
     Ok(())
 }
+fn handle_focus(session: &mut session::Session, now: NaiveDateTime) -> anyhow::Result<()> {
+    let Some((task_id, started_at)) = session.active_task else {
+        bail!("開始中のタスクがありません。start <tid> で開始してください");
+    };
+    let allocated = session.tick_allocation_at(&task_id, started_at);
+    let stop_at = started_at + allocated;
+    // @timestamp で now が上書きされている場合もずれを保ったまま時計を進める
+    let offset = now - chrono::Local::now().naive_local();
+    let stdin = std::io::stdin();
+    loop {
+        let current = chrono::Local::now().naive_local() + offset;
+        let task = session.tasks.get(&task_id).unwrap();
+        print!("\x1B[2J\x1B[H");
+        println!("{} {} - {}", theme::symbol("🎯"), session.short_id(&task.id), task.title);
+        println!();
+        println!("  予想残り時間: {}", session.format_duration(task.remaining() - (current - started_at)));
+        if current < stop_at {
+            println!("  区切りまで: {} ({}まで)", session.format_duration(stop_at - current), stop_at.format("%H:%M"));
+        } else {
+            println!("  区切りを{}超過しています {}", session.format_duration(current - stop_at), theme::symbol("⏰"));
+        }
+        println!();
+        println!("  stop / done [at HH:MM | in <duration> | immediately] : 中断/完了して終了");
+        println!("  pause : タスクを続けたまま focus を抜ける");
+        println!("  (Enter で表示を更新)");
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let mut parts = line.split_whitespace();
+        let cmd = parts.next().unwrap_or("");
+        let args = parts.collect::<Vec<_>>();
+        let current = chrono::Local::now().naive_local() + offset;
+        let result = match cmd {
+            "" => continue,
+            "pause" => return Ok(()),
+            "sto" | "stop" => handle_stop(session, current, args),
+            "dn" | "done" => handle_done(session, current, args),
+            _ => {
+                println!("{} focus 中は stop / done / pause のみ使えます", theme::symbol("⚠️"));
+                continue;
+            }
+        };
+        match result {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                eprintln!("{} Error: {}", theme::symbol("❌"), err);
+                println!("(Enter で続行)");
+                stdin.read_line(&mut String::new())?;
+            }
+        }
+    }
+}
 fn handle_done(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
-    let Some(stop_kind) = parse_stop_kind(&args, now) else {
+    let Some(mut stop_kind) = parse_stop_kind(&args, now) else {
         bail!("Usage: done <task-id> (at HH:MM | in <duration> | immediately)");
     };
-    let task = session.stop_current_task(stop_kind, true)?;
-    println!("✅ 完了: {} - {}", task.id, task.title);
+    // 見積のないタスクを記録なしで完了するときは、かかった時間を聞いて実績に残す
+    if let (StopKind::Immediately(_), Some((task_id, _))) = (&stop_kind, session.active_task)
+        && needs_actual_duration(&session.tasks[&task_id])
+        && let Some(duration) = ask_actual_duration(session)?
+    {
+        stop_kind = StopKind::EndsIn(duration);
+    }
+    let logged = logged_interval(session, &stop_kind);
+    let task = session.stop_current_task(stop_kind, true)?.clone();
+    println!("{} 完了: {} - {}", theme::symbol("✅"), session.short_id(&task.id), task.title);
+    warn_if_outside_working_hours(session, logged);
     Ok(())
 }
 fn handle_stop(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
     let Some(stop_kind) = parse_stop_kind(&args, now) else {
         bail!("Usage: stop (at HH:MM | in <duration> | immediately)");
     };
-    let task = session.stop_current_task(stop_kind, false)?;
-    println!("⏸️ 中断: {} - {}", task.id, task.title);
+    let logged = logged_interval(session, &stop_kind);
+    let task = session.stop_current_task(stop_kind, false)?.clone();
+    println!("{} 中断: {} - {}", theme::symbol("⏸️"), session.short_id(&task.id), task.title);
+    warn_if_outside_working_hours(session, logged);
     Ok(())
 }
+/// 作業中のタスクを `stop_kind` で止めたときに作業記録に残る区間
+fn logged_interval(session: &session::Session, stop_kind: &StopKind) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let (_, started_at) = session.active_task?;
+    match stop_kind {
+        StopKind::Immediately(_) => None,
+        StopKind::EndsAt(end_time) => Some((started_at, *end_time)),
+        StopKind::EndsIn(duration) => Some((started_at, started_at + *duration)),
+    }
+}
+/// 記録した区間が勤務時間 (カレンダーの空き時間) からはみ出していれば警告する。記録自体は残す
+fn warn_if_outside_working_hours(session: &session::Session, logged: Option<(NaiveDateTime, NaiveDateTime)>) {
+    let Some((begin, end)) = logged else {
+        return;
+    };
+    if begin < end && !session.calendar.is_available_between(begin, end) {
+        println!("{} 勤務時間外の記録です ({} - {})", theme::symbol("⚠️"), session.format_datetime(begin), session.format_datetime(end));
+    }
+}
 fn handle_complete(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
     let mut args = args.iter();
     let Some(id_key) = args.next() else {
         bail!("<task-id> を指定してください");
     };
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let duration = match args.next() {
+        Some(arg) => match session.parse_duration(arg) {
+            Some(duration) if duration > Duration::zero() => Some(duration),
+            _ => bail!("Usage: comp <task-id> [<duration>] (例: comp {} 45m)", id_key),
+        },
+        None if needs_actual_duration(&session.tasks[&task_id]) => ask_actual_duration(session)?,
+        None => None,
+    };
+    session.complete_task(&task_id, now, duration);
+    let task = &session.tasks[&task_id];
+    println!("{} 完了: {} - {}", theme::symbol("✅"), session.short_id(&task.id), task.title);
+    if task.completed_without_time && task.estimate().is_none() {
+        println!(
+            "{} 見積も実績もないため、見積の精度の集計には使われません (record <tid> <duration> で後から実績を残せます)",
+            theme::symbol("💡")
+        );
+    }
+    Ok(())
+}
+/// 見積も実績もない (完了しても見積の振り返りに使えない) タスクか
+fn needs_actual_duration(task: &Task) -> bool {
+    task.estimate().is_none() && task.actual_total.is_zero()
+}
+/// 完了したタスクにかかった時間を尋ねる。端末から実行していなければ尋ねない。Enter だけなら記録しない
+fn ask_actual_duration(session: &session::Session) -> anyhow::Result<Option<Duration>> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(None);
+    }
+    loop {
+        print!("{} 実際にかかった時間は? (30m / 1h など、Enter で記録しない): ", theme::symbol("⏱️"));
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        match session.parse_duration(line) {
+            Some(duration) if duration > Duration::zero() => return Ok(Some(duration)),
+            _ => println!("{} 時間を解釈できません: {}", theme::symbol("⚠️"), line),
+        }
+    }
+}
+fn handle_reopen(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let Some(id_key) = args.first() else {
+        bail!("<task-id> を指定してください");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
-    let duration = args.next().and_then(|arg| parse_human_duration(arg));
-    let task = session.complete_task(&task_id, now, duration);
-    println!("✅ 完了: {} - {}", task.id, task.title);
+    session.reopen_task(&task_id)?;
+    let task = &session.tasks[&task_id];
+    println!("{} 再開: {} - {}", theme::symbol("↩️"), session.short_id(&task.id), task.title);
     Ok(())
 }
 fn handle_drop(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
@@ -350,11 +1066,11 @@ fn handle_drop(session: &mut session::Session, args: Vec<&str>) -> anyhow::Resul
     if id_key.is_empty() {
         bail!("ID is required for drop command");
     }
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
     let task_title = session.drop_task(&task_id);
-    println!("❌ 削除: {} - {}", task_id, task_title);
+    println!("{} 削除: {} - {}", theme::symbol("❌"), session.short_id(&task_id), task_title);
     Ok(())
 }
 fn handle_deadline(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
@@ -362,124 +1078,1334 @@ fn handle_deadline(session: &mut session::Session, now: NaiveDateTime, args: Vec
     if id_key.is_empty() {
         bail!("<task-id> を指定してください");
     }
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
-    let default_deadline_time = chrono::NaiveTime::from_hms_opt(17, 0, 0).unwrap();
-    let deadline = parse_deadline(now, default_deadline_time, args.into_iter().skip(1))?;
-    let task = session.set_deadline(&task_id, deadline);
-    println!("⌛ 期限: {} - {}", task.id, task.title);
+    let default_deadline_time = session.deadline_time_of(&task_id);
+    let deadline = match args.get(1).copied() {
+        Some("after") => parse_deadline_after(session, &args[2..])?,
+        _ => parse_deadline(now, &session.calendar, default_deadline_time, args.into_iter().skip(1))?,
+    };
+    session.set_deadline(&task_id, deadline);
+    let task = &session.tasks[&task_id];
+    println!("{} 期限: {} - {}", theme::symbol("⌛"), session.short_id(&task.id), task.title);
     println!("  期限: {:#?}", task.deadline);
+    warn_unreachable_deadline(session, now, &task_id);
+    nudge_estimate_if_due_soon(session, now, &task_id);
     Ok(())
 }
 
-fn handle_estimate(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
-    let task_id = if let Some((tid, _)) = session.active_task {
-        tid
-    } else {
-        let id_key = args.first().unwrap_or(&"");
-        if id_key.is_empty() {
-            bail!("<task-id> を指定してください");
-        }
-        let Some(task_id) = session.find_task_by_prefix(id_key) else {
-            bail!("⚠️タスク{}が見つかりません。", id_key);
-        };
-        task_id
+/// `after <task-id> <n>d` を、参照先タスクの期限から n 稼働日後の固定期限として解釈する。
+/// 参照先の期限を後から変更しても追従しない。
+fn parse_deadline_after(session: &session::Session, args: &[&str]) -> anyhow::Result<Deadline> {
+    let usage = "Usage: deadline <task-id> after <task-id> <n>d";
+    let (Some(ref_key), Some(offset)) = (args.first(), args.get(1)) else {
+        bail!(usage);
     };
-    let current_remaining = Estimate::new(session.tasks.get(&task_id).unwrap().remaining());
-    let times: Vec<_> = args.iter().filter_map(|arg| parse_human_duration_with_sign(arg)).collect();
-    let estimate = match (times.as_slice(), current_remaining) {
-        ([(None, m)], _) => Estimate::new(*m),
-        ([(None, m), (None, o), (None, p)], _) => Estimate::from_mop(*m, *o, *p).map_err(|_| anyhow!("m o p で指定してください"))?,
-        ([(Some(sm), m)], curr) => curr + Estimate::new(*m * *sm),
-        ([(Some(sm), m), (Some(so), o), (Some(sp), p)], curr) => curr + Estimate::from_mop(*m * *sm, *o * *so, *p * *sp).map_err(|_| anyhow!("m o p で指定してください"))?,
-        _ => bail!("<most-likely> (<optimistic> <pessimistic>) の形式で指定してください"),
+    let Some(days) = offset.strip_suffix("bd").or_else(|| offset.strip_suffix('d')).and_then(|n| n.parse::<u16>().ok()) else {
+        bail!(usage);
     };
-    let task = session.estimate_task(&task_id, estimate.clone())?;
-    println!("⌛ 予測: {} - {}", task.id, task.title);
-    println!("  予測残り時間: {}", format_human_duration(estimate.mean()));
-    Ok(())
+    let Some(ref_id) = find_task(session, ref_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), ref_key);
+    };
+    let ref_task = session.tasks.get(&ref_id).unwrap();
+    let ref_deadline = ref_task
+        .resolve_deadline(&session.calendar, session.deadline_time_of(&ref_id))
+        .map_err(anyhow::Error::msg)?
+        .ok_or_else(|| anyhow!("タスク{}には期限がありません", session.short_id(&ref_task.id)))?;
+    let Some(date) = session.calendar.add_business_days(ref_deadline.date(), days) else {
+        bail!("{}の{}稼働日後がカレンダーの範囲外です", ref_deadline.date(), days);
+    };
+    Ok(Deadline::Exact(date.and_time(ref_deadline.time())))
 }
-fn handle_record(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
-    let mut args = args.iter();
-    let Some(id_key) = args.next() else {
+
+fn handle_work_tick(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let id_key = args.first().unwrap_or(&"");
+    if id_key.is_empty() {
         bail!("<task-id> を指定してください");
+    }
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
-    let Some(duration) = args.next().and_then(|arg| parse_human_duration(arg)) else {
-        bail!("Usage: record <task-id> <duration>");
-    };
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let work_tick = match args.get(1).copied() {
+        Some("default") => None,
+        Some(arg) => match session.parse_duration(arg) {
+            Some(tick) if tick > Duration::zero() => Some(tick),
+            _ => bail!("割り当て単位は正の時間で指定してください (例: 5m, 1h)"),
+        },
+        None => bail!("Usage: tick <task-id> (<duration> | default)"),
     };
-    let task = session.record_task(&task_id, duration);
-    println!("📝 記録: {} - {}", task.id, task.title);
+    let default_tick = session.scheduler.work_tick;
+    session.set_work_tick(&task_id, work_tick);
+    let task = &session.tasks[&task_id];
+    println!("{} 割り当て単位: {} - {}", theme::symbol("⏱️"), session.short_id(&task.id), task.title);
+    match task.work_tick {
+        Some(tick) => println!("  割り当て単位: {}", session.format_duration(tick)),
+        None => println!("  割り当て単位: {} (既定)", session.format_duration(default_tick)),
+    }
     Ok(())
 }
-fn handle_todo(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
-    let today = now.date();
-    let mut tasks = session.iter_tasks();
 
-    let today_slots = session.slots.get(&today);
-    if today_slots.is_empty() {
-        println!("✅ 今日のタスクはありません。");
-        return Ok(());
+fn handle_week(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let date = match args.first() {
+        Some(tok) => parse_date(now, tok).map_err(anyhow::Error::msg)?,
+        None => now.date(),
     };
+    let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+    let friday = monday + Duration::days(4);
+    println!("{} {} - {} の週:", theme::symbol("📆"), monday.format("%m/%d"), friday.format("%m/%d"));
+    const WEEKDAYS: [&str; 5] = ["月", "火", "水", "木", "金"];
+    for (i, day) in monday.iter_days().take(5).enumerate() {
+        let marker = if day == now.date() { theme::symbol("👉") } else { "  " };
+        if session.calendar.working_time(day).is_none() {
+            println!("{}{} {} (休み)", marker, WEEKDAYS[i], day.format("%m/%d"));
+            continue;
+        }
+        let mut slots: Vec<_> = session.slots.get(&day).iter().collect();
+        slots.sort_by_key(|(_, allocated)| std::cmp::Reverse(**allocated));
+        let total: Duration = slots.iter().map(|(_, allocated)| **allocated).sum();
+        println!("{}{} {} 合計 {}", marker, WEEKDAYS[i], day.format("%m/%d"), session.format_duration(total));
+        for (task_id, allocated) in slots {
+            let title = session.tasks.get(task_id).map_or("(不明なタスク)", |t| t.title.as_str());
+            println!("      {} {} [{}]", session.short_id(task_id), title, session.format_duration(*allocated));
+        }
+    }
+    Ok(())
+}
 
-    let mut todo_all = today_slots
-        .iter()
-        .map(|(task_id, allocated)| {
-            let Some(task) = tasks.find(|t| t.id == *task_id) else {
-                panic!("Task not found");
-            };
-            (task.clone(), allocated)
-        })
-        .collect::<Vec<_>>();
-    if todo_all.is_empty() {
-        println!("✅ 今日のタスクはありません。");
+fn handle_timeline(session: &mut session::Session, now: NaiveDateTime) -> anyhow::Result<()> {
+    let timeline = session.timeline(now);
+    if timeline.is_empty() {
+        println!("(未完了のタスクはありません)");
         return Ok(());
     }
+    // 余裕の少ない (優先度の高くなりやすい) 順
+    let mut rows: Vec<_> = timeline.iter().collect();
+    rows.sort_by(|(_, a), (_, b)| a.slack_days.total_cmp(&b.slack_days));
+    println!("{} 着手可能時刻・最遅開始時刻・余裕:", theme::symbol("🧭"));
+    for (task_id, timing) in rows {
+        let task = &session.tasks[task_id];
+        println!(
+            "  {} {} {}\n      最早 {} / 最遅 {} / 余裕 {:.1}日{}",
+            task_status_symbol(task),
+            session.short_id(&task.id),
+            task.title,
+            session.format_datetime(timing.earliest),
+            session.format_datetime(timing.latest),
+            timing.slack_days,
+            if timing.slack_days < session.config.min_slack_days {
+                format!(" {}", theme::symbol("🔥"))
+            } else {
+                String::new()
+            }
+        );
+    }
+    Ok(())
+}
+fn handle_cal(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let date = match args.first() {
+        Some(tok) => parse_date(now, tok).map_err(anyhow::Error::msg)?,
+        None => now.date(),
+    };
+    if session.calendar.working_time(date).is_none() {
+        println!("{} {} は稼働日ではありません。", theme::symbol("🏖️"), date);
+        return Ok(());
+    }
+    // 今日なら現在時刻以降の空きだけを数える
+    let from = if date == now.date() { now } else { date.and_time(NaiveTime::MIN) };
+    println!("{} {} の予定:", theme::symbol("🗓️"), date);
+    let mut available = Duration::zero();
+    for window in session.calendar.time_windows(from).take_while(|w| w.date == date) {
+        if window.available() {
+            available += window.duration();
+            println!(
+                "    {}-{} 空き ({})",
+                window.start.format("%H:%M"),
+                window.end.format("%H:%M"),
+                session.format_duration(window.duration())
+            );
+        } else {
+            println!("    {}-{} {} {}", window.start.format("%H:%M"), window.end.format("%H:%M"), theme::symbol("📅"), window.note());
+        }
+    }
+    let allocated: Duration = session.slots.get(&date).values().copied().sum();
+    let label = if date == now.date() { "本日".to_string() } else { date.format("%m/%d").to_string() };
+    println!(
+        "{}の空き: {} (空き時間 {} - 割当済み {})",
+        label,
+        session.format_duration((available - allocated).max(Duration::zero())),
+        session.format_duration(available),
+        session.format_duration(allocated)
+    );
+    Ok(())
+}
+fn handle_import(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    match args.as_slice() {
+        ["calendar", path] => import_calendar(session, path),
+        ["worklog", path] => import_worklog(session, path),
+        _ => bail!("Usage: import calendar <path.ics> | import worklog <path.csv>"),
+    }
+}
+/// 他のツールの作業記録 (CSV) を取り込む。タイトルの一致するタスクがなければ作り、作ったタスクを知らせる
+fn import_worklog(session: &mut session::Session, path: &str) -> anyhow::Result<()> {
+    let rows = store::load_worklog_csv(path)?;
+    let (mut imported, mut duplicates, mut total) = (0, 0, Duration::zero());
+    let mut created: Vec<TaskID> = Vec::new();
+    for row in rows {
+        match session.import_log_item(row.date, row.begin_at, row.duration, &row.title) {
+            session::LogImport::Duplicate(_) => duplicates += 1,
+            result => {
+                imported += 1;
+                total += row.duration;
+                if let session::LogImport::Created(task_id) = result {
+                    created.push(task_id);
+                }
+            }
+        }
+    }
+    println!(
+        "{} {}件の作業記録 (計 {}) を取り込みました (重複 {}件)",
+        theme::symbol("📥"),
+        imported,
+        session.format_duration(total),
+        duplicates
+    );
+    if !created.is_empty() {
+        println!("  タイトルの一致するタスクがなかったため、{}件のタスクを作りました:", created.len());
+        for task_id in &created {
+            let task = &session.tasks[task_id];
+            println!("    {} {} (実績 {})", session.short_id(&task.id), task.title, session.format_duration(task.actual_total));
+        }
+        println!(
+            "  {} 終わったタスクは complete で完了に、既存のタスクと同じものならタイトルを揃えて取り込み直してください (log rm で記録を削除できます)",
+            theme::symbol("💡")
+        );
+    }
+    Ok(())
+}
+/// .ics の予定をカレンダーの予定として取り込む (schedule/<date>.yaml には終了時に保存する)
+fn import_calendar(session: &mut session::Session, path: &str) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("{} を読み込めませんでした", path))?;
+    let (events, mut skipped) = ics::parse_events(&text).map_err(anyhow::Error::msg)?;
+    let mut by_date: BTreeMap<NaiveDate, Vec<ScheduleItem>> = BTreeMap::new();
+    for event in events {
+        let item = ScheduleItem {
+            start: event.start,
+            duration: event.end - event.start,
+            note: event.summary,
+        };
+        // 休日やカレンダーの範囲外の予定はスケジュールに影響しない
+        if session.calendar.working_time(event.date).is_none() {
+            skipped += 1;
+            continue;
+        }
+        by_date.entry(event.date).or_default().push(item);
+    }
+    let mut imported = 0;
+    for (date, items) in by_date {
+        for item in items {
+            if session.add_busy_item(date, item) {
+                imported += 1;
+            } else {
+                skipped += 1;
+            }
+        }
+    }
+    println!("{} {}件の予定を取り込みました (対象外・重複 {}件)", theme::symbol("📅"), imported, skipped);
+    Ok(())
+}
+fn handle_export(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    match args.as_slice() {
+        ["accuracy", path] => {
+            let count = store::export_accuracy_csv(&session.tasks, path).with_context(|| format!("{} に書き出せませんでした", path))?;
+            println!("{} {}件の見積と実績を {} に書き出しました", theme::symbol("📤"), count, path);
+            Ok(())
+        }
+        _ => bail!("Usage: export accuracy <path.csv>"),
+    }
+}
+fn handle_settings(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    match args.as_slice() {
+        [] => {}
+        // datetime_format などは値に空白を含むので、残りをまとめて値とする
+        [key, value @ ..] if !value.is_empty() => {
+            session.update_setting(key, &value.join(" "))?;
+            println!("{} {} を変更しました (終了時に settings.yaml へ保存します)", theme::symbol("✅"), key);
+        }
+        _ => bail!("Usage: settings [<key> <value>]"),
+    }
+    let scheduler = &session.scheduler;
+    println!("{} 現在の設定:", theme::symbol("⚙️"));
+    println!("  work_tick:              {}", session.format_duration(scheduler.work_tick));
+    println!("  buffer_time:            {}", session.format_duration(scheduler.buffer_time));
+    println!("  working_time:           {}-{}", scheduler.working_time.0.format("%H:%M"), scheduler.working_time.1.format("%H:%M"));
+    println!("  default_deadline_time:  {}", scheduler.default_deadline_time.format("%H:%M"));
+    println!("  allocation_granularity: {}", session.format_duration(scheduler.granularity));
+    println!("  min_window:             {}", session.format_duration(scheduler.min_window));
+    println!("  schedule_strategy:      {}", scheduler.strategy);
+    println!("  strict:                 {}", scheduler.strict);
+    match session.config.schedule_horizon_days {
+        Some(days) => println!("  schedule_horizon_days:  {}日", days),
+        None => println!("  schedule_horizon_days:  (カレンダーの終わりまで)"),
+    }
+    println!("  min_slack_days:         {}日", session.config.min_slack_days);
+    println!("  datetime_format:        {} (例: {})", session.config.datetime_format, session.format_datetime(session.now()));
+    println!("  priority_weights:       (weights で表示)");
+    Ok(())
+}
+fn handle_weights(session: &mut session::Session) -> anyhow::Result<()> {
+    let weights = session.scheduler.weights;
+    println!("{} 優先度スコアの重み (settings.yaml の priority_weights):", theme::symbol("⚖️"));
+    println!("  緊急度 (urgency):    {:.2}", weights.urgency);
+    println!("  リスク (risk):       {:.2}", weights.risk);
+    println!("  依存度 (dependents): {:.2}", weights.dependents);
+    println!("  スコア = 緊急度×{:.2} + リスク×{:.2} + 依存度×{:.2}", weights.urgency, weights.risk, weights.dependents);
+    Ok(())
+}
+fn handle_note(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let Some(id_key) = args.first() else {
+        bail!("Usage: note <task-id> [add <text>]");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    match args.get(1).copied() {
+        Some("add") => {
+            let text = args[2..].join(" ");
+            if text.is_empty() {
+                bail!("メモの内容を指定してください");
+            }
+            session.add_note(&task_id, now, text);
+            let task = &session.tasks[&task_id];
+            println!("{} メモを追加: {} - {}", theme::symbol("📝"), session.short_id(&task.id), task.title);
+        }
+        Some(sub) => bail!("不明なサブコマンドです: {} (Usage: note <task-id> [add <text>])", sub),
+        None => {
+            let task = &session.tasks[&task_id];
+            println!("{} メモ: {} - {}", theme::symbol("📝"), session.short_id(&task.id), task.title);
+            if task.notes.is_empty() {
+                println!("  (メモはありません)");
+            }
+            for (at, text) in &task.notes {
+                println!("  {} {}", session.format_datetime(*at), text);
+            }
+        }
+    }
+    Ok(())
+}
+fn handle_grep(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let query = args.join(" ");
+    if query.is_empty() {
+        bail!("Usage: grep <query>");
+    }
+    let matches = session.grep_notes(&query);
+    if matches.is_empty() {
+        println!("(「{}」を含むメモはありません)", query);
+        return Ok(());
+    }
+    let mut current = None;
+    for (task, (at, text)) in matches {
+        if current != Some(task.id) {
+            println!("{} {}", session.short_id(&task.id), task.title);
+            current = Some(task.id);
+        }
+        println!("  {} {}", session.format_datetime(*at), text);
+    }
+    Ok(())
+}
+fn handle_tag(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let Some(id_key) = args.first() else {
+        bail!("Usage: tag <task-id> [+]<tag>... | -<tag>...");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let mut add = Vec::new();
+    let mut remove = Vec::new();
+    for arg in &args[1..] {
+        match arg.strip_prefix('-') {
+            Some(tag) => remove.push(tag.trim_start_matches('#').to_string()),
+            None => add.push(arg.trim_start_matches('+').trim_start_matches('#').to_string()),
+        }
+    }
+    if add.iter().chain(&remove).any(|tag| tag.is_empty()) {
+        bail!("空のタグは指定できません");
+    }
+    session.update_tags(&task_id, &add, &remove);
+    let task = &session.tasks[&task_id];
+    println!("{} タグ: {} - {}", theme::symbol("🏷️"), session.short_id(&task.id), task.title);
+    println!("  {}", format_tags(task));
+    Ok(())
+}
+fn handle_category(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let [id_key, category @ ..] = args.as_slice() else {
+        bail!("Usage: category <task-id> <category|none>");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let category = category.join(" ");
+    let category = match category.as_str() {
+        "" => bail!("Usage: category <task-id> <category|none>"),
+        "none" => None,
+        _ => Some(category),
+    };
+    session.set_category(&task_id, category);
+    let task = &session.tasks[&task_id];
+    match &task.category {
+        Some(_) => println!(
+            "{} カテゴリ: {} - {} {}",
+            theme::symbol("🗂️"),
+            session.short_id(&task.id),
+            task.title,
+            format_category(&session.category_colors(), task).trim_start()
+        ),
+        None => println!("{} カテゴリを外しました: {} - {}", theme::symbol("🗂️"), session.short_id(&task.id), task.title),
+    }
+    Ok(())
+}
+fn format_tags(task: &Task) -> String {
+    if task.tags.is_empty() {
+        return "(タグなし)".to_string();
+    }
+    task.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" ")
+}
+fn handle_fix(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let Some(id_key) = args.first() else {
+        bail!("Usage: fix <task-id> (<date> <HH:MM> | <HH:MM> | none)");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let parse_time = |tok: &str| NaiveTime::parse_from_str(tok, "%H:%M").map_err(|_| anyhow!("時刻形式は HH:MM で指定してください: {}", tok));
+    let fixed_at = match &args[1..] {
+        ["none" | "clear"] => None,
+        [time] => Some(now.date().and_time(parse_time(time)?)),
+        [date, time] => Some(parse_date(now, date).map_err(anyhow::Error::msg)?.and_time(parse_time(time)?)),
+        _ => bail!("Usage: fix <task-id> (<date> <HH:MM> | <HH:MM> | none)"),
+    };
+    // 過ぎた時刻はスケジュールで通常のタスクとして扱われ、指定が効かないので受け付けない
+    if let Some(at) = fixed_at.filter(|at| *at < now) {
+        bail!("{} は既に過ぎています。これからの時刻を指定してください", session.format_datetime(at));
+    }
+    let task = session.set_fixed_at(&task_id, fixed_at).clone();
+    match task.fixed_at {
+        Some(at) => {
+            println!("{} 時刻指定: {} - {}", theme::symbol("📌"), session.short_id(&task.id), task.title);
+            println!("  {} から {}", session.format_datetime(at), session.format_duration(task.remaining()));
+            // 時刻指定のタスクは予定と同じく時間帯を埋めるので、既存の予定と重なれば知らせる
+            let end = at + task.remaining();
+            let overlapping = session
+                .calendar
+                .time_windows(at.date().and_time(NaiveTime::MIN))
+                .take_while(|w| w.date == at.date())
+                .filter(|w| !w.available() && w.start_datetime() < end && at < w.end_datetime());
+            for window in overlapping {
+                println!(
+                    "  {} 予定と重なっています: {}-{} {}",
+                    theme::symbol("⚠️"),
+                    window.start.format("%H:%M"),
+                    window.end.format("%H:%M"),
+                    window.note()
+                );
+            }
+        }
+        None => println!("{} 時刻指定を解除: {} - {}", theme::symbol("📌"), session.short_id(&task.id), task.title),
+    }
+    Ok(())
+}
+fn handle_defer(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let (Some(id_key), Some(date)) = (args.first(), args.get(1)) else {
+        bail!("Usage: defer <task-id> (<date> | none)");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let not_before = match *date {
+        "none" | "clear" => None,
+        date => Some(parse_date(now, date).map_err(anyhow::Error::msg)?.and_time(NaiveTime::MIN)),
+    };
+    session.set_not_before(&task_id, not_before);
+    let task = &session.tasks[&task_id];
+    match task.not_before {
+        Some(at) => println!("{} {} 以降に先送り: {} - {}", theme::symbol("⏭️"), at.date(), session.short_id(&task.id), task.title),
+        None => println!("{} 先送りを解除: {} - {}", theme::symbol("⏭️"), session.short_id(&task.id), task.title),
+    }
+    Ok(())
+}
+fn handle_prefer(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let usage = "Usage: prefer <task-id> (after <task-id>... | none)";
+    let Some(id_key) = args.first() else {
+        bail!(usage);
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let prefer_after = match &args[1..] {
+        ["none" | "clear"] => vec![],
+        ["after", keys @ ..] if !keys.is_empty() => {
+            let mut ids = vec![];
+            for key in keys {
+                let Some(before_id) = find_task(session, key) else {
+                    bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), key);
+                };
+                if before_id == task_id {
+                    bail!("自分自身は指定できません: {}", key);
+                }
+                ids.push(before_id);
+            }
+            ids
+        }
+        _ => bail!(usage),
+    };
+    let task = session.set_prefer_after(&task_id, prefer_after).clone();
+    if task.prefer_after.is_empty() {
+        println!("{} 緩い依存を解除: {} - {}", theme::symbol("🔀"), session.short_id(&task.id), task.title);
+    } else {
+        println!("{} できれば次のタスクの後に着手: {} - {}", theme::symbol("🔀"), session.short_id(&task.id), task.title);
+        for before_id in &task.prefer_after {
+            println!("  {} {}", session.short_id(before_id), session.tasks[before_id].title);
+        }
+    }
+    Ok(())
+}
+fn handle_subtask(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let (Some(id_key), Some(parent_key)) = (args.first(), args.get(1)) else {
+        bail!("Usage: sub <task-id> (<parent-id> | none)");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let parent_id = match *parent_key {
+        "none" => None,
+        key => {
+            let Some(parent_id) = find_task(session, key) else {
+                bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), key);
+            };
+            Some(parent_id)
+        }
+    };
+    session.set_parent(&task_id, parent_id)?;
+    let task = &session.tasks[&task_id];
+    let Some(parent_id) = parent_id else {
+        println!("{} 親タスクを解除: {} - {}", theme::symbol("🔗"), session.short_id(&task.id), task.title);
+        return Ok(());
+    };
+    println!("{} サブタスク: {} - {}", theme::symbol("🔗"), session.short_id(&task.id), task.title);
+    let parent = &session.tasks[&parent_id];
+    println!("  親タスク: {} - {}", session.short_id(&parent.id), parent.title);
+    warn_rollup_conflict(session, &parent_id);
+    Ok(())
+}
+
+/// 子タスクを持つ親タスクに手動の見積がある場合、子タスクの合計が優先されることを警告する
+fn warn_rollup_conflict(session: &session::Session, task_id: &TaskID) {
+    let task = &session.tasks[task_id];
+    if task.estimate().is_none() || !task.has_children(&session.tasks) {
+        return;
+    }
+    let rolled_up = task.rolled_up_estimate(&session.tasks).map(|e| session.format_duration(e.mean())).unwrap_or_else(|| "なし".to_string());
+    println!(
+        "{} {} には子タスクがあるため、手動の見積 ({}) ではなく子タスクの見積の合計 ({}) を使います。",
+        theme::symbol("⚠️"),
+        session.short_id(&task.id),
+        session.format_duration(task.estimate().unwrap().mean()),
+        rolled_up
+    );
+}
+
+fn handle_deadline_time(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let id_key = args.first().unwrap_or(&"");
+    if id_key.is_empty() {
+        bail!("<task-id> を指定してください");
+    }
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let time = match args.get(1).copied() {
+        Some("default") => None,
+        Some(ts) => Some(
+            NaiveTime::parse_from_str(ts, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(ts, "%H:%M"))
+                .map_err(|_| anyhow!("時刻形式は HH:MM(:SS) で指定してください"))?,
+        ),
+        None => bail!("Usage: deadline-time <task-id> (<HH:MM> | default)"),
+    };
+    let default_deadline_time = session.scheduler.default_deadline_time;
+    session.set_default_deadline_time(&task_id, time);
+    let task = &session.tasks[&task_id];
+    println!("{} 期限時刻: {} - {}", theme::symbol("⌛"), session.short_id(&task.id), task.title);
+    match task.default_deadline_time {
+        Some(time) => println!("  期限時刻: {}", time.format("%H:%M")),
+        None => println!("  期限時刻: {} (既定)", default_deadline_time.format("%H:%M")),
+    }
+    Ok(())
+}
+
+fn handle_estimate(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    // #tag:<name> でタグの付いた未完了タスクにまとめて見積を設定する
+    if let Some(tag) = args.first().and_then(|arg| arg.strip_prefix("#tag:")) {
+        let task_ids = session.find_open_tasks_by_tag(tag);
+        if task_ids.is_empty() {
+            bail!("タグ #{} の付いた未完了タスクがありません", tag);
+        }
+        for task_id in &task_ids {
+            apply_estimate(session, now, task_id, &args[1..])?;
+        }
+        println!("{} #{} の{}件のタスクに見積を設定しました", theme::symbol("✅"), tag, task_ids.len());
+        return Ok(());
+    }
+    let task_id = if let Some((tid, _)) = session.active_task {
+        tid
+    } else {
+        let id_key = args.first().unwrap_or(&"");
+        if id_key.is_empty() {
+            bail!("<task-id> を指定してください");
+        }
+        let Some(task_id) = find_task(session, id_key) else {
+            bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+        };
+        task_id
+    };
+    if args.contains(&"suggest") {
+        return suggest_estimate(session, &task_id);
+    }
+    apply_estimate(session, now, &task_id, &args)
+}
+/// 似たタイトルの完了タスクの実績から見積を提案する (設定はしない)
+fn suggest_estimate(session: &session::Session, task_id: &TaskID) -> anyhow::Result<()> {
+    let task = &session.tasks[task_id];
+    let Some(estimate) = session.estimate_from_history(&task.title) else {
+        println!(
+            "{} {} - {} に似たタイトルの完了タスク (実績あり) がないため、提案できません。",
+            theme::symbol("💡"),
+            session.short_id(&task.id),
+            task.title
+        );
+        return Ok(());
+    };
+    let similar = session.similar_completed_tasks(&task.title);
+    println!("{} 見積の提案: {} - {}", theme::symbol("💡"), session.short_id(&task.id), task.title);
+    println!(
+        "  似たタスク{}件の実績: 中央値 {} (最短 {}, 最長 {})",
+        similar.len(),
+        session.format_duration(estimate.most_likely),
+        session.format_duration(estimate.optimistic),
+        session.format_duration(estimate.pessimistic)
+    );
+    for similar in similar.iter().take(5) {
+        println!("    {} {} ({})", session.short_id(&similar.id), similar.title, session.format_duration(similar.actual_total));
+    }
+    if similar.len() > 5 {
+        println!("    (他{}件)", similar.len() - 5);
+    }
+    println!("  適用するには: est {} {}", session.short_id(&task.id), estimate.to_command_args());
+    Ok(())
+}
+/// `args` 中の見積指定 (時間・ストーリーポイント) を解釈してタスクに設定する
+fn apply_estimate(session: &mut session::Session, now: NaiveDateTime, task_id: &TaskID, args: &[&str]) -> anyhow::Result<()> {
+    let task_id = *task_id;
+    if let Some(points) = args.iter().find_map(|arg| parse_story_points(arg)) {
+        let task = session.estimate_task_in_points(&task_id, points, now)?.clone();
+        println!("{} 予測: {} - {}", theme::symbol("⌛"), session.short_id(&task.id), task.title);
+        println!("  予測残り時間: {} ({}pt)", session.format_duration(task.remaining()), points);
+        warn_rollup_conflict(session, &task_id);
+        return Ok(());
+    }
+    let current_remaining = Estimate::new(session.tasks.get(&task_id).unwrap().remaining());
+    let (estimate, relative) = parse_estimate_times(args, current_remaining, session.calendar.duration_units())?;
+    let short_id = session.short_id(&task_id);
+    let task = if relative {
+        // 加減算は現在の残り時間が基準なので、入力の表記は残さない
+        session.estimate_task(&task_id, estimate.clone(), now)?
+    } else {
+        let input = args
+            .iter()
+            .filter(|arg| session.parse_duration(arg).is_some() || parse_spread_percent(arg).is_some())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        session.estimate_task_as_entered(&task_id, estimate.clone(), input, now)?
+    };
+    println!("{} 予測: {} - {}", theme::symbol("⌛"), short_id, task.title);
+    println!("  予測残り時間: {}", session.format_duration(estimate.mean()));
+    warn_rollup_conflict(session, &task_id);
+    Ok(())
+}
+/// 見積の時間指定を解釈する。`+`/`-` 付き (加減算) なら `current_remaining` に足し引きし、2 つ目の値が true
+/// `1d` / `1w` は `units` の長さで解釈する
+fn parse_estimate_times(args: &[&str], current_remaining: Estimate, units: DurationUnits) -> anyhow::Result<(Estimate, bool)> {
+    for arg in args.iter().filter(|arg| parse_human_duration(arg).is_some()) {
+        parse_positive_duration(arg).map_err(|_| anyhow!("見積は0より大きい時間で指定してください: {}", arg))?;
+    }
+    let times: Vec<_> = args.iter().filter_map(|arg| parse_human_duration_with_sign(arg, units)).collect();
+    let estimate = match (times.as_slice(), current_remaining) {
+        // <most-likely> ±<n>% で楽観/悲観を展開
+        ([(None, m)], _) if let Some(percent) = args.iter().find_map(|arg| parse_spread_percent(arg)) => Estimate::from_spread(*m, percent).map_err(anyhow::Error::msg)?,
+        ([(None, m)], _) => Estimate::new(*m),
+        ([(None, m), (None, o), (None, p)], _) => Estimate::from_mop(*m, *o, *p).map_err(|err| anyhow!("m o p (最尤 楽観 悲観) の順で、楽観 ≤ 最尤 ≤ 悲観 になるよう指定してください: {}", err))?,
+        // 減らす分は負の見積を足さず、0 で止まる引き算で引く
+        ([(Some(sm), m)], curr) if *sm < 0 => curr - Estimate::new(*m),
+        ([(Some(_), m)], curr) => curr + Estimate::new(*m),
+        ([(Some(sm), m), (Some(so), o), (Some(sp), p)], curr) => {
+            let part = |sign: i32| Estimate {
+                most_likely: if *sm == sign { *m } else { Duration::zero() },
+                optimistic: if *so == sign { *o } else { Duration::zero() },
+                pessimistic: if *sp == sign { *p } else { Duration::zero() },
+            };
+            let estimate = curr + part(1) - part(-1);
+            Estimate::from_mop(estimate.most_likely, estimate.optimistic, estimate.pessimistic)
+                .map_err(|err| anyhow!("m o p (最尤 楽観 悲観) の順で、楽観 ≤ 最尤 ≤ 悲観 になるよう指定してください: {}", err))?
+        }
+        _ => bail!("<most-likely> (<optimistic> <pessimistic>) または <most-likely> ±<n>% の形式で指定してください"),
+    };
+    // 増減した結果が 0 以下になる見積 (1h の見積に -2h など) も受け付けない
+    if [estimate.most_likely, estimate.optimistic, estimate.pessimistic].iter().any(|d| *d <= Duration::zero()) {
+        let inputs: Vec<_> = args.iter().filter(|arg| parse_human_duration_with_sign(arg, units).is_some()).copied().collect();
+        bail!("見積は0より大きい時間で指定してください: {}", inputs.join(" "));
+    }
+    Ok((estimate, times.iter().any(|(sign, _)| sign.is_some())))
+}
+fn handle_estimate_history(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let id_key = args.first().unwrap_or(&"");
+    if id_key.is_empty() {
+        bail!("<task-id> を指定してください");
+    }
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    let task = session.tasks.get(&task_id).unwrap();
+    println!("{} 見積履歴: {} - {}", theme::symbol("📈"), session.short_id(&task.id), task.title);
+    if task.estimate_history().is_empty() {
+        println!("  (見積履歴なし)");
+        return Ok(());
+    }
+    let mut previous: Option<Duration> = None;
+    for (updated_at, estimate) in task.estimate_history() {
+        let mean = estimate.mean();
+        let drift = match previous {
+            Some(prev) if mean >= prev => format!(" (+{})", session.format_duration(mean - prev)),
+            Some(prev) => format!(" (-{})", session.format_duration(prev - mean)),
+            None => "".to_owned(),
+        };
+        println!(
+            "  {}: {} (最尤{}, 楽観{}, 最悪{}){}",
+            session.format_datetime(*updated_at),
+            session.format_duration(mean),
+            session.format_duration(estimate.most_likely),
+            session.format_duration(estimate.optimistic),
+            session.format_duration(estimate.pessimistic),
+            drift
+        );
+        previous = Some(mean);
+    }
+    Ok(())
+}
+fn handle_record(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let mut args = args.iter();
+    let Some(id_key) = args.next() else {
+        bail!("<task-id> を指定してください");
+    };
+    let Some(duration) = args.next().and_then(|arg| session.parse_duration(arg)) else {
+        bail!("Usage: record <task-id> <duration>");
+    };
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+    };
+    session.record_task(&task_id, duration);
+    let task = &session.tasks[&task_id];
+    println!("{} 記録: {} - {}", theme::symbol("📝"), session.short_id(&task.id), task.title);
+    Ok(())
+}
+fn handle_log(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    // 表示・指定ともに番号は 1 始まり
+    let parse_index = |arg: Option<&&str>| -> anyhow::Result<usize> {
+        match arg.and_then(|s| s.parse::<usize>().ok()) {
+            Some(n) if n >= 1 => Ok(n - 1),
+            _ => bail!("番号は 1 以上の整数で指定してください"),
+        }
+    };
+    match args.first().copied() {
+        Some("edit") => {
+            let Some(date) = args.get(1) else {
+                bail!("Usage: log edit <date> <index> <duration>");
+            };
+            let date = parse_date(now, date).map_err(anyhow::Error::msg)?;
+            let index = parse_index(args.get(2))?;
+            let Some(duration) = args.get(3).and_then(|arg| session.parse_duration(arg)) else {
+                bail!("Usage: log edit <date> <index> <duration>");
+            };
+            let task = session.edit_log_item(date, index, duration)?.clone();
+            println!("{} 作業記録を修正: {} - {}", theme::symbol("📝"), session.short_id(&task.id), task.title);
+            println!("  実績: {}", session.format_duration(task.actual_total));
+        }
+        Some("rm") => {
+            let Some(date) = args.get(1) else {
+                bail!("Usage: log rm <date> <index>");
+            };
+            let date = parse_date(now, date).map_err(anyhow::Error::msg)?;
+            let index = parse_index(args.get(2))?;
+            let task = session.remove_log_item(date, index)?.clone();
+            println!("{} 作業記録を削除: {} - {}", theme::symbol("🗑️"), session.short_id(&task.id), task.title);
+            println!("  実績: {}", session.format_duration(task.actual_total));
+        }
+        date => {
+            let date = match date {
+                Some(date) => parse_date(now, date).map_err(anyhow::Error::msg)?,
+                None => now.date(),
+            };
+            let Some(items) = session.log.get_items(date) else {
+                println!("{} {} の作業記録はありません。", theme::symbol("📭"), date);
+                return Ok(());
+            };
+            println!("{} {} の作業記録:", theme::symbol("📒"), date);
+            for (i, item) in items.iter().enumerate() {
+                let title = session.tasks.get(&item.task_id).map(|t| t.title.as_str()).unwrap_or("(不明なタスク)");
+                println!(
+                    "  {}. {} {} {} - {}",
+                    i + 1,
+                    item.begin_at.format("%H:%M"),
+                    session.format_duration(item.duration),
+                    session.short_id(&item.task_id),
+                    title
+                );
+            }
+        }
+    }
+    Ok(())
+}
+fn handle_burndown(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let days = match args.first() {
+        Some(arg) => arg.parse::<usize>().map_err(|_| anyhow!("Usage: burndown [days]"))?,
+        None => 14,
+    };
+    let snapshots = store::load_burndown(crate::BURNDOWN_FILE)?;
+    let mut daily = burndown::daily_remaining(&snapshots);
+    // 未保存の変更も含めて、現在の残り時間を今日の値とする
+    daily.insert(now.date(), BurndownSnapshot::take(now, session.iter_tasks()).remaining());
+    let recent: Vec<_> = daily.into_iter().rev().take(days.max(1)).rev().collect();
+
+    let values: Vec<_> = recent.iter().map(|(_, remaining)| *remaining).collect();
+    println!("{} 残り時間の推移 (直近{}日): {}", theme::symbol("📉"), recent.len(), theme::render(&burndown::sparkline(&values)));
+    let mut previous: Option<Duration> = None;
+    for (date, remaining) in &recent {
+        let diff = match previous {
+            Some(prev) if *remaining < prev => format!(" (-{})", session.format_duration(prev - *remaining)),
+            Some(prev) if *remaining > prev => format!(" (+{})", session.format_duration(*remaining - prev)),
+            Some(_) => " (±0)".to_string(),
+            None => String::new(),
+        };
+        println!("  {} {}{}", date, session.format_duration(*remaining), diff);
+        previous = Some(*remaining);
+    }
+    Ok(())
+}
+fn handle_eod(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let record = args.contains(&"--record");
+    let date = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(date) => parse_date(now, date).map_err(anyhow::Error::msg)?,
+        None => now.date(),
+    };
+    let Some(reconciled) = session.baseline.reconcile_day(date, &session.log) else {
+        bail!("{} の計画が記録されていません", date);
+    };
+
+    println!("{} {} の振り返り:", theme::symbol("🌙"), date);
+    let mut shortfall_total = Duration::zero();
+    for (task_id, planned, actual) in reconciled.iter().filter(|(_, planned, _)| !planned.is_zero()) {
+        let title = session.tasks.get(task_id).map(|t| t.title.as_str()).unwrap_or("(不明なタスク)");
+        let shortfall = *planned - *actual;
+        if shortfall > Duration::zero() {
+            shortfall_total += shortfall;
+            println!(
+                "  {} {} {}: 予定 {} / 実績 {} ({}未達)",
+                theme::symbol("⏳"),
+                session.short_id(task_id),
+                title,
+                session.format_duration(*planned),
+                session.format_duration(*actual),
+                session.format_duration(shortfall)
+            );
+        } else {
+            println!(
+                "  {} {} {}: 予定 {} / 実績 {}",
+                theme::symbol("✅"),
+                session.short_id(task_id),
+                title,
+                session.format_duration(*planned),
+                session.format_duration(*actual)
+            );
+        }
+    }
+    if shortfall_total.is_zero() {
+        println!("  予定どおり完了しました {}", theme::symbol("🎉"));
+    } else {
+        println!("  未達の合計: {} (残りは翌日以降に再計画されます)", session.format_duration(shortfall_total));
+    }
+
+    if record {
+        let end_of_day = session.calendar.working_time(date).map(|(_, end)| end).unwrap_or(session.scheduler.working_time.1);
+        let recorded = session.record_untouched_plan(date, end_of_day)?;
+        println!("{} 手をつけなかった{}件のタスクに 0 分の作業記録を残しました", theme::symbol("📝"), recorded.len());
+    }
+    Ok(())
+}
+fn handle_due(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let days = match args.first() {
+        Some(arg) => arg.parse::<i64>().ok().filter(|days| *days > 0).ok_or_else(|| anyhow!("Usage: due [days] (days は 1 以上)"))?,
+        None => 7,
+    };
+    let until = now + Duration::days(days);
+    let mut due_tasks = Vec::new();
+    // 期限を解決できないタスクがあっても一覧は表示し、最後にまとめて知らせる
+    let mut unresolved = Vec::new();
+    for task in session.iter_tasks().filter(|t| !t.is_completed() && !t.is_dropped()) {
+        match task.resolve_deadline(&session.calendar, session.scheduler.default_deadline_time) {
+            Ok(Some(deadline)) if deadline <= until => due_tasks.push((deadline, task)),
+            Ok(_) => {}
+            Err(err) => unresolved.push((task, err)),
+        }
+    }
+    due_tasks.sort_by_key(|(deadline, task)| (*deadline, task.id));
+    let (overdue, upcoming): (Vec<_>, Vec<_>) = due_tasks.into_iter().partition(|(deadline, _)| *deadline < now);
+
+    if !overdue.is_empty() {
+        println!("{} 期限切れのタスク:", theme::symbol("⚠️"));
+        for (deadline, task) in &overdue {
+            println!(
+                "  {} {} {} ({}超過{})",
+                session.format_datetime(*deadline),
+                session.short_id(&task.id),
+                task.title,
+                session.format_duration(now - *deadline),
+                theme::symbol("⚠️")
+            );
+        }
+        println!();
+    }
+    println!("{} {}日以内に期限のタスク:", theme::symbol("📅"), days);
+    if upcoming.is_empty() {
+        println!("  (期限の近いタスクはありません)");
+    }
+    for (deadline, task) in &upcoming {
+        println!(
+            "  {} {} {} (あと{})",
+            session.format_datetime(*deadline),
+            session.short_id(&task.id),
+            task.title,
+            session.format_duration(*deadline - now)
+        );
+    }
+    if !unresolved.is_empty() {
+        println!();
+        println!("{} 期限を解決できないタスク:", theme::symbol("⚠️"));
+        for (task, err) in &unresolved {
+            println!("  {} {} ({})", session.short_id(&task.id), task.title, err);
+        }
+    }
+    Ok(())
+}
+fn handle_stale(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    let days = match args.first() {
+        Some(arg) => arg.parse::<i64>().ok().filter(|days| *days > 0).ok_or_else(|| anyhow!("Usage: stale [days] (days は 1 以上)"))?,
+        None => 14,
+    };
+    let stale = session.stale_tasks(now, days);
+    println!("{} 作成から{}日以上、一度も手を付けていないタスク:", theme::symbol("🕸️"), days);
+    if stale.is_empty() {
+        println!("  (放置されているタスクはありません)");
+        return Ok(());
+    }
+    for task in &stale {
+        println!(
+            "  {} {} {} ({}日前に作成)",
+            task.created_at.format("%m/%d"),
+            session.short_id(&task.id),
+            task.title,
+            (now - task.created_at).num_days()
+        );
+    }
+    println!("{} schedule で予定に入れるか、defer で後回しにするか、drop で削除してください", theme::symbol("💡"));
+    Ok(())
+}
+fn handle_now(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    match args.first().copied() {
+        None => {}
+        Some("clear") => session.now_override = None,
+        Some("set") => {
+            let usage = "Usage: now set <date> [HH:MM] [--fixed]";
+            let fixed = args.contains(&"--fixed");
+            let mut parts = args[1..].iter().filter(|arg| **arg != "--fixed");
+            let Some(date) = parts.next() else {
+                bail!(usage);
+            };
+            let target = match NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S") {
+                Ok(datetime) => datetime,
+                Err(_) => {
+                    let date = parse_date(now, date).map_err(anyhow::Error::msg)?;
+                    let time = match parts.next() {
+                        Some(ts) => NaiveTime::parse_from_str(ts, "%H:%M:%S")
+                            .or_else(|_| NaiveTime::parse_from_str(ts, "%H:%M"))
+                            .map_err(|_| anyhow!("時刻形式は HH:MM(:SS) で指定してください"))?,
+                        None => now.time(),
+                    };
+                    date.and_time(time)
+                }
+            };
+            session.now_override = Some(if fixed {
+                session::NowOverride::Fixed(target)
+            } else {
+                session::NowOverride::Offset(target - chrono::Local::now().naive_local())
+            });
+        }
+        Some(other) => bail!("不明なサブコマンドです: {} (set / clear)", other),
+    }
+    let label = match session.now_override {
+        None => "実時刻",
+        Some(session::NowOverride::Offset(_)) => "上書き中",
+        Some(session::NowOverride::Fixed(_)) => "固定",
+    };
+    println!("{} 現在時刻: {} ({})", theme::symbol("🕒"), session.now().format("%Y-%m-%d %H:%M:%S"), label);
+    Ok(())
+}
+fn handle_todo(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    if args.first() == Some(&"done") {
+        return handle_todo_done(session, now, &args[1..]);
+    }
+    // 表示しなかった一覧の番号で todo done しないよう、前回の一覧はここで捨てる (--json や空の日も含む)
+    session.todo_indices.clear();
+    let (json, args) = take_json_flag(args);
+    let today = match args.iter().position(|arg| *arg == "--date" || *arg == "-d") {
+        Some(i) => match args.get(i + 1).copied() {
+            Some(date) => parse_date(now, date).map_err(anyhow::Error::msg)?,
+            None => bail!("Usage: todo [--date <today|tomorrow|date>] [--json]"),
+        },
+        None => now.date(),
+    };
+    let day_label = match (today - now.date()).num_days() {
+        0 => "今日".to_string(),
+        1 => "明日".to_string(),
+        _ => today.format("%m/%d").to_string(),
+    };
+    if json {
+        return print_json(&todo_json(session, today));
+    }
+    let pinned = session.pinned_task().map(|t| t.id).filter(|_| today == now.date());
+    if session.slots.get(&today).is_empty() && pinned.is_none() {
+        println!("{} {}のタスクはありません。", theme::symbol("✅"), day_label);
+        if session.tasks.is_empty() {
+            println!("   add <タイトル> でタスクを追加すると、ここに{}やることが表示されます。", day_label);
+        }
+        return Ok(());
+    };
+
+    let todo_all = todo_entries(session, today, pinned);
+    if todo_all.is_empty() {
+        println!("{} {}のタスクはありません。", theme::symbol("✅"), day_label);
+        return Ok(());
+    }
+
+    // カレンダーの予定 (会議など) を時刻順に並べ、時刻指定のタスクの間に差し込む
+    let meetings: Vec<_> = session
+        .calendar
+        .time_windows(today.and_time(NaiveTime::MIN))
+        .take_while(|w| w.date == today)
+        .filter(|w| !w.available())
+        .collect();
+    let print_meetings_until = |next: &mut usize, until: Option<NaiveTime>| {
+        while let Some(window) = meetings.get(*next).filter(|w| until.is_none_or(|until| w.start <= until)) {
+            println!("    {}-{} {} {}", window.start.format("%H:%M"), window.end.format("%H:%M"), theme::symbol("📅"), window.note());
+            *next += 1;
+        }
+    };
+    let mut next_meeting = 0;
+
+    let todo = todo_all.iter().filter(|(t, _)| t.is_ready()).collect::<Vec<_>>();
+    let todo_ids: Vec<_> = todo.iter().map(|(t, _)| t.id).collect();
+
+    println!(
+        "{} {}やること（全{}件, ブロッキング{}件）:\n",
+        theme::symbol("🦥"),
+        day_label,
+        todo_all.len(),
+        todo_all.len() - todo.len()
+    );
 
-    // ソート：仮で allocated 大きい順（将来は progressなど）
-    todo_all.sort_by_key(|&(_, d)| std::cmp::Reverse(d));
-
-    let todo = todo_all.iter().filter(|(t, _)| t.is_ready()).collect::<Vec<_>>();
-
-    println!("🦥 今日やること（全{}件, ブロッキング{}件）:\n", todo_all.len(), todo_all.len() - todo.len());
-
     for (i, (task, allocated)) in todo.iter().enumerate() {
         let title = task.title.clone();
+        let icon = if pinned == Some(task.id) { theme::symbol("⭐") } else { theme::symbol("📝") };
+
+        // 時刻指定のタスクは予定として時刻付きで表示する
+        if let Some(fixed_at) = task.fixed_at.filter(|at| at.date() == today) {
+            print_meetings_until(&mut next_meeting, Some(fixed_at.time()));
+            let icon = if pinned == Some(task.id) { theme::symbol("⭐") } else { theme::symbol("📌") };
+            println!("#{:<2} {} {} {} [{}]", i + 1, icon, fixed_at.format("%H:%M"), task.title, session.format_duration(*allocated));
+            continue;
+        }
+        print_meetings_until(&mut next_meeting, None);
+
+        // 見積がなければ進捗は計算できず、割当もスケジューラ既定の残り時間によるもの
+        if task.estimate().is_none() {
+            println!("#{:<2} {} {} [{}] (見積未設定 — 割当は仮)", i + 1, icon, task.title, session.format_duration(*allocated));
+            continue;
+        }
 
         let simulated_progress = match task.simulate_progress(allocated) {
             Ok(progress) => format!(" -> 本日で{}", progress),
             Err(_) => "".to_owned(),
         };
 
+        let plan = match session.plan_variance(&task.id, now) {
+            Some(variance) => format!(" {}", format_plan_variance(session, variance)),
+            None => "".to_owned(),
+        };
+
         println!(
-            "#{:<2} 📝 {} [{}] (進捗: {}{})",
+            "#{:<2} {} {} [{}] (進捗: {}{}){}",
             i + 1,
+            icon,
             task.title,
-            format_human_duration(**allocated),
+            session.format_duration(*allocated),
             task.progress(),
             simulated_progress,
+            plan,
         );
     }
 
+    print_meetings_until(&mut next_meeting, None);
+    session.todo_indices = todo_ids;
+
+    let unscheduled = session.unscheduled_tasks().len();
+    if unscheduled > 0 {
+        println!("\n{} 未割当(スケジュール外)のタスクが{}件あります (schedule で一覧)", theme::symbol("⚠️"), unscheduled);
+    }
+    Ok(())
+}
+
+fn handle_pin(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
+    let task_id = match args.first().copied() {
+        None => {
+            match session.pinned_task() {
+                Some(task) => println!("{} 今日の最優先: {} - {}", theme::symbol("⭐"), session.short_id(&task.id), task.title),
+                None => println!("(固定中のタスクはありません)"),
+            }
+            return Ok(());
+        }
+        Some("none") => None,
+        Some(id_key) => {
+            let Some(task_id) = find_task(session, id_key) else {
+                bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
+            };
+            Some(task_id)
+        }
+    };
+    match session.pin_task(task_id)?.cloned() {
+        Some(task) => println!("{} 今日の最優先に固定しました: {} - {}", theme::symbol("⭐"), session.short_id(&task.id), task.title),
+        None => println!("{} 固定を解除しました", theme::symbol("⭐")),
+    }
+    Ok(())
+}
+/// 次にやるタスク: 固定 (pin) したタスクが未完了ならそれ、なければ今日の todo の先頭
+fn handle_next(session: &mut session::Session, now: NaiveDateTime) -> anyhow::Result<()> {
+    if let Some(task) = session.pinned_task() {
+        println!(
+            "{} 次にやること: {} - {} (残り{})",
+            theme::symbol("⭐"),
+            session.short_id(&task.id),
+            task.title,
+            session.format_duration(task.remaining())
+        );
+        if !task.is_ready() {
+            println!("  {} このタスクはブロック中です (pin none で固定を解除できます)", theme::symbol("⚠️"));
+        }
+        return Ok(());
+    }
+    match todo_entries(session, now.date(), None).into_iter().find(|(t, _)| t.is_ready()) {
+        Some((task, allocated)) => println!(
+            "{} 次にやること: {} - {} [{}]",
+            theme::symbol("👉"),
+            session.short_id(&task.id),
+            task.title,
+            session.format_duration(allocated)
+        ),
+        None => println!("{} 今日のタスクはありません。", theme::symbol("✅")),
+    }
+    Ok(())
+}
+/// `date` の割当を todo の表示順に並べる。固定 (pin) したタスクは割当がなくても先頭に置く
+fn todo_entries(session: &session::Session, date: NaiveDate, pinned: Option<TaskID>) -> Vec<(Task, Duration)> {
+    let mut entries: Vec<_> = session.slots.get(&date).iter().map(|(task_id, allocated)| (session.tasks[task_id].clone(), *allocated)).collect();
+    if let Some(pinned) = pinned
+        && !entries.iter().any(|(t, _)| t.id == pinned)
+    {
+        entries.push((session.tasks[&pinned].clone(), Duration::zero()));
+    }
+    // ソート：固定したタスク、時刻指定のタスク (時刻順) を先頭へ、残りは仮で allocated 大きい順（将来は progressなど）
+    entries.sort_by_key(|(t, d)| (Some(t.id) != pinned, t.fixed_at.filter(|at| at.date() == date).is_none(), t.fixed_at, std::cmp::Reverse(*d)));
+    entries
+}
+
+/// `todo done <n>`: 直近の todo の n 番目のタスクを完了する。作業中のタスクなら done と同じく現在時刻までを記録する
+fn handle_todo_done(session: &mut session::Session, now: NaiveDateTime, args: &[&str]) -> anyhow::Result<()> {
+    let Some(n) = args.first().and_then(|arg| arg.trim_start_matches('#').parse::<usize>().ok()) else {
+        bail!("Usage: todo done <n> (n は todo で表示した番号)");
+    };
+    if session.todo_indices.is_empty() {
+        bail!("先に todo で一覧を表示してください");
+    }
+    let Some(&task_id) = n.checked_sub(1).and_then(|i| session.todo_indices.get(i)) else {
+        bail!("#{} は todo の一覧にありません (1〜{})", n, session.todo_indices.len());
+    };
+    if !session.tasks[&task_id].is_ready() {
+        let task = &session.tasks[&task_id];
+        bail!("#{} のタスクは着手可能ではありません: {} - {}", n, session.short_id(&task.id), task.title);
+    }
+    if session.active_task.is_some_and(|(id, _)| id == task_id) {
+        return handle_done(session, now, vec![]);
+    }
+    handle_complete(session, now, vec![&task_id.hex()])
+}
+
+/// `todo --json` の出力。割当のあるタスクと、その日の予定 (会議など)
+fn todo_json(session: &session::Session, date: NaiveDate) -> serde_json::Value {
+    let tasks: Vec<_> = session
+        .slots
+        .get(&date)
+        .iter()
+        .filter_map(|(task_id, allocated)| {
+            let mut value = task_json(session, session.tasks.get(task_id)?);
+            value["allocated_minutes"] = allocated.num_minutes().into();
+            Some(value)
+        })
+        .collect();
+    let meetings: Vec<_> = session
+        .calendar
+        .time_windows(date.and_time(NaiveTime::MIN))
+        .take_while(|w| w.date == date)
+        .filter(|w| !w.available())
+        .map(|w| serde_json::json!({ "start": w.start_datetime(), "end": w.end_datetime(), "note": w.note() }))
+        .collect();
+    serde_json::json!({ "date": date, "tasks": tasks, "meetings": meetings })
+}
+
+/// 直近 `days` 日 (今日を含む) に完了したタスクを、完了日ごとに新しい順で表示する
+fn handle_done_log(session: &session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
+    const WEEKDAYS: [&str; 7] = ["月", "火", "水", "木", "金", "土", "日"];
+    let days = match args.first() {
+        Some(arg) => arg.parse::<i64>().ok().filter(|days| *days > 0).ok_or_else(|| anyhow!("日数は 1 以上で指定してください: {}", arg))?,
+        None => 7,
+    };
+    let since = now.date() - Duration::days(days - 1);
+    let completed = session.completed_by_day(since);
+    if completed.is_empty() {
+        println!("(直近{}日間に完了したタスクはありません)", days);
+        return Ok(());
+    }
+    let (count, total) = completed
+        .values()
+        .flatten()
+        .fold((0, Duration::zero()), |(count, total), (_, task)| (count + 1, total + task.actual_total));
+    println!("{} 直近{}日間に完了したタスク: {}件 / 実績 {}", theme::symbol("🏁"), days, count, session.format_duration(total));
+    for (date, tasks) in completed.iter().rev() {
+        let day_total = tasks.iter().map(|(_, task)| task.actual_total).fold(Duration::zero(), |a, b| a + b);
+        let weekday = WEEKDAYS[date.weekday().num_days_from_monday() as usize];
+        println!(
+            "\n{} {} ({}) {}件 / 実績 {}",
+            theme::symbol("📅"),
+            date.format("%m/%d"),
+            weekday,
+            tasks.len(),
+            session.format_duration(day_total)
+        );
+        for (completed_at, task) in tasks {
+            let actual = if task.actual_total.is_zero() {
+                "記録なし".to_string()
+            } else {
+                session.format_duration(task.actual_total)
+            };
+            println!("  {} {} {} {} ({})", theme::symbol("✅"), completed_at.format("%H:%M"), session.short_id(&task.id), task.title, actual);
+        }
+    }
     Ok(())
 }
 
 fn handle_schedule(session: &mut session::Session, now: NaiveDateTime, args: Vec<&str>) -> anyhow::Result<()> {
     session.schedule(now)?;
-    println!("✅ スケジュールを更新しました。");
+    let open_tasks: Vec<_> = session.iter_tasks().filter(|t| t.is_ready() || t.is_blocked()).collect();
+    if open_tasks.is_empty() {
+        println!(
+            "{} スケジュール対象のタスクがありません (未完了のタスクがありません)。add でタスクを追加できます。",
+            theme::symbol("📭")
+        );
+        return Ok(());
+    }
+    // 今すぐ着手できるタスクがなければ、いつ何から着手できるかを示す
+    if let Some((task_id, startable_at)) = session.first_startable_task(now).filter(|(_, at)| *at > now) {
+        let task = &session.tasks[&task_id];
+        if open_tasks.iter().all(|t| session.slots.total(&t.id).is_zero()) {
+            println!("{} スケジュール対象のタスクがありません: 未完了のタスクはすべてブロック中か、着手日前です。", theme::symbol("📭"));
+            println!(
+                "  最も早く着手できるのは {} 以降の {} - {} ですが、カレンダーの範囲内に割り当てられませんでした。",
+                session.format_datetime(startable_at),
+                session.short_id(&task.id),
+                task.title
+            );
+            println!("  依存タスク (redep) や外部ブロックの解除見込み (ble) を見直してください。");
+            return Ok(());
+        }
+        println!("{} スケジュールを更新しました。", theme::symbol("✅"));
+        println!(
+            "{} 今すぐ着手できるタスクはありません。最も早く着手できるのは {} 以降の {} - {} です。",
+            theme::symbol("⏸"),
+            session.format_datetime(startable_at),
+            session.short_id(&task.id),
+            task.title
+        );
+    } else {
+        println!("{} スケジュールを更新しました。", theme::symbol("✅"));
+    }
+    print_low_slack_warnings(session, now);
+    let unscheduled = session.unscheduled_tasks();
+    if !unscheduled.is_empty() {
+        println!("{} 未割当(スケジュール外): {}件", theme::symbol("⚠️"), unscheduled.len());
+        for task in unscheduled {
+            println!("  {} {} (残り{})", session.short_id(&task.id), task.title, session.format_duration(task.remaining()));
+        }
+        match session.config.schedule_horizon_days {
+            Some(days) => println!(
+                "  {}日先までに割り当てられませんでした (schedule_horizon_days)。期限や優先度の重み (weights) を見直してください。",
+                days
+            ),
+            None => println!("  カレンダーの範囲内に割り当てられませんでした。期限や優先度の重み (weights) を見直してください。"),
+        }
+    }
     Ok(())
 }
 
+/// 余裕が min_slack_days を下回ったタスクを警告する (期限に遅れる前の早めの注意)
+fn print_low_slack_warnings(session: &session::Session, now: NaiveDateTime) {
+    let low_slack = session.low_slack_tasks(now);
+    if low_slack.is_empty() {
+        return;
+    }
+    println!("{} 余裕が{}日を切ったタスク: {}件", theme::symbol("🔥"), session.config.min_slack_days, low_slack.len());
+    for (task_id, slack) in low_slack {
+        let task = &session.tasks[&task_id];
+        if slack < 0.0 {
+            println!("  {} {} {} 最遅開始を過ぎています ({:.1}日)", session.short_id(&task.id), task.title, theme::symbol("⚠️"), slack);
+        } else {
+            println!("  {} {} {} 余裕わずか ({:.1}日)", session.short_id(&task.id), task.title, theme::symbol("🔥"), slack);
+        }
+    }
+}
+
 fn todo_block_by_task(session: &mut session::Session, args: Vec<&str>) -> anyhow::Result<()> {
     let id_key = args.first().unwrap_or(&"");
     if id_key.is_empty() {
         bail!("ID is required for block command");
     }
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
     let dependencies = args
         .iter()
@@ -489,8 +2415,8 @@ fn todo_block_by_task(session: &mut session::Session, args: Vec<&str>) -> anyhow
             if id_key.is_empty() {
                 bail!("ID is required for block command");
             }
-            let Some(tid) = session.find_task_by_prefix(id_key) else {
-                bail!("⚠️タスク{}が見つかりません。", id_key);
+            let Some(tid) = find_task(session, id_key) else {
+                bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
             };
             if task_id == tid {
                 return Ok(None);
@@ -499,8 +2425,9 @@ fn todo_block_by_task(session: &mut session::Session, args: Vec<&str>) -> anyhow
         })
         .filter_map(|x| x.transpose())
         .collect::<Result<Vec<_>, _>>()?;
+    let short_id = session.short_id(&task_id);
     let (task, dependencies) = session.block_task_by_tasks(&task_id, dependencies);
-    println!("⌛ ブロッキング: {} - {}", task.id, task.title);
+    println!("{} ブロッキング: {} - {}", theme::symbol("⌛"), short_id, task.title);
     if dependencies.is_empty() {
         println!("  依存タスクなし");
     } else {
@@ -518,25 +2445,21 @@ fn handle_progress(session: &mut session::Session, now: NaiveDateTime, args: Vec
     if id_key.is_empty() {
         bail!("ID is required for progress command");
     }
-    let Some(task_id) = session.find_task_by_prefix(id_key) else {
-        bail!("⚠️タスク{}が見つかりません。", id_key);
+    let Some(task_id) = find_task(session, id_key) else {
+        bail!("{}タスク{}が見つかりません。", theme::symbol("⚠️"), id_key);
     };
-    let current_progress: u8 = session.tasks.get(&task_id).unwrap().progress().into();
+    let current_progress = session.tasks.get(&task_id).unwrap().progress();
     let Some(progress_str) = args.get(1).map(|s| s.trim()) else {
-        bail!("Usage: progress <task-id> <progress>");
+        bail!("Usage: progress <task-id> (<progress> | +<diff> | -<diff> | auto)");
     };
     let progress = match progress_str {
-        "none" => None,
+        // 手動上書きを解除して、見積と実績から自動算出に戻す
+        "auto" | "none" => None,
         arg if arg.starts_with('+') || arg.starts_with('-') => {
-            let sign: i32 = match arg.chars().next().unwrap() {
-                '+' => 1,
-                '-' => -1,
-                _ => unreachable!(),
+            let Ok(diff) = arg.parse::<i32>() else {
+                bail!("Invalid progress diff: {}", arg);
             };
-            let diff = (arg[1..].trim().parse::<u8>()? as i32 * sign).clamp(-100, 100);
-            let new_progress = (current_progress as i32 + diff).clamp(0, 100);
-            let new_progress = Progress::try_from(new_progress as u8).expect("Invalid progress");
-            Some(new_progress)
+            Some(current_progress.offset(diff))
         }
         _ => {
             let new_progress = progress_str.parse::<u8>()?;
@@ -546,65 +2469,183 @@ fn handle_progress(session: &mut session::Session, now: NaiveDateTime, args: Vec
             Some(new_progress)
         }
     };
-    let task = session.update_progress_task(&task_id, progress);
-    println!("✅ 進捗: {} - {} ({})", task.id, task.title, task.progress());
+    session.update_progress_task(&task_id, progress)?;
+    let task = &session.tasks[&task_id];
+    let source = if task.progress.is_some() { "手動" } else { "自動" };
+    println!("{} 進捗: {} - {} ({}, {})", theme::symbol("✅"), session.short_id(&task.id), task.title, task.progress(), source);
     Ok(())
 }
 
-pub fn handle_command(session: &mut session::Session, mut input: &str) -> anyhow::Result<()> {
-    let mut parts = input.split_whitespace();
-    let now: NaiveDateTime = if input.starts_with('@') {
-        let now_str = parts.next().unwrap_or("");
-        NaiveDateTime::parse_from_str(now_str, "@%Y-%m-%dT%H:%M:%S")?
-    } else {
-        chrono::Local::now().naive_local()
+/// 行頭の `@YYYY-MM-DDTHH:MM:SS` を現在時刻の上書きとして切り出し、残りの入力と返す。
+/// `@` で始まるのに時刻として読めない場合は、コマンドを実行しないようエラーにする。
+pub fn split_now_prefix(input: &str) -> anyhow::Result<(Option<NaiveDateTime>, &str)> {
+    let input = input.trim_start();
+    if !input.starts_with('@') {
+        return Ok((None, input));
+    }
+    let (token, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    let Ok(now) = NaiveDateTime::parse_from_str(token, "@%Y-%m-%dT%H:%M:%S") else {
+        bail!("時刻の上書き {} を解釈できません。@YYYY-MM-DDTHH:MM:SS の形式で指定してください (例: @2025-05-07T10:00:00 list)", token);
     };
+    Ok((Some(now), rest))
+}
+
+pub fn handle_command(session: &mut session::Session, input: &str) -> anyhow::Result<()> {
+    let (now_override, input) = split_now_prefix(input)?;
+    let now = now_override.unwrap_or_else(|| session.now());
+    let mut parts = input.split_whitespace();
     let cmd = parts.next().unwrap_or("");
     let args = parts.collect::<Vec<_>>();
     let today = now.date();
 
+    // 別名は help の一覧から正式な名前に直す (一覧と食い違わないよう、ここでは別名を持たない)
+    let cmd = help::find(cmd).map_or(cmd, |command| command.name);
     match cmd {
-        "a" | "add" => handle_add(session, args)?,
-        "l" | "ls" | "list" => handle_list(session, now, args)?,
-        "sta" | "start" => handle_start(session, now, args)?,
-        "sto" | "stop" => handle_stop(session, now, args)?,
-        "dn" | "done" => handle_done(session, now, args)?,
-        "r" | "rc" | "record" => handle_record(session, now, args)?,
-        "co" | "comp" | "complete" => handle_complete(session, now, args)?,
-        "dr" | "drop" => handle_drop(session, args)?,
-        "dl" | "deadline" => handle_deadline(session, now, args)?,
-        "blt" | "block-by-task" => handle_block_by_task(session, args)?,
-        "ble" | "block-by-external" => handle_block_by_external(session, now, args)?,
-        "e" | "est" | "estimate" => handle_estimate(session, args)?,
-        "pr" | "progress" => handle_progress(session, now, args)?,
-        "sc" | "schedule" => handle_schedule(session, now, args)?,
-        "t" | "todo" => handle_todo(session, now, args)?,
-        "" | "help" => {
-            let commands = if session.active_task.is_some() {
-                vec!["add", "list", "stop", "done", "comp", "drop", "est", "help", "exit"]
-            } else {
-                vec!["add", "list", "start", "comp", "drop", "est", "schedule", "help"]
-            };
-            println!("Available commands: {}", commands.join(", "));
-            println!("Usage:");
-            println!("  add <title> - タスクを追加");
-            println!("  list - タスクを表示");
-            println!("  start <tid> - タスクを開始");
-            println!("  stop - 開始したタスクを中断");
-            println!("  done - 開始したタスクを完了");
-            println!("  comp <tid> - タスクを完了");
-            println!("  drop <tid> - タスクを削除");
-            println!("  est <tid> <time> - タスクの残り時間見積もりを設定");
-            println!("  dl <tid> <deadline> - タスクの期限を設定");
-            println!("  r <tid> <time> - タスクの実績時間を記録");
-            println!("  progress <tid> <progress> - タスクの進捗を手動で上書き");
-            println!("  schedule - タスクをスケジュール");
-            println!("  help - このヘルプを表示");
-            println!("  exit/Ctrl+D - 終了");
-            println!("  todo - 今日のTODOを表示");
-        }
-        unknown => bail!("Unknown command: {}", unknown),
+        "add" => handle_add(session, now, args)?,
+        "new" => handle_new(session, now, args)?,
+        "done-log" => handle_done_log(session, now, args)?,
+        "list" => handle_list(session, now, args)?,
+        "show" => handle_show(session, now, args)?,
+        "log" => handle_log(session, now, args)?,
+        "burndown" => handle_burndown(session, now, args)?,
+        "due" => handle_due(session, now, args)?,
+        "stale" => handle_stale(session, now, args)?,
+        "pin" => handle_pin(session, args)?,
+        "next" => handle_next(session, now)?,
+        "eod" => handle_eod(session, now, args)?,
+        "tag" => handle_tag(session, args)?,
+        "category" => handle_category(session, args)?,
+        "fix" => handle_fix(session, now, args)?,
+        "defer" => handle_defer(session, now, args)?,
+        "prefer" => handle_prefer(session, args)?,
+        "note" => handle_note(session, now, args)?,
+        "weights" => handle_weights(session)?,
+        "settings" => handle_settings(session, args)?,
+        "import" => handle_import(session, args)?,
+        "export" => handle_export(session, args)?,
+        "cal" => handle_cal(session, now, args)?,
+        "timeline" => handle_timeline(session, now)?,
+        "week" => handle_week(session, now, args)?,
+        "grep" => handle_grep(session, args)?,
+        "now" => handle_now(session, now, args)?,
+        "clone" => handle_clone(session, now, args)?,
+        "start" => handle_start(session, now, args)?,
+        "stop" => handle_stop(session, now, args)?,
+        "focus" => handle_focus(session, now)?,
+        "done" => handle_done(session, now, args)?,
+        "record" => handle_record(session, now, args)?,
+        "complete" => handle_complete(session, now, args)?,
+        "reopen" => handle_reopen(session, args)?,
+        "drop" => handle_drop(session, args)?,
+        "deadline" => handle_deadline(session, now, args)?,
+        "deadline-time" => handle_deadline_time(session, args)?,
+        "subtask" => handle_subtask(session, args)?,
+        "work-tick" => handle_work_tick(session, args)?,
+        "block-by-task" => handle_block_by_task(session, args)?,
+        "block-by-external" => handle_block_by_external(session, now, args)?,
+        "followup" => handle_follow_up(session, now, args)?,
+        "replace-dependency" => handle_replace_dependency(session, args)?,
+        "estimate" => handle_estimate(session, now, args)?,
+        "est-history" => handle_estimate_history(session, args)?,
+        "progress" => handle_progress(session, now, args)?,
+        "schedule" => handle_schedule(session, now, args)?,
+        "todo" => handle_todo(session, now, args)?,
+        "" | "help" => match args.first() {
+            None => help::print_overview(session.active_task.is_some()),
+            Some(name) => {
+                let Some(command) = help::find(name) else {
+                    bail!("{}", help::unknown_command_message(name));
+                };
+                help::print_detail(command);
+            }
+        },
+        unknown => bail!("{}", help::unknown_command_message(unknown)),
     };
-    session.schedule(now)?;
+    // `now set` などで現在時刻が変わりうるので、@ 指定がなければ取り直してスケジュールする
+    let now = if now_override.is_some() { now } else { session.now() };
+    session.schedule_if_stale(now)?;
     Ok(())
 }
+
+#[test]
+fn test_split_now_prefix() {
+    let now = NaiveDateTime::parse_from_str("2025-05-07T10:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+    assert_eq!(split_now_prefix("@2025-05-07T10:00:00 list -n 3").unwrap(), (Some(now), "list -n 3"));
+    assert_eq!(split_now_prefix("list").unwrap(), (None, "list"));
+    assert_eq!(split_now_prefix("@2025-05-07T10:00:00").unwrap(), (Some(now), ""));
+
+    // 不正な時刻はコマンドとして扱わずエラーにし、期待する形式を示す
+    let err = split_now_prefix("@notadate list").unwrap_err().to_string();
+    assert!(err.contains("@notadate"));
+    assert!(err.contains("@YYYY-MM-DDTHH:MM:SS"));
+}
+
+#[test]
+fn test_relative_estimate_must_stay_positive() {
+    let units = Calendar::new((NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap())).duration_units();
+    let current = Estimate::new(Duration::hours(1));
+    // 1h の見積から 2h 引くと負になるので拒否する
+    let err = parse_estimate_times(&["-2h"], current.clone(), units).unwrap_err().to_string();
+    assert!(err.contains("0より大きい"), "{}", err);
+    // ちょうど 0 になるのも拒否する
+    assert!(parse_estimate_times(&["-1h"], current.clone(), units).is_err());
+    let (estimate, relative) = parse_estimate_times(&["-30m"], current, units).unwrap();
+    assert_eq!(estimate.most_likely, Duration::minutes(30));
+    assert!(relative);
+    // m o p を個別に増減しても、減らす分は 0 で止まり、結果が 0 以下なら拒否する
+    let current = Estimate::from_mop(Duration::hours(2), Duration::hours(1), Duration::hours(4)).unwrap();
+    let (estimate, _) = parse_estimate_times(&["-30m", "-30m", "+1h"], current.clone(), units).unwrap();
+    assert_eq!(
+        (estimate.most_likely, estimate.optimistic, estimate.pessimistic),
+        (Duration::minutes(90), Duration::minutes(30), Duration::hours(5))
+    );
+    assert!(parse_estimate_times(&["-1h", "-2h", "+1h"], current, units).is_err());
+}
+
+#[test]
+fn test_todo_done_after_empty_todo() {
+    use crate::core::{config::Config, plan::PlanBaseline, work_log::WorkLog};
+
+    let task = Task::new("前回の一覧のタスク".to_string(), None, None);
+    let task_id = task.id;
+    let mut calendar = Calendar::new((NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap()));
+    // 翌日は稼働日ではないので、何も割り当てられない
+    calendar.add_working_day(NaiveDate::from_ymd_opt(2025, 6, 10).unwrap(), true);
+    let tasks = [(task.id, task)].into_iter().collect();
+    let mut session = session::Session::new(calendar, Config::default(), tasks, WorkLog::new(), PlanBaseline::new());
+    // 前回の todo で表示した一覧が残っている
+    session.todo_indices = vec![task_id];
+
+    // 割り当てのない日の todo を表示すると、前回の一覧では todo done できない
+    handle_command(&mut session, "@2025-06-10T10:00:00 todo --date tomorrow").unwrap();
+    assert!(handle_command(&mut session, "@2025-06-10T10:00:00 todo done 1").is_err());
+    assert!(!session.tasks[&task_id].is_completed());
+}
+
+#[test]
+fn test_parse_deadline_in_workdays() {
+    let working_time = (NaiveTime::from_hms_opt(9, 0, 0).unwrap(), NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+    let mut calendar = Calendar::new(working_time);
+    // 2025-05-05 (月) からの 2 週間、土日は休み
+    for day in NaiveDate::from_ymd_opt(2025, 5, 5).unwrap().iter_days().take(14) {
+        calendar.add_working_day(day, day.weekday().num_days_from_monday() < 5);
+    }
+    let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+    let parse = |now: &str, args: &str| parse_deadline(at(now), &calendar, working_time.1, args.split_whitespace()).unwrap();
+    let exact = |deadline: Deadline| match deadline {
+        Deadline::Exact(at) => at,
+        other => panic!("unexpected deadline: {:?}", other),
+    };
+
+    // 水曜から 3 稼働日 → 木・金・(土日を飛ばして) 月
+    assert_eq!(exact(parse("2025-05-07T10:00:00", "in 3 workdays")), at("2025-05-12T17:00:00"));
+    assert_eq!(exact(parse("2025-05-09T10:00:00", "in 2wd")), at("2025-05-13T17:00:00"));
+    // 休日から数えても翌稼働日が 1 日目
+    assert_eq!(exact(parse("2025-05-10T10:00:00", "in 1 wd")), at("2025-05-12T17:00:00"));
+    // 24 時間単位の d とは違う
+    assert_eq!(exact(parse("2025-05-09T10:00:00", "in 3d")), at("2025-05-12T17:00:00"));
+    assert_eq!(exact(parse("2025-05-09T10:00:00", "in 3 workdays")), at("2025-05-14T17:00:00"));
+
+    assert!(parse_deadline(at("2025-05-07T10:00:00"), &calendar, working_time.1, "in 1.5wd".split_whitespace()).is_err());
+    assert!(parse_deadline(at("2025-05-07T10:00:00"), &calendar, working_time.1, "in 30 workdays".split_whitespace()).is_err());
+}
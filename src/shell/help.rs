@@ -0,0 +1,605 @@
+use crate::theme;
+
+/// コマンド1つ分のヘルプ
+pub struct CommandHelp {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub usage: &'static str,
+    pub summary: &'static str,
+    /// `help <command>` で表示する詳細な書式と例
+    pub details: &'static [&'static str],
+}
+
+impl CommandHelp {
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name)
+    }
+}
+
+const DEADLINE_GRAMMAR: &[&str] = &[
+    "期限の書式:",
+    "  on <date> [HH:MM]   絶対指定 (date は YYYY-MM-DD, YYYY/MM/DD, MM/DD, today, tomorrow, mon〜sun)",
+    "  on <HH:MM>          今日の指定時刻",
+    "  in <n><unit>        今からの相対時間 (unit: m, h, d, w, mo; 12h 超は日付のみ採用)",
+    "  in <n> workdays     n 稼働日後の既定時刻 (wd でも可。土日・休日は数えない; 固定の期限になる)",
+    "  about <n><unit>     ゆるい期限 (空白なしで書く)",
+    "      bd   n 稼働日後",
+    "      fri  n 週後の金曜",
+    "      w    n 週間後",
+    "      me   n か月後の月末",
+    "      m    n か月後",
+    "  after <tid> <n>d    他タスクの期限の n 稼働日後 (deadline のみ; 設定時点の値で固定)",
+    "  none | clear        期限なし",
+    "  unknown             期限不明",
+];
+
+/// 全コマンドのヘルプ (help / help <command> / 補完で共有)
+pub const COMMANDS: &[CommandHelp] = &[
+    CommandHelp {
+        name: "add",
+        aliases: &["a"],
+        usage: "add <title> [after <tid>...] [--force]",
+        summary: "タスクを追加",
+        details: &[
+            "例: add 会議資料まとめる",
+            "after <tid>... を付けると、そのタスクを待つ (ブロックされた) 状態で追加します (例: add レビュー依頼 after #1d48)",
+            "同じタイトルの未完了タスクがある場合は --force が必要です",
+        ],
+    },
+    CommandHelp {
+        name: "new",
+        aliases: &[],
+        usage: "new [--force]",
+        summary: "タイトル・見積・期限・依存タスクを順に尋ねてタスクを作成",
+        details: &[
+            "コマンドの書式を覚えていなくてもタスクを作れます",
+            "各項目は est / dl / add ... after と同じ書式で入力し、Enter で省略できます (タイトルを空にすると中止)",
+            "入力を解釈できなければ、その項目を尋ね直します",
+            "同じタイトルの未完了タスクがある場合は --force が必要です (add と同じ)",
+            "端末からの対話実行専用です",
+        ],
+    },
+    CommandHelp {
+        name: "clone",
+        aliases: &["cl", "copy"],
+        usage: "clone <tid> [title]",
+        summary: "タスクを複製 (見積・期限・メモを引き継ぐ)",
+        details: &["タイトル省略時は元のタイトルに \" (copy)\" を付けます", "例: clone 1d48 PRレビュー #2"],
+    },
+    CommandHelp {
+        name: "list",
+        aliases: &["l", "ls"],
+        usage: "list [--limit N] [--status ready|blocked|completed] [--compact] [--by-category] [--json]",
+        summary: "タスクを表示",
+        details: &[
+            "--limit N (-n)   各セクションの表示件数を制限",
+            "--status S (-s)  ready / blocked / completed のセクションのみ表示",
+            "--compact (-c)   1 タスク 1 行で表示 (#id 状態 タイトル [期限] [進捗] [残り])",
+            "--by-category (-g) 各セクションをカテゴリごとにまとめて表示 (カテゴリなしは最後)",
+            "--json           表示の代わりに JSON を出力 (スクリプト向け)",
+            "例: list -s ready -n 5",
+        ],
+    },
+    CommandHelp {
+        name: "show",
+        aliases: &["sh"],
+        usage: "show <tid> [--json]",
+        summary: "タスクの詳細を表示",
+        details: &["見積・実績・期限・ブロック状況と、残り時間の算出根拠を表示します", "--json で表示の代わりに JSON を出力します"],
+    },
+    CommandHelp {
+        name: "start",
+        aliases: &["sta"],
+        usage: "start <tid>",
+        summary: "タスクを開始",
+        details: &["ブロック中・完了済み・削除済みのタスクは開始できません", "例: start 1d48"],
+    },
+    CommandHelp {
+        name: "stop",
+        aliases: &["sto"],
+        usage: "stop [at HH:MM | in <duration> | immediately]",
+        summary: "開始したタスクを中断",
+        details: &[
+            "stop               現在時刻で中断",
+            "stop at <HH:MM>    中断時刻を記録して中断",
+            "stop in <duration> 作業時間のみ記録して中断",
+            "stop immediately   なにも記録せず即中断",
+        ],
+    },
+    CommandHelp {
+        name: "done",
+        aliases: &["dn"],
+        usage: "done [at HH:MM | in <duration> | immediately]",
+        summary: "開始したタスクを完了",
+        details: &[
+            "done               現在時刻で完了",
+            "done at <HH:MM>    完了時刻を記録して完了",
+            "done in <duration> 作業時間のみ記録して完了",
+            "done immediately   なにも記録せず即完了 (見積のないタスクは、かかった時間を尋ねます)",
+        ],
+    },
+    CommandHelp {
+        name: "focus",
+        aliases: &["f"],
+        usage: "focus",
+        summary: "開始したタスクだけを表示する集中モード",
+        details: &["stop / done で中断・完了して終了、pause でタスクを続けたまま抜けます", "Enter で残り時間の表示を更新します"],
+    },
+    CommandHelp {
+        name: "complete",
+        aliases: &["co", "comp"],
+        usage: "comp <tid> [duration]",
+        summary: "タスクを完了",
+        details: &[
+            "duration を指定すると実績時間として記録します",
+            "見積のないタスクで duration を省くと、かかった時間を尋ねます (Enter で記録なし)",
+            "例: comp 1d48 45m",
+        ],
+    },
+    CommandHelp {
+        name: "reopen",
+        aliases: &[],
+        usage: "reopen <tid>",
+        summary: "完了・削除を取り消す",
+        details: &["完了済み・削除済みのタスクを着手可能に戻します", "完了を前提に後続タスクへ着手済みの場合は取り消せません"],
+    },
+    CommandHelp {
+        name: "drop",
+        aliases: &["dr"],
+        usage: "drop <tid>",
+        summary: "タスクを削除",
+        details: &[],
+    },
+    CommandHelp {
+        name: "estimate",
+        aliases: &["e", "est"],
+        usage: "est <tid> <time> [<optimistic> <pessimistic>]",
+        summary: "タスクの残り時間見積もりを設定",
+        details: &[
+            "est <tid> <m>              残り時間を設定",
+            "est <tid> <m> <o> <p>      最尤・楽観・悲観の3点見積",
+            "est <tid> <m> ±<n>%        最尤値から幅を指定 (例: 1d ±50%)",
+            "est <tid> +<m> / -<m>      現在の残り時間に加減算",
+            "est <tid> <n>pt            ストーリーポイントで指定 (settings.yaml の story_points)",
+            "est #tag:<name> <m> ...    タグの付いた未完了タスクにまとめて設定",
+            "est <tid> suggest          似たタイトルの完了タスクの実績 (中央値・最短・最長) から見積を提案 (設定はしない)",
+            "time の単位: m, h, d (8h), w (5d)",
+            "開始中のタスクがあれば <tid> は省略できます",
+        ],
+    },
+    CommandHelp {
+        name: "est-history",
+        aliases: &["eh"],
+        usage: "est-history <tid>",
+        summary: "タスクの見積履歴を表示",
+        details: &[],
+    },
+    CommandHelp {
+        name: "deadline",
+        aliases: &["dl"],
+        usage: "dl <tid> <deadline>",
+        summary: "タスクの期限を設定",
+        details: DEADLINE_GRAMMAR,
+    },
+    CommandHelp {
+        name: "deadline-time",
+        aliases: &["dlt"],
+        usage: "dlt <tid> <HH:MM|default>",
+        summary: "日付のみ・相対指定の期限に使う時刻を設定",
+        details: &["default で既定の時刻に戻します", "例: dlt 1d48 12:00"],
+    },
+    CommandHelp {
+        name: "week",
+        aliases: &[],
+        usage: "week [date]",
+        summary: "1 週間 (月〜金) の割り当てを表示",
+        details: &[
+            "date を含む週の、日ごとの割り当てタスクと合計を表示します。稼働日でない日は (休み) と表示します",
+            "例: week, week 2025-05-12",
+        ],
+    },
+    CommandHelp {
+        name: "timeline",
+        aliases: &[],
+        usage: "timeline",
+        summary: "各タスクの最早・最遅開始時刻と余裕を表示",
+        details: &["スケジューラが優先度の計算に使う値です。余裕 (稼働日数) の少ない順に表示します"],
+    },
+    CommandHelp {
+        name: "cal",
+        aliases: &["calendar"],
+        usage: "cal [date]",
+        summary: "1 日の空き時間と予定を表示",
+        details: &[
+            "date を省略すると今日 (現在時刻以降) を表示します",
+            "最後に、空き時間から割当済みの作業時間を引いた残りの空きを表示します",
+            "例: cal, cal tomorrow, cal 2025-05-08",
+        ],
+    },
+    CommandHelp {
+        name: "import",
+        aliases: &[],
+        usage: "import calendar <path.ics> | import worklog <path.csv>",
+        summary: "外部カレンダーの予定・作業記録を取り込む",
+        details: &[
+            ".ics の VEVENT (DTSTART / DTEND / SUMMARY) を作業できない予定として取り込みます",
+            "取り込んだ予定は settings/schedule/<日付>.yaml に保存されます",
+            "終日の予定・日をまたぐ予定・稼働日以外の予定は取り込みません",
+            "worklog: 他のツールの作業記録 (列: date,begin,duration,task-title) を作業記録とタスクの実績に取り込みます",
+            "  date は YYYY-MM-DD、begin は HH:MM、duration は 30m / 1h または分数。1 行目は見出しでもかまいません",
+            "  タイトルの一致するタスク (大文字小文字を区別しない) に記録し、なければタスクを作ります。同じ記録は二重に取り込みません",
+        ],
+    },
+    CommandHelp {
+        name: "export",
+        aliases: &[],
+        usage: "export accuracy <path.csv>",
+        summary: "完了したタスクの見積と実績を CSV に書き出す",
+        details: &[
+            "列: id, title, tags, completed_at, mean, optimistic, most_likely, pessimistic, actual (時間は分)",
+            "見積のないタスク、実績なしで完了したタスクは含めません",
+        ],
+    },
+    CommandHelp {
+        name: "settings",
+        aliases: &[],
+        usage: "settings [<key> <value>]",
+        summary: "スケジューラの設定を表示・変更",
+        details: &[
+            "引数なしで現在の設定を表示します",
+            "変更できる項目: work_tick / buffer_time / working_time / default_deadline_time / allocation_granularity / min_window / schedule_strategy / strict / schedule_horizon_days / datetime_format / min_slack_days",
+            "schedule_horizon_days を指定すると、今日からその日数より先には割り当てません (none で解除)",
+            "min_window より短い空き時間 (予定の合間の数分など) には割り当てません (0m で無効)",
+            "schedule_strategy: greedy は優先度の高いタスクから順に、fair:<k> は上位 k 件をその日の割り当てが少ない順に交代で進めます (fair だけなら k = 3)",
+            "min_slack_days を下回る余裕のタスクは schedule の後に 🔥 で警告します (0 なら最遅開始を過ぎたタスクだけ警告)",
+            "datetime_format は list / show / start で表示する日時の形式です (strftime 形式、既定は %m/%d %H:%M)",
+            "変更はすぐにスケジュールへ反映され、終了時に settings.yaml へ保存されます",
+            "例: settings work_tick 30m, settings working_time 09:00-18:00, settings schedule_horizon_days 14",
+        ],
+    },
+    CommandHelp {
+        name: "weights",
+        aliases: &[],
+        usage: "weights",
+        summary: "スケジュールの優先度スコアの重みを表示",
+        details: &["重みは settings.yaml の priority_weights (urgency, risk, dependents; 合計 1) で変更できます"],
+    },
+    CommandHelp {
+        name: "note",
+        aliases: &[],
+        usage: "note <tid> [add <text>]",
+        summary: "タスクのメモ (作業ジャーナル) を表示・追記",
+        details: &["note <tid>             メモを時刻順に表示", "note <tid> add <text>  現在時刻付きでメモを追記"],
+    },
+    CommandHelp {
+        name: "grep",
+        aliases: &[],
+        usage: "grep <query>",
+        summary: "全タスクのメモを検索",
+        details: &["大文字小文字を区別せずに部分一致で検索します"],
+    },
+    CommandHelp {
+        name: "fix",
+        aliases: &[],
+        usage: "fix <tid> (<date> <HH:MM> | <HH:MM> | none)",
+        summary: "タスクを決まった時刻に固定",
+        details: &[
+            "固定したタスクは、残り時間分の予定としてその時刻を埋めます (打ち合わせ・電話など)",
+            "過ぎた時刻は指定できません。カレンダーの予定と重なる場合は警告します",
+            "none で固定を解除します",
+        ],
+    },
+    CommandHelp {
+        name: "defer",
+        aliases: &[],
+        usage: "defer <tid> (<date> | none)",
+        summary: "指定日まで着手を先送り",
+        details: &[
+            "指定日より前にはスケジュールしません (ブロック扱いにはしません)",
+            "例: defer 1d48 5/10, defer 1d48 mon, defer 1d48 none",
+        ],
+    },
+    CommandHelp {
+        name: "prefer",
+        aliases: &[],
+        usage: "prefer <tid> (after <tid>... | none)",
+        summary: "できれば別タスクの後に着手する (緩い依存)",
+        details: &[
+            "指定したタスクが割り当てられる間は、こちらを後回しにします",
+            "ブロックとは違い、先のタスクが着手できないときは待たずに割り当てます",
+            "例: prefer 1d48 after 7c61",
+        ],
+    },
+    CommandHelp {
+        name: "tag",
+        aliases: &[],
+        usage: "tag <tid> [+]<tag>... | -<tag>...",
+        summary: "タスクにタグを付け外し",
+        details: &["例: tag 1d48 chores +urgent -later", "タグは est #tag:<name> でまとめて見積もるときに使います"],
+    },
+    CommandHelp {
+        name: "category",
+        aliases: &["cat"],
+        usage: "category <tid> <category|none>",
+        summary: "タスクのカテゴリ (Work / Personal など 1 つだけ) を設定",
+        details: &[
+            "例: category 1d48 Work  /  category 1d48 none で外す",
+            "list --by-category でカテゴリごとにまとめて表示します",
+            "表示色は settings.yaml の category_colors で指定できます (例: category_colors: { Work: blue })",
+        ],
+    },
+    CommandHelp {
+        name: "work-tick",
+        aliases: &["tick"],
+        usage: "tick <tid> <time|default>",
+        summary: "タスクごとのスケジュール割り当て単位を設定",
+        details: &["例: tick 1d48 5m  (短いタスクを 25 分単位で割り当てない)", "default で既定の割り当て単位に戻す"],
+    },
+    CommandHelp {
+        name: "subtask",
+        aliases: &["sub"],
+        usage: "sub <tid> (<parent-tid> | none)",
+        summary: "タスクを別のタスクのサブタスクにする",
+        details: &[
+            "子タスクを持つタスクの見積・残り時間は子タスクの合計になり、スケジュールには子タスクが割り当てられます",
+            "none で親タスクを解除",
+        ],
+    },
+    CommandHelp {
+        name: "block-by-task",
+        aliases: &["blt"],
+        usage: "blt <tid> <dep-tid>...",
+        summary: "別タスクの完了待ちにする",
+        details: &["例: blt 2347 dbcc"],
+    },
+    CommandHelp {
+        name: "replace-dependency",
+        aliases: &["redep"],
+        usage: "redep <tid> <old-dep-tid> <new-dep-tid>",
+        summary: "依存タスクを別のタスクに差し替える",
+        details: &["依存関係が循環する差し替えはできません", "例: redep 2347 dbcc 7c61"],
+    },
+    CommandHelp {
+        name: "block-by-external",
+        aliases: &["ble"],
+        usage: "ble <tid> <deadline> [remind <interval>]",
+        summary: "外部要因待ちにする (解除見込みを期限の書式で指定)",
+        details: DEADLINE_GRAMMAR,
+    },
+    CommandHelp {
+        name: "followup",
+        aliases: &[],
+        usage: "followup <tid>",
+        summary: "外部待ちを先方に確認したことを記録する",
+        details: &[
+            "ble <tid> <deadline> remind 3d のように間隔 (d: 日, w: 週, h: 時間) を指定すると、",
+            "前回の確認から間隔が過ぎた外部待ちを起動時と show で「要フォロー」として表示します",
+            "followup で確認した日時を記録し、次の間隔を数え直します",
+        ],
+    },
+    CommandHelp {
+        name: "record",
+        aliases: &["r", "rc"],
+        usage: "r <tid> <time>",
+        summary: "タスクの実績時間を記録",
+        details: &["例: r 1d48 30m"],
+    },
+    CommandHelp {
+        name: "log",
+        aliases: &[],
+        usage: "log [<date>] | log edit <date> <n> <duration> | log rm <date> <n>",
+        summary: "作業記録の表示・修正・削除",
+        details: &[
+            "log [<date>]                     指定日 (省略時は今日) の作業記録を番号付きで表示",
+            "log edit <date> <n> <duration>   n 番目の記録の作業時間を修正",
+            "log rm <date> <n>                n 番目の記録を削除",
+            "修正・削除はタスクの実績時間にも反映されます",
+        ],
+    },
+    CommandHelp {
+        name: "progress",
+        aliases: &["pr"],
+        usage: "progress <tid> <progress>",
+        summary: "タスクの進捗を手動で上書き",
+        details: &[
+            "progress <tid> <0-100>   進捗を上書き",
+            "progress <tid> +n / -n   現在の進捗から相対指定 (0〜100 に丸め)",
+            "progress <tid> auto      上書きを解除して見積と実績から自動算出",
+        ],
+    },
+    CommandHelp {
+        name: "burndown",
+        aliases: &["bd"],
+        usage: "burndown [days]",
+        summary: "残り時間の合計の推移を表示",
+        details: &["保存時に記録した全タスクの残り時間の合計を日ごとに表示します (既定は直近14日)", "今日の値には未保存の変更も含まれます"],
+    },
+    CommandHelp {
+        name: "eod",
+        aliases: &[],
+        usage: "eod [<date>] [--record]",
+        summary: "その日の計画と実績を突き合わせる",
+        details: &[
+            "その日最初の計画に対して、タスクごとの未達を表示します (省略時は今日)",
+            "--record で、予定していたのに手をつけなかったタスクに 0 分の作業記録を残します",
+        ],
+    },
+    CommandHelp {
+        name: "due",
+        aliases: &[],
+        usage: "due [days]",
+        summary: "期限の近い未完了タスクを期限順に表示",
+        details: &["今から days 日以内 (既定は7日) に期限が来るタスクを表示します", "期限切れのタスクは先頭にまとめて表示します"],
+    },
+    CommandHelp {
+        name: "done-log",
+        aliases: &[],
+        usage: "done-log [days]",
+        summary: "直近に完了したタスクを完了日ごとに表示 (振り返り用)",
+        details: &[
+            "今日を含む直近 days 日 (既定は7日) に完了したタスクを、新しい日から順に件数・実績の合計とともに表示します",
+            "例: done-log, done-log 30",
+        ],
+    },
+    CommandHelp {
+        name: "stale",
+        aliases: &[],
+        usage: "stale [days]",
+        summary: "作成から日数が経つのに手付かずのタスクを古い順に表示",
+        details: &[
+            "作成から days 日以上 (既定は14日) 経ち、作業記録が一度もない着手可能なタスクを表示します",
+            "予定に入れる・後回しにする・削除するなど、忘れられたタスクの整理に使います",
+        ],
+    },
+    CommandHelp {
+        name: "pin",
+        aliases: &[],
+        usage: "pin [<tid> | none]",
+        summary: "今日の最優先のタスクを固定",
+        details: &[
+            "固定したタスクはスケジューラの順序に関わらず todo の先頭に ⭐ 付きで表示され、next でも最初に返されます",
+            "完了・削除すると固定は外れます。none で解除、引数なしで固定中のタスクを表示します",
+        ],
+    },
+    CommandHelp {
+        name: "next",
+        aliases: &[],
+        usage: "next",
+        summary: "次にやるタスクを1件表示",
+        details: &["固定 (pin) したタスクが未完了ならそれを、なければ今日の todo の先頭のタスクを表示します"],
+    },
+    CommandHelp {
+        name: "schedule",
+        aliases: &["sc"],
+        usage: "schedule",
+        summary: "タスクをスケジュール",
+        details: &[],
+    },
+    CommandHelp {
+        name: "todo",
+        aliases: &["t"],
+        usage: "todo [--date <date>] [--json] | todo done <n>",
+        summary: "今日のTODOを表示",
+        details: &[
+            "--date/-d で指定日の予定を表示 (date は YYYY-MM-DD, MM/DD, today, tomorrow, yesterday, mon〜sun)",
+            "--json で表示の代わりに JSON を出力します (時間は分単位)",
+            "todo done <n> で、直前の todo で #n と表示したタスクを完了します",
+            "起動引数でも実行可能: lazy-scheduler todo --date tomorrow",
+        ],
+    },
+    CommandHelp {
+        name: "now",
+        aliases: &[],
+        usage: "now [set <date> [HH:MM] [--fixed] | clear]",
+        summary: "セッション全体の現在時刻を上書き",
+        details: &[
+            "now set <date> [HH:MM]           以降のコマンドの現在時刻をずらす (時計は進む)",
+            "now set <date> [HH:MM] --fixed   現在時刻を固定する",
+            "now clear                        上書きを解除",
+            "コマンド先頭の @<timestamp> はこの設定より優先されます",
+        ],
+    },
+    CommandHelp {
+        name: "help",
+        aliases: &[],
+        usage: "help [command]",
+        summary: "ヘルプを表示",
+        details: &["例: help dl"],
+    },
+    CommandHelp {
+        name: "exit",
+        aliases: &["quit"],
+        usage: "exit",
+        summary: "終了 (Ctrl+D でも可)",
+        details: &[],
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static CommandHelp> {
+    COMMANDS.iter().find(|c| c.matches(name))
+}
+
+/// 打ち間違いと思われるコマンド名に近い名前・別名を返す (編集距離 2 以下、かつ入力の長さ未満)
+pub fn suggest(name: &str) -> Option<&'static str> {
+    let threshold = 2.min(name.chars().count().saturating_sub(1));
+    COMMANDS
+        .iter()
+        .flat_map(|c| std::iter::once(c.name).chain(c.aliases.iter().copied()))
+        .map(|candidate| (levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// 文字単位の編集距離 (挿入・削除・置換をそれぞれ 1 と数える)
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// 未知のコマンドのエラーメッセージ (近い候補があれば添える)
+pub fn unknown_command_message(name: &str) -> String {
+    match suggest(name) {
+        Some(candidate) => format!("Unknown command: {} (もしかして: {}?)", name, candidate),
+        None => format!("Unknown command: {}", name),
+    }
+}
+
+pub fn print_overview(active: bool) {
+    let commands = if active {
+        vec!["add", "list", "stop", "done", "focus", "comp", "drop", "est", "help", "exit"]
+    } else {
+        vec!["add", "list", "start", "comp", "drop", "est", "schedule", "help"]
+    };
+    println!("Available commands: {}", commands.join(", "));
+    println!("Usage:");
+    for command in COMMANDS {
+        println!("  {} - {}", command.usage, theme::render(command.summary));
+    }
+    println!("詳細は help <command> で表示します");
+}
+
+pub fn print_detail(command: &CommandHelp) {
+    println!("{} - {}", command.usage, theme::render(command.summary));
+    if !command.aliases.is_empty() {
+        println!("  別名: {}", command.aliases.join(", "));
+    }
+    for line in command.details {
+        println!("  {}", theme::render(line));
+    }
+}
+
+#[test]
+fn test_find_by_alias() {
+    assert_eq!(find("dl").unwrap().name, "deadline");
+    assert_eq!(find("deadline").unwrap().name, "deadline");
+    assert!(find("nope").is_none());
+    // 名前と別名に重複がないこと
+    let mut names = COMMANDS.iter().flat_map(|c| std::iter::once(c.name).chain(c.aliases.iter().copied())).collect::<Vec<_>>();
+    let len = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), len);
+}
+
+#[test]
+fn test_suggest() {
+    assert_eq!(levenshtein("lsit", "list"), 2);
+    assert_eq!(levenshtein("", "add"), 3);
+    assert_eq!(suggest("lst"), Some("list"));
+    assert_eq!(suggest("schedul"), Some("schedule"));
+    assert_eq!(suggest("deadlien"), Some("deadline"));
+    // 短すぎる入力や遠い入力には候補を出さない
+    assert_eq!(suggest("q"), None);
+    assert_eq!(suggest("frobnicate"), None);
+}
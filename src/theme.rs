@@ -0,0 +1,132 @@
+//! 出力の見た目 (絵文字・色を使うか)。
+//! 絵文字を表示できない端末やログ向けに、`--ascii` または環境変数 `NO_EMOJI` で ASCII の記号に置き換える。
+//! 置き換えるのはメッセージの記号 (`symbol`) と固定の文言 (`render`) だけで、タスク名などの入力や JSON の出力はそのまま出す
+use crate::core::config::CategoryColor;
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// カテゴリの色に対応する ANSI エスケープの色番号
+fn ansi_code(color: CategoryColor) -> u8 {
+    match color {
+        CategoryColor::Red => 31,
+        CategoryColor::Green => 32,
+        CategoryColor::Yellow => 33,
+        CategoryColor::Blue => 34,
+        CategoryColor::Magenta => 35,
+        CategoryColor::Cyan => 36,
+    }
+}
+
+/// 絵文字 → ASCII の記号。ここにない絵文字は `*` にする
+const SYMBOLS: &[(char, &str)] = &[
+    // タスクの状態
+    ('⬜', "[ ]"),
+    ('📝', "[.]"),
+    ('⌛', "[~]"),
+    ('⏳', "[~]"),
+    ('✅', "[x]"),
+    ('❌', "[!]"),
+    ('⚠', "[!]"),
+    ('⭐', "[*]"),
+    ('📌', "[@]"),
+    ('⏸', "[-]"),
+    ('🔥', "[!!]"),
+    ('🎯', "[=]"),
+    ('📈', "[+]"),
+    ('📉', "[-]"),
+    ('ℹ', "[i]"),
+    // 記号・スパークライン
+    ('→', "->"),
+    ('↩', "<-"),
+    ('≤', "<="),
+    ('👉', "->"),
+    ('▁', "1"),
+    ('▂', "2"),
+    ('▃', "3"),
+    ('▄', "4"),
+    ('▅', "5"),
+    ('▆', "6"),
+    ('▇', "7"),
+    ('█', "8"),
+];
+
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// 色付きで出力するか (端末への出力で、`NO_COLOR` も ASCII モードも指定されていないとき)
+pub fn set_color(color: bool) {
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+/// 色付きの出力が有効なら、文字列を ANSI エスケープで色付けする
+pub fn paint(text: &str, color: CategoryColor) -> String {
+    if COLOR.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", ansi_code(color), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// メッセージの先頭などに付ける記号。ASCII モードなら対応する ASCII の記号にする
+pub fn symbol(emoji: &'static str) -> &'static str {
+    if is_ascii() { ascii_symbol(emoji) } else { emoji }
+}
+
+fn ascii_symbol(emoji: &str) -> &'static str {
+    match emoji.chars().next().and_then(|c| SYMBOLS.iter().find(|(e, _)| *e == c)) {
+        Some((_, symbol)) => symbol,
+        None => "*",
+    }
+}
+
+/// ヘルプやスパークラインなど、固定の文言に含まれる絵文字を ASCII モードなら記号に置き換える (入力された文字列には使わない)
+pub fn render(text: &str) -> Cow<'_, str> {
+    if is_ascii() { Cow::Owned(to_ascii(text)) } else { Cow::Borrowed(text) }
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2190..=0x21FF | 0x2300..=0x23FF | 0x2580..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FFFF)
+}
+
+fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match SYMBOLS.iter().find(|(emoji, _)| *emoji == c) {
+            Some((_, symbol)) => out.push_str(symbol),
+            // 異体字セレクタ (⚠️ の後半など) は落とす
+            None if c == '\u{FE0F}' => {}
+            None if is_emoji(c) => out.push('*'),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+#[test]
+fn test_to_ascii() {
+    assert_eq!(to_ascii("⬜ #1d48 資料"), "[ ] #1d48 資料");
+    assert_eq!(to_ascii("⌛ ブロッキング / ✅ 完了 / ❌ 削除"), "[~] ブロッキング / [x] 完了 / [!] 削除");
+    assert_eq!(to_ascii("⚠️ 期限切れ"), "[!] 期限切れ");
+    assert_eq!(to_ascii("🧠 LazyScheduler"), "* LazyScheduler");
+    assert_eq!(to_ascii("█▄▁"), "841");
+    assert!(to_ascii("🔥🦥📊⏭️🗓️").is_ascii());
+}
+
+#[test]
+fn test_ascii_symbol() {
+    assert_eq!(ascii_symbol("⚠️"), "[!]");
+    assert_eq!(ascii_symbol("⬜"), "[ ]");
+    // 着手可能 (⬜) と未見積 (📝) は見分けられるようにする
+    assert_ne!(ascii_symbol("📝"), ascii_symbol("⬜"));
+    assert_eq!(ascii_symbol("🦥"), "*");
+}